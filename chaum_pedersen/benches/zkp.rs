@@ -0,0 +1,108 @@
+//! Benchmarks for the core arithmetic over the default 1024-bit group.
+//!
+//! Inputs come from a fixed seed so runs are comparable. Each benchmark name carries the
+//! number of `modpow` calls the operation makes, since that is what dominates its cost.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use chaum_pedersen::{modexp, BatchProof, Scalar, Transcript, ZkpBuilder, ZkpGroup, ZKP};
+
+fn bench_zkp(criterion: &mut Criterion) {
+    let zkp = ZKP::new();
+    let mut rng = ChaCha20Rng::seed_from_u64(0x5eed);
+
+    let mut scalar = || {
+        Scalar::new(&zkp, ZKP::generate_random_number_below_with(&mut rng, &zkp.q)).unwrap()
+    };
+    let (x, k, c) = (scalar(), scalar(), scalar());
+    let (y1, y2) = zkp.compute_pair(&x);
+    let (r1, r2) = zkp.compute_pair(&k);
+    let s = zkp.solve(&k, &c, &x);
+
+    let mut group = criterion.benchmark_group("modp1024");
+
+    group.bench_function("compute_pair (2 modpow)", |b| {
+        b.iter(|| zkp.compute_pair(black_box(&k)))
+    });
+
+    let precomputed = ZkpBuilder::default().precompute(true).build().unwrap();
+    group.bench_function("compute_pair (precomputed)", |b| {
+        b.iter(|| precomputed.compute_pair(black_box(&k)))
+    });
+
+    group.bench_function("solve (0 modpow)", |b| {
+        b.iter(|| zkp.solve(black_box(&k), black_box(&c), black_box(&x)))
+    });
+
+    // Four exponentiations plus the two reductions by `modpow(1, p)`
+    group.bench_function("verify (6 modpow)", |b| {
+        b.iter(|| {
+            zkp.verify(
+                black_box(&r1),
+                black_box(&r2),
+                black_box(&y1),
+                black_box(&y2),
+                black_box(&c),
+                black_box(&s),
+            )
+        })
+    });
+
+    group.finish();
+}
+
+/// `BigUint::modpow` against `modexp::sliding_window` on the exponentiations `verify`
+/// makes: a 1024-bit base to a 160-bit exponent.
+fn bench_modexp(criterion: &mut Criterion) {
+    let zkp = ZKP::new();
+    let mut rng = ChaCha20Rng::seed_from_u64(0x5eed);
+    let base = ZKP::generate_random_number_below_with(&mut rng, &zkp.p);
+    let exp = ZKP::generate_random_number_below_with(&mut rng, &zkp.q);
+
+    let mut group = criterion.benchmark_group("modexp");
+
+    group.bench_function("BigUint::modpow", |b| {
+        b.iter(|| black_box(&base).modpow(black_box(&exp), &zkp.p))
+    });
+
+    group.bench_function("sliding_window", |b| {
+        b.iter(|| modexp::sliding_window(black_box(&base), black_box(&exp), &zkp.p))
+    });
+
+    group.finish();
+}
+
+/// `verify_batch` on 100 honest proofs against verifying each of them on its own.
+fn bench_batch(criterion: &mut Criterion) {
+    let zkp = ZKP::new();
+    let mut rng = ChaCha20Rng::seed_from_u64(0x5eed);
+    let proofs: Vec<BatchProof> = (0..100)
+        .map(|_| {
+            let mut scalar = || ZKP::generate_random_number_below_with(&mut rng, &zkp.q);
+            let (x, k, c) = (scalar(), scalar(), scalar());
+            let Transcript { r1, r2, y1, y2, c, s } = zkp.honest_transcript(&x, &k, &c);
+            (r1, r2, y1, y2, c, s)
+        })
+        .collect();
+
+    let mut group = criterion.benchmark_group("batch");
+
+    group.bench_function("verify_batch (100 proofs)", |b| {
+        b.iter(|| zkp.verify_batch_with(&mut rng, black_box(&proofs)))
+    });
+
+    group.bench_function("verify (100 proofs)", |b| {
+        b.iter(|| {
+            black_box(&proofs)
+                .iter()
+                .all(|(r1, r2, y1, y2, c, s)| ZkpGroup::verify(&zkp, r1, r2, y1, y2, c, s))
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_zkp, bench_modexp, bench_batch);
+criterion_main!(benches);
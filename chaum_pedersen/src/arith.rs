@@ -0,0 +1,214 @@
+//! The modular arithmetic of one proof, on bare `BigUint`s. Nothing here needs `std` or
+//! an RNG, so a prover on an embedded target can build with `--no-default-features` and
+//! bring its own nonces.
+//!
+//! With the `trace` feature, each exponentiation in `compute_pair` and `verify_each` runs
+//! inside a `modpow` span at trace level, whose `op` field names it and whose `elapsed_ns`
+//! field records how long it took.
+
+use num_bigint::BigUint;
+use subtle::ConstantTimeEq;
+
+use crate::{encoding, modexp::modpow};
+
+/// Returns `(alpha^exp, beta^exp) mod p`.
+pub fn compute_pair(
+    p: &BigUint,
+    alpha: &BigUint,
+    beta: &BigUint,
+    exp: &BigUint,
+) -> (BigUint, BigUint) {
+    (
+        timed("alpha^exp", || alpha.modpow(exp, p)),
+        timed("beta^exp", || beta.modpow(exp, p)),
+    )
+}
+
+/// Returns the response `s = k - c * x mod q`, in `[0, q)` whatever the size of the inputs.
+pub fn solve(q: &BigUint, k: &BigUint, c: &BigUint, x: &BigUint) -> BigUint {
+    // Both terms are reduced first, so `k + q - cx` cannot underflow and stays below 2q
+    let k = k % q;
+    let cx = (c * x) % q;
+    (k + q - cx) % q
+}
+
+/// Checks `r1 = alpha^s * y1^c` and `r2 = beta^s * y2^c (mod p)`, in constant time.
+#[allow(clippy::too_many_arguments)]
+pub fn verify(
+    p: &BigUint,
+    alpha: &BigUint,
+    beta: &BigUint,
+    r1: &BigUint,
+    r2: &BigUint,
+    y1: &BigUint,
+    y2: &BigUint,
+    c: &BigUint,
+    s: &BigUint,
+) -> bool {
+    let (cond1, cond2) = verify_each(p, alpha, beta, r1, r2, y1, y2, c, s);
+    cond1 & cond2
+}
+
+/// The two checks of `verify`, `(alpha side, beta side)`. Both are always computed.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_each(
+    p: &BigUint,
+    alpha: &BigUint,
+    beta: &BigUint,
+    r1: &BigUint,
+    r2: &BigUint,
+    y1: &BigUint,
+    y2: &BigUint,
+    c: &BigUint,
+    s: &BigUint,
+) -> (bool, bool) {
+    let alpha_s = timed("alpha^s", || modpow(alpha, s, p));
+    let y1_c = timed("y1^c", || modpow(y1, c, p));
+    let beta_s = timed("beta^s", || modpow(beta, s, p));
+    let y2_c = timed("y2^c", || modpow(y2, c, p));
+    verify_parts(p, r1, r2, (&y1_c, &y2_c), (&alpha_s, &beta_s))
+}
+
+/// The checks of `verify_each` once its four exponentiations are done: compares `r1` with
+/// `alpha^s * y1^c` and `r2` with `beta^s * y2^c (mod p)`, given `(y1^c, y2^c)` and
+/// `(alpha^s, beta^s)`.
+pub fn verify_parts(
+    p: &BigUint,
+    r1: &BigUint,
+    r2: &BigUint,
+    (y1_c, y2_c): (&BigUint, &BigUint),
+    (alpha_s, beta_s): (&BigUint, &BigUint),
+) -> (bool, bool) {
+    let cond1 = ct_eq(p, r1, &((alpha_s * y1_c) % p));
+    let cond2 = ct_eq(p, r2, &((beta_s * y2_c) % p));
+    (cond1, cond2)
+}
+
+/// Runs `pow` inside a `modpow` span named by `op`, recording its duration as `elapsed_ns`.
+#[cfg(feature = "trace")]
+pub(crate) fn timed(op: &'static str, pow: impl FnOnce() -> BigUint) -> BigUint {
+    let span = tracing::trace_span!("modpow", op, elapsed_ns = tracing::field::Empty);
+    let _entered = span.enter();
+    let start = std::time::Instant::now();
+    let result = pow();
+    span.record("elapsed_ns", start.elapsed().as_nanos() as u64);
+    result
+}
+
+#[cfg(not(feature = "trace"))]
+#[inline(always)]
+pub(crate) fn timed(_op: &'static str, pow: impl FnOnce() -> BigUint) -> BigUint {
+    pow()
+}
+
+/// Compares two values without an early exit.
+///
+/// `BigUint`'s `==` stops at the first differing limb, so how long a failed check takes
+/// would reveal how much of the expected commitment an attacker already matched. Both
+/// sides are padded to the width of `p` (or the wider value) and compared with `subtle`
+/// instead, and callers combine results with `&` rather than `&&` so the second check
+/// always runs too.
+pub fn ct_eq(p: &BigUint, a: &BigUint, b: &BigUint) -> bool {
+    let width = [p.bits(), a.bits(), b.bits()]
+        .into_iter()
+        .max()
+        .unwrap()
+        .div_ceil(8) as usize;
+    let a = encoding::to_fixed_bytes(a, width);
+    let b = encoding::to_fixed_bytes(b, width);
+    a.ct_eq(&b).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toy_group_proof_verifies() {
+        // alpha = 4 and beta = 9 both have order 11 mod 23
+        let (p, q) = (BigUint::from(23u32), BigUint::from(11u32));
+        let (alpha, beta) = (BigUint::from(4u32), BigUint::from(9u32));
+        let (x, k, c) = (BigUint::from(6u32), BigUint::from(7u32), BigUint::from(4u32));
+
+        let (y1, y2) = compute_pair(&p, &alpha, &beta, &x);
+        let (r1, r2) = compute_pair(&p, &alpha, &beta, &k);
+        let s = solve(&q, &k, &c, &x);
+        assert_eq!(s, BigUint::from(5u32));
+        assert!(verify(&p, &alpha, &beta, &r1, &r2, &y1, &y2, &c, &s));
+        assert!(!verify(&p, &alpha, &beta, &r1, &r2, &y1, &y2, &c, &(s + 1u32)));
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn modpow_spans_record_their_duration() {
+        use std::sync::{Arc, Mutex};
+
+        use tracing::{
+            field::{Field, Visit},
+            span::{Attributes, Id, Record},
+            Subscriber,
+        };
+        use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+        /// Each `modpow` span's `op`, and its `elapsed_ns` once recorded.
+        type Recorded = Vec<(String, Option<u64>)>;
+
+        #[derive(Clone, Default)]
+        struct Spans(Arc<Mutex<Recorded>>);
+
+        #[derive(Default)]
+        struct Fields(Option<String>, Option<u64>);
+
+        impl Visit for Fields {
+            fn record_str(&mut self, field: &Field, value: &str) {
+                if field.name() == "op" {
+                    self.0 = Some(value.to_string());
+                }
+            }
+            fn record_u64(&mut self, field: &Field, value: u64) {
+                if field.name() == "elapsed_ns" {
+                    self.1 = Some(value);
+                }
+            }
+            fn record_debug(&mut self, _: &Field, _: &dyn core::fmt::Debug) {}
+        }
+
+        impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Spans {
+            fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+                let mut fields = Fields::default();
+                attrs.record(&mut fields);
+                let mut spans = self.0.lock().unwrap();
+                ctx.span(id).unwrap().extensions_mut().insert(spans.len());
+                spans.push((fields.0.unwrap_or_default(), None));
+            }
+            fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+                let mut fields = Fields::default();
+                values.record(&mut fields);
+                let index = *ctx.span(id).unwrap().extensions().get::<usize>().unwrap();
+                self.0.lock().unwrap()[index].1 = fields.1;
+            }
+        }
+
+        let spans = Spans::default();
+        let subscriber = tracing_subscriber::registry().with(spans.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let (p, q) = (BigUint::from(23u32), BigUint::from(11u32));
+            let (alpha, beta) = (BigUint::from(4u32), BigUint::from(9u32));
+            let (x, k, c) = (BigUint::from(6u32), BigUint::from(7u32), BigUint::from(4u32));
+            let (y1, y2) = compute_pair(&p, &alpha, &beta, &x);
+            let (r1, r2) = compute_pair(&p, &alpha, &beta, &k);
+            let s = solve(&q, &k, &c, &x);
+            assert!(verify(&p, &alpha, &beta, &r1, &r2, &y1, &y2, &c, &s));
+        });
+
+        let spans = spans.0.lock().unwrap();
+        let ops: Vec<&str> = spans.iter().map(|(op, _)| op.as_str()).collect();
+        assert_eq!(
+            ops,
+            ["alpha^exp", "beta^exp", "alpha^exp", "beta^exp", "alpha^s", "y1^c", "beta^s", "y2^c"]
+        );
+        for (op, elapsed) in spans.iter() {
+            assert!(elapsed.is_some_and(|ns| ns > 0), "{} recorded {:?}", op, elapsed);
+        }
+    }
+}
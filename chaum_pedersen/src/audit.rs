@@ -0,0 +1,129 @@
+//! A record of every answered challenge, for auditing logins after the fact.
+//!
+//! An `AuditEntry` holds who answered which challenge, when, and whether it verified,
+//! with the transcript reduced to a SHA-256 digest so the log never holds `s` or the
+//! commitments themselves.
+
+use std::{fmt, io};
+#[cfg(feature = "serde")]
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Mutex,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{service::PendingChallenge, ZkpGroup};
+
+/// How an answered challenge turned out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AuditOutcome {
+    /// The proof verified and a session was opened.
+    Success,
+    /// The answer was refused, with the `AuthError` message saying why.
+    Failure(String),
+}
+
+/// One answered challenge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuditEntry {
+    pub user: String,
+    pub auth_id: String,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub outcome: AuditOutcome,
+    /// Hex SHA-256 of the transcript; see `transcript_hash`.
+    pub transcript_hash: String,
+}
+
+/// SHA-256 of `r1 || r2 || c || s` in the group's wire encodings, as lowercase hex. Equal
+/// transcripts give equal hashes, so a transcript kept elsewhere can be matched to its
+/// entry without the log holding it.
+pub fn transcript_hash<G: ZkpGroup>(
+    group: &G,
+    pending: &PendingChallenge<G>,
+    s: &G::Scalar,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(group.element_to_bytes(&pending.r1));
+    hasher.update(group.element_to_bytes(&pending.r2));
+    hasher.update(group.scalar_to_bytes(&pending.c));
+    hasher.update(group.scalar_to_bytes(s));
+    hex::encode(hasher.finalize())
+}
+
+/// Where `AuditEntry`s go.
+pub trait AuditLog: fmt::Debug + Send + Sync {
+    fn record(&self, entry: AuditEntry) -> io::Result<()>;
+}
+
+/// Appends each entry to a file as one line of JSON.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub struct JsonlAuditLog {
+    file: Mutex<File>,
+}
+
+#[cfg(feature = "serde")]
+impl JsonlAuditLog {
+    /// Opens `path` for appending, creating it if needed. Existing entries are kept.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonlAuditLog {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl AuditLog for JsonlAuditLog {
+    fn record(&self, entry: AuditEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        // One write per line, under the lock, so concurrent entries do not interleave
+        self.file.lock().unwrap().write_all(line.as_bytes())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jsonl_log_appends_one_entry_per_line() {
+        let path = std::env::temp_dir().join(format!("audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let entry = |outcome| AuditEntry {
+            user: "alice".to_string(),
+            auth_id: "a1".to_string(),
+            timestamp: 1_700_000_000,
+            outcome,
+            transcript_hash: "00".repeat(32),
+        };
+
+        let log = JsonlAuditLog::open(&path).unwrap();
+        log.record(entry(AuditOutcome::Success)).unwrap();
+        drop(log);
+        // Reopening appends rather than truncating
+        let log = JsonlAuditLog::open(&path).unwrap();
+        log.record(entry(AuditOutcome::Failure("wrong".to_string()))).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let entries: Vec<AuditEntry> =
+            text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(
+            entries,
+            [
+                entry(AuditOutcome::Success),
+                entry(AuditOutcome::Failure("wrong".to_string()))
+            ]
+        );
+        assert!(text.starts_with(r#"{"user":"alice","auth_id":"a1","timestamp":1700000000,"#));
+    }
+}
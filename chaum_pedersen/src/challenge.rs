@@ -0,0 +1,55 @@
+//! Hashes for deriving the challenge of a non-interactive (Fiat-Shamir) proof. The prover
+//! and verifier must use the same one, so it is a parameter rather than fixed to SHA-256.
+
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256, Sha512};
+
+/// Turns the public values of a proof into its challenge `c`.
+pub trait ChallengeHasher {
+    /// Hashes `inputs` in order and returns the digest reduced mod `q`. Each input is
+    /// length-prefixed, so moving bytes from one input to the next changes the result.
+    fn hash_to_scalar(&self, inputs: &[&[u8]], q: &BigUint) -> BigUint;
+}
+
+/// `SHA-256(len_1 || input_1 || ...) mod q`, with each length as 8 big-endian bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha256Hasher;
+
+/// As `Sha256Hasher`, with SHA-512.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha512Hasher;
+
+fn hash_with<D: Digest>(inputs: &[&[u8]], q: &BigUint) -> BigUint {
+    let mut hasher = D::new();
+    for input in inputs {
+        hasher.update((input.len() as u64).to_be_bytes());
+        hasher.update(input);
+    }
+    BigUint::from_bytes_be(&hasher.finalize()) % q
+}
+
+impl ChallengeHasher for Sha256Hasher {
+    fn hash_to_scalar(&self, inputs: &[&[u8]], q: &BigUint) -> BigUint {
+        hash_with::<Sha256>(inputs, q)
+    }
+}
+
+impl ChallengeHasher for Sha512Hasher {
+    fn hash_to_scalar(&self, inputs: &[&[u8]], q: &BigUint) -> BigUint {
+        hash_with::<Sha512>(inputs, q)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inputs_are_length_prefixed_and_reduced() {
+        let q = BigUint::from(1_000_003u32);
+        let c = Sha256Hasher.hash_to_scalar(&[b"ab", b"c"], &q);
+        assert!(c < q);
+        assert_ne!(c, Sha256Hasher.hash_to_scalar(&[b"a", b"bc"], &q));
+        assert_ne!(c, Sha512Hasher.hash_to_scalar(&[b"ab", b"c"], &q));
+    }
+}
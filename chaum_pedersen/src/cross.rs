@@ -0,0 +1,197 @@
+//! Proofs that `log_alpha(y1) mod p1` equals `log_beta(y2) mod p2` for two different
+//! moduli, generalizing `ZKP`, where alpha and beta share one `p`.
+//!
+//! Both generators must have the same prime order `q`, which divides `p1 - 1` and
+//! `p2 - 1`. The response `s = k - c * x` is then taken mod `q` and checked in each group
+//! separately. With different orders there is no single modulus to reduce `s` by, so
+//! such pairs are refused rather than proved over the integers.
+
+use num_bigint::BigUint;
+
+use crate::{arith, modexp::modpow, ParamError, Proof};
+
+/// Two groups, `<alpha> mod p1` and `<beta> mod p2`, both of order `q`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossGroupZkp {
+    pub p1: BigUint,
+    pub alpha: BigUint,
+    pub p2: BigUint,
+    pub beta: BigUint,
+    pub q: BigUint,
+}
+
+impl CrossGroupZkp {
+    /// Checks that `q` divides both `p1 - 1` and `p2 - 1` and that `alpha` and `beta`
+    /// generate subgroups of order `q`. A bad `beta` is reported as `NotInSubgroup`, as a
+    /// bad `alpha` is.
+    pub fn new(
+        p1: BigUint,
+        alpha: BigUint,
+        p2: BigUint,
+        beta: BigUint,
+        q: BigUint,
+    ) -> Result<Self, ParamError> {
+        let one = BigUint::from(1u32);
+        for (p, g) in [(&p1, &alpha), (&p2, &beta)] {
+            if q.bits() == 0 || *p <= one {
+                return Err(ParamError::InvalidOrder);
+            }
+            if ((p - 1u32) % &q).bits() != 0 {
+                return Err(ParamError::QNotDivisorOfPMinus1);
+            }
+            if *g <= one || g >= p || g.modpow(&q, p) != one {
+                return Err(ParamError::NotInSubgroup);
+            }
+        }
+        Ok(CrossGroupZkp {
+            p1,
+            alpha,
+            p2,
+            beta,
+            q,
+        })
+    }
+
+    /// Returns `(alpha^exp mod p1, beta^exp mod p2)`: the public keys for a secret, or
+    /// the commitments for a nonce.
+    pub fn compute_pair(&self, exp: &BigUint) -> (BigUint, BigUint) {
+        (
+            self.alpha.modpow(exp, &self.p1),
+            self.beta.modpow(exp, &self.p2),
+        )
+    }
+
+    /// Proves that `compute_pair(x)` share the exponent `x`, with nonce `k` and challenge
+    /// `c`. The challenge is reduced mod `q`, the order both sides have, and the proof
+    /// carries the reduced value.
+    pub fn prove(&self, x: &BigUint, k: &BigUint, c: &BigUint) -> Proof {
+        let (r1, r2) = self.compute_pair(k);
+        let c = c % &self.q;
+        let s = arith::solve(&self.q, k, &c, x);
+        Proof { r1, r2, c, s }
+    }
+
+    /// Checks `r1 = alpha^s * y1^c mod p1` and `r2 = beta^s * y2^c mod p2`.
+    ///
+    /// `c` and `s` must be below `q`, the commitments must be elements of their groups and
+    /// the keys must lie in the order-q subgroups: a `y` with a component of another order
+    /// would make the two sides agree on `x` mod `q` only by chance, not by proof.
+    pub fn verify(&self, y1: &BigUint, y2: &BigUint, proof: &Proof) -> bool {
+        let Proof { r1, r2, c, s } = proof;
+        if *c >= self.q || *s >= self.q {
+            return false;
+        }
+        let one = BigUint::from(1u32);
+        for (p, r, y) in [(&self.p1, r1, y1), (&self.p2, r2, y2)] {
+            if r.bits() == 0 || r >= p || y >= p || y.modpow(&self.q, p) != one {
+                return false;
+            }
+        }
+        let expected1 = (modpow(&self.alpha, s, &self.p1) * modpow(y1, c, &self.p1)) % &self.p1;
+        let expected2 = (modpow(&self.beta, s, &self.p2) * modpow(y2, c, &self.p2)) % &self.p2;
+        arith::ct_eq(&self.p1, r1, &expected1) & arith::ct_eq(&self.p2, r2, &expected2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `q = 1019` in the safe-prime group mod `p1 = 2q + 1 = 2039` and in the group mod
+    /// `p2 = 32q + 1 = 32609`. No two distinct safe primes share a `q`, so `p2` is not one.
+    fn groups() -> CrossGroupZkp {
+        let q = BigUint::from(1019u32);
+        let p2 = BigUint::from(32609u32);
+        let beta = BigUint::from(2u32).modpow(&((&p2 - 1u32) / &q), &p2);
+        CrossGroupZkp::new(BigUint::from(2039u32), BigUint::from(4u32), p2, beta, q).unwrap()
+    }
+
+    #[test]
+    fn cross_group_proofs_verify() {
+        let zkp = groups();
+        assert_eq!(zkp.beta, BigUint::from(3297u32));
+        for (x, k, c) in [
+            (6u32, 7u32, 4u32),
+            (1, 1018, 1018),
+            (500, 3, 0),
+            (1018, 0, 1),
+        ] {
+            let (x, k, c) = (BigUint::from(x), BigUint::from(k), BigUint::from(c));
+            let (y1, y2) = zkp.compute_pair(&x);
+            let proof = zkp.prove(&x, &k, &c);
+            assert!(
+                zkp.verify(&y1, &y2, &proof),
+                "x = {}, k = {}, c = {}",
+                x,
+                k,
+                c
+            );
+
+            // Keys for a different exponent on the p2 side; c = 0 does not bind the keys
+            let (_, other_y2) = zkp.compute_pair(&(&x + 1u32));
+            assert_eq!(zkp.verify(&y1, &other_y2, &proof), c.bits() == 0);
+        }
+
+        // A challenge of q or more is reduced before it goes into the proof
+        let (x, k) = (BigUint::from(6u32), BigUint::from(7u32));
+        let (y1, y2) = zkp.compute_pair(&x);
+        let proof = zkp.prove(&x, &k, &(&zkp.q + 4u32));
+        assert_eq!(proof, zkp.prove(&x, &k, &BigUint::from(4u32)));
+        assert!(zkp.verify(&y1, &y2, &proof));
+    }
+
+    #[test]
+    fn verify_rejects_out_of_range_values() {
+        let zkp = groups();
+        let (x, k, c) = (
+            BigUint::from(6u32),
+            BigUint::from(7u32),
+            BigUint::from(4u32),
+        );
+        let (y1, y2) = zkp.compute_pair(&x);
+        let proof = zkp.prove(&x, &k, &c);
+
+        // c = q makes y^c = 1 on both sides, so any s with r = alpha^s, beta^s would pass
+        let s = BigUint::from(5u32);
+        let (r1, r2) = zkp.compute_pair(&s);
+        let forged = Proof {
+            r1,
+            r2,
+            c: zkp.q.clone(),
+            s,
+        };
+        assert!(!zkp.verify(&y1, &y2, &forged));
+
+        let wide_s = Proof {
+            s: &proof.s + &zkp.q,
+            ..proof.clone()
+        };
+        assert!(!zkp.verify(&y1, &y2, &wide_s));
+        let zero_r = Proof {
+            r1: BigUint::from(0u32),
+            ..proof.clone()
+        };
+        assert!(!zkp.verify(&y1, &y2, &zero_r));
+        // p2 - 1 has order 2, outside the order-q subgroup
+        assert!(!zkp.verify(&y1, &(&zkp.p2 - 1u32), &proof));
+    }
+
+    #[test]
+    fn new_checks_both_groups() {
+        let zkp = groups();
+        let with = |p2: u32, beta: u32| {
+            CrossGroupZkp::new(
+                zkp.p1.clone(),
+                zkp.alpha.clone(),
+                BigUint::from(p2),
+                BigUint::from(beta),
+                zkp.q.clone(),
+            )
+        };
+        // 1019 does not divide 32610
+        assert_eq!(with(32611, 3297), Err(ParamError::QNotDivisorOfPMinus1));
+        // 2 generates far more than the order-q subgroup mod p2
+        assert_eq!(with(32609, 2), Err(ParamError::NotInSubgroup));
+        assert_eq!(with(32609, 3297), Ok(zkp));
+    }
+}
@@ -0,0 +1,239 @@
+//! Chaum-Pedersen over the Ristretto255 prime-order group.
+//!
+//! Mirrors the `ZKP` API, with points in place of residues mod p and `Scalar`s in place of
+//! exponents mod q. Every scalar is reduced mod the group order
+//! `l = 2^252 + 27742317777372353535851937790883648493`, so `solve` and `verify` work in
+//! that field rather than mod q.
+
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT,
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::Identity,
+};
+use rand::{CryptoRng, RngCore};
+use sha2::Sha512;
+
+use crate::ZkpGroup;
+
+/// Domain separator hashed to obtain `beta`, so nobody knows `log_alpha(beta)`.
+const BETA_DOMAIN: &[u8] = b"Chaum-Pedersen-Protocol/ristretto255/beta";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcZkp {
+    pub alpha: RistrettoPoint,
+    pub beta: RistrettoPoint,
+}
+
+impl Default for EcZkp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EcZkp {
+    /// Uses the Ristretto basepoint as `alpha` and a hash-derived point as `beta`.
+    pub fn new() -> Self {
+        EcZkp {
+            alpha: RISTRETTO_BASEPOINT_POINT,
+            beta: RistrettoPoint::hash_from_bytes::<Sha512>(BETA_DOMAIN),
+        }
+    }
+
+    pub fn compute_pair(&self, exp: &Scalar) -> (RistrettoPoint, RistrettoPoint) {
+        (self.alpha * exp, self.beta * exp)
+    }
+
+    pub fn solve(&self, k: &Scalar, c: &Scalar, x: &Scalar) -> Scalar {
+        k - c * x
+    }
+
+    pub fn verify(
+        &self,
+        r1: &RistrettoPoint,
+        r2: &RistrettoPoint,
+        y1: &RistrettoPoint,
+        y2: &RistrettoPoint,
+        c: &Scalar,
+        s: &Scalar,
+    ) -> bool {
+        let cond1 = *r1 == self.alpha * s + y1 * c;
+        let cond2 = *r2 == self.beta * s + y2 * c;
+
+        cond1 && cond2
+    }
+
+    pub fn generate_random_scalar() -> Scalar {
+        Self::generate_random_scalar_with(&mut rand::thread_rng())
+    }
+
+    /// Like `generate_random_scalar`, but drawing from a caller-supplied CSPRNG.
+    pub fn generate_random_scalar_with<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+        Scalar::random(rng)
+    }
+}
+
+impl ZkpGroup for EcZkp {
+    type Scalar = Scalar;
+    type Element = RistrettoPoint;
+
+    fn compute_pair(&self, exp: &Scalar) -> (RistrettoPoint, RistrettoPoint) {
+        EcZkp::compute_pair(self, exp)
+    }
+
+    fn solve(&self, k: &Scalar, c: &Scalar, x: &Scalar) -> Scalar {
+        EcZkp::solve(self, k, c, x)
+    }
+
+    fn verify(
+        &self,
+        r1: &RistrettoPoint,
+        r2: &RistrettoPoint,
+        y1: &RistrettoPoint,
+        y2: &RistrettoPoint,
+        c: &Scalar,
+        s: &Scalar,
+    ) -> bool {
+        EcZkp::verify(self, r1, r2, y1, y2, c, s)
+    }
+
+    fn generate_challenge(&self) -> Scalar {
+        Self::generate_random_scalar()
+    }
+
+    fn generate_short_challenge(&self, bits: u64) -> Scalar {
+        // The group order is just above 2^252
+        if bits >= 252 {
+            return self.generate_challenge();
+        }
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        // Keep the low `bits` bits of the little-endian encoding
+        let full_bytes = (bits / 8) as usize;
+        bytes[full_bytes] &= (1u8 << (bits % 8)) - 1;
+        bytes[full_bytes + 1..].fill(0);
+        Scalar::from_bytes_mod_order(bytes)
+    }
+
+    /// Accepts only the canonical 32-byte little-endian encoding.
+    fn scalar_from_bytes(&self, bytes: &[u8]) -> Option<Scalar> {
+        let bytes: [u8; 32] = bytes.try_into().ok()?;
+        Scalar::from_canonical_bytes(bytes).into()
+    }
+
+    fn scalar_to_bytes(&self, scalar: &Scalar) -> Vec<u8> {
+        scalar.to_bytes().to_vec()
+    }
+
+    /// Accepts only a valid compressed Ristretto encoding.
+    fn element_from_bytes(&self, bytes: &[u8]) -> Option<RistrettoPoint> {
+        CompressedRistretto::from_slice(bytes).ok()?.decompress()
+    }
+
+    fn element_to_bytes(&self, element: &RistrettoPoint) -> Vec<u8> {
+        element.compress().to_bytes().to_vec()
+    }
+
+    /// Every decoded Ristretto point is a group element, so there is nothing left to check.
+    fn validate_commitment(&self, _r: &RistrettoPoint) -> bool {
+        true
+    }
+
+    fn is_trivial_public_key(&self, y1: &RistrettoPoint, y2: &RistrettoPoint) -> bool {
+        *y1 == RistrettoPoint::identity() || *y2 == RistrettoPoint::identity()
+    }
+
+    /// Ristretto255 has prime order, so every point is in the subgroup.
+    fn in_subgroup(&self, _y: &RistrettoPoint) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generators_are_independent() {
+        let zkp = EcZkp::new();
+        assert_ne!(zkp.alpha, zkp.beta);
+    }
+
+    #[test]
+    fn end_to_end_register_challenge_verify() {
+        let zkp = EcZkp::new();
+
+        // Registration: the prover publishes (y1, y2) for its secret x
+        let x = EcZkp::generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+
+        // Challenge: commitments from an ephemeral k, then a verifier-chosen c
+        let k = EcZkp::generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let c = EcZkp::generate_random_scalar();
+
+        // Answer and verification
+        let s = zkp.solve(&k, &c, &x);
+        assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &s));
+
+        // A response computed with the wrong secret is rejected
+        let wrong_x = EcZkp::generate_random_scalar();
+        let wrong_s = zkp.solve(&k, &c, &wrong_x);
+        assert!(!zkp.verify(&r1, &r2, &y1, &y2, &c, &wrong_s));
+    }
+
+    /// A prover/verifier written only against the trait, run over both backends.
+    fn honest_run<G: ZkpGroup>(group: &G, x: G::Scalar, k: G::Scalar, c: G::Scalar) -> bool {
+        let (y1, y2) = group.compute_pair(&x);
+        let (r1, r2) = group.compute_pair(&k);
+        let s = group.solve(&k, &c, &x);
+        group.verify(&r1, &r2, &y1, &y2, &c, &s)
+    }
+
+    #[test]
+    fn short_challenges_fit_in_their_bits_and_verify() {
+        let zkp = EcZkp::new();
+        for bits in [64, 100, 128] {
+            let c = zkp.generate_short_challenge(bits);
+            let bytes = c.to_bytes();
+            let (whole, rest) = ((bits / 8) as usize, bits % 8);
+            assert!(bytes[whole] >> rest == 0 && bytes[whole + 1..].iter().all(|&b| b == 0));
+            let (x, k) = (EcZkp::generate_random_scalar(), EcZkp::generate_random_scalar());
+            assert!(honest_run(&zkp, x, k, c));
+        }
+    }
+
+    #[test]
+    fn wire_encoding_round_trips() {
+        let zkp = EcZkp::new();
+        let x = EcZkp::generate_random_scalar();
+        let (y1, _) = zkp.compute_pair(&x);
+
+        let x_bytes = zkp.scalar_to_bytes(&x);
+        assert_eq!(zkp.scalar_from_bytes(&x_bytes), Some(x));
+        let y1_bytes = zkp.element_to_bytes(&y1);
+        assert_eq!(zkp.element_from_bytes(&y1_bytes), Some(y1));
+
+        assert_eq!(zkp.scalar_from_bytes(&[1, 2, 3]), None);
+        assert_eq!(zkp.element_from_bytes(&[0xff; 32]), None);
+    }
+
+    #[test]
+    fn backends_are_swappable_through_zkp_group() {
+        let ec = EcZkp::new();
+        assert!(honest_run(
+            &ec,
+            EcZkp::generate_random_scalar(),
+            EcZkp::generate_random_scalar(),
+            EcZkp::generate_random_scalar(),
+        ));
+
+        let modp = crate::ZKP::new();
+        assert!(honest_run(
+            &modp,
+            crate::ZKP::generate_random_number_below(&modp.q),
+            crate::ZKP::generate_random_number_below(&modp.q),
+            crate::ZKP::generate_random_number_below(&modp.q),
+        ));
+    }
+}
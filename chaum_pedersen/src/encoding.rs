@@ -0,0 +1,213 @@
+//! Text encodings of `BigUint` values, for debugging and for clients that can't easily
+//! produce raw big-endian bytes.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use num_bigint::BigUint;
+
+/// Lowercase hex of the big-endian bytes, always an even number of digits (zero is `"00"`).
+pub fn biguint_to_hex(value: &BigUint) -> String {
+    hex::encode(value.to_bytes_be())
+}
+
+/// Parses big-endian hex, accepting upper or lower case, leading zeros and an odd number of
+/// digits.
+pub fn biguint_from_hex(s: &str) -> Result<BigUint, hex::FromHexError> {
+    let bytes = if s.len() % 2 == 1 {
+        hex::decode(format!("0{}", s))?
+    } else {
+        hex::decode(s)?
+    };
+    Ok(BigUint::from_bytes_be(&bytes))
+}
+
+/// Big-endian bytes left-padded with zeros to `byte_len`, for clients that expect fixed-width
+/// fields. Values that need more than `byte_len` bytes are returned unpadded.
+pub fn to_fixed_bytes(value: &BigUint, byte_len: usize) -> Vec<u8> {
+    let bytes = value.to_bytes_be();
+    let mut out = vec![0u8; byte_len.saturating_sub(bytes.len())];
+    out.extend_from_slice(&bytes);
+    out
+}
+
+/// Bytes in a scalar of the default group, whose q is 160 bits.
+pub const SCALAR_BYTES: usize = 20;
+/// Bytes in an element of the default group, whose p is 1024 bits.
+pub const ELEMENT_BYTES: usize = 128;
+
+/// A value too wide for the fixed-width field it was meant for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverflowError {
+    /// The field's width in bytes.
+    pub width: usize,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value does not fit in {} bytes", self.width)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OverflowError {}
+
+/// Big-endian bytes left-padded with zeros to exactly `N`; unlike `to_fixed_bytes`, a value
+/// needing more than `N` bytes is an error.
+pub fn to_array<const N: usize>(value: &BigUint) -> Result<[u8; N], OverflowError> {
+    let bytes = value.to_bytes_be();
+    if bytes.len() > N {
+        return Err(OverflowError { width: N });
+    }
+    let mut out = [0u8; N];
+    out[N - bytes.len()..].copy_from_slice(&bytes);
+    Ok(out)
+}
+
+/// Parses big-endian bytes that must fit in `N` bytes. Shorter input is taken as if
+/// left-padded, and longer input only if the extra leading bytes are zero.
+pub fn from_array<const N: usize>(bytes: &[u8]) -> Result<BigUint, OverflowError> {
+    let value = BigUint::from_bytes_be(bytes);
+    if value.bits() > 8 * N as u64 {
+        return Err(OverflowError { width: N });
+    }
+    Ok(value)
+}
+
+/// A scalar of the default group as its 20-byte field.
+pub fn scalar_to_array(s: &BigUint) -> Result<[u8; SCALAR_BYTES], OverflowError> {
+    to_array(s)
+}
+
+/// The inverse of `scalar_to_array`. Only the width is checked, not that the value is
+/// below q; wrap it in a `Scalar` for that.
+pub fn scalar_from_array(bytes: &[u8]) -> Result<BigUint, OverflowError> {
+    from_array::<SCALAR_BYTES>(bytes)
+}
+
+/// An element of the default group as its 128-byte field.
+pub fn group_elem_to_array(g: &BigUint) -> Result<[u8; ELEMENT_BYTES], OverflowError> {
+    to_array(g)
+}
+
+/// The inverse of `group_elem_to_array`. As with `scalar_from_array`, only the width is
+/// checked.
+pub fn group_elem_from_array(bytes: &[u8]) -> Result<BigUint, OverflowError> {
+    from_array::<ELEMENT_BYTES>(bytes)
+}
+
+/// Standard (padded) base64 of the big-endian bytes.
+pub fn biguint_to_b64(value: &BigUint) -> String {
+    STANDARD.encode(value.to_bytes_be())
+}
+
+pub fn biguint_from_b64(s: &str) -> Result<BigUint, base64::DecodeError> {
+    Ok(BigUint::from_bytes_be(&STANDARD.decode(s)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ZkpGroup, ZKP};
+
+    #[test]
+    fn hex_round_trips() {
+        let zkp = ZKP::new();
+        for value in [BigUint::from(0u32), BigUint::from(1u32), zkp.p.clone(), zkp.q.clone()] {
+            assert_eq!(biguint_from_hex(&biguint_to_hex(&value)).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn hex_handles_leading_zeros_and_odd_length() {
+        assert_eq!(biguint_to_hex(&BigUint::from(0u32)), "00");
+        assert_eq!(biguint_to_hex(&BigUint::from(0x0102u32)), "0102");
+        assert_eq!(biguint_from_hex("0000ff").unwrap(), BigUint::from(255u32));
+        assert_eq!(biguint_from_hex("1ff").unwrap(), BigUint::from(511u32));
+        assert_eq!(biguint_from_hex("FF").unwrap(), BigUint::from(255u32));
+        assert!(biguint_from_hex("xyz").is_err());
+    }
+
+    #[test]
+    fn fixed_bytes_keep_leading_zeros() {
+        let zkp = ZKP::new();
+        let mut bytes = vec![0u8; 20];
+        bytes[1] = 0x7f;
+        bytes[19] = 0x01;
+        let c = BigUint::from_bytes_be(&bytes);
+        assert_eq!(c.to_bytes_be().len(), 19);
+
+        let fixed = zkp.scalar_to_bytes(&c);
+        assert_eq!(fixed, bytes);
+        assert_eq!(zkp.scalar_from_bytes(&fixed), Some(c.clone()));
+        assert_eq!(zkp.scalar_from_bytes(&c.to_bytes_be()), Some(c));
+
+        assert_eq!(zkp.element_to_bytes(&BigUint::from(1u32)).len(), 128);
+        assert_eq!(to_fixed_bytes(&BigUint::from(0u32), 2), vec![0, 0]);
+        assert_eq!(to_fixed_bytes(&BigUint::from(0x0102u32), 1), vec![1, 2]);
+    }
+
+    #[test]
+    fn arrays_round_trip_at_exact_width() {
+        let zkp = ZKP::new();
+        let q_minus_one = &zkp.q - 1u32;
+        let s = scalar_to_array(&q_minus_one).unwrap();
+        assert_eq!(s.to_vec(), q_minus_one.to_bytes_be());
+        assert_eq!(scalar_from_array(&s).unwrap(), q_minus_one);
+
+        let p_minus_one = &zkp.p - 1u32;
+        let g = group_elem_to_array(&p_minus_one).unwrap();
+        assert_eq!(g.to_vec(), p_minus_one.to_bytes_be());
+        assert_eq!(group_elem_from_array(&g).unwrap(), p_minus_one);
+    }
+
+    #[test]
+    fn arrays_left_pad_small_values() {
+        let s = scalar_to_array(&BigUint::from(0x0102u32)).unwrap();
+        assert_eq!(s[..18], [0u8; 18]);
+        assert_eq!(s[18..], [1, 2]);
+        assert_eq!(scalar_from_array(&s).unwrap(), BigUint::from(0x0102u32));
+        assert_eq!(scalar_from_array(&[1, 2]).unwrap(), BigUint::from(0x0102u32));
+
+        let g = group_elem_to_array(&BigUint::from(0u32)).unwrap();
+        assert_eq!(g, [0u8; ELEMENT_BYTES]);
+        assert_eq!(group_elem_from_array(&g).unwrap(), BigUint::from(0u32));
+    }
+
+    #[test]
+    fn arrays_reject_overflow() {
+        let too_wide = BigUint::from(1u32) << (8 * SCALAR_BYTES);
+        assert_eq!(scalar_to_array(&too_wide), Err(OverflowError { width: SCALAR_BYTES }));
+        assert_eq!(
+            scalar_from_array(&too_wide.to_bytes_be()),
+            Err(OverflowError { width: SCALAR_BYTES })
+        );
+        // Extra leading zeros are not an overflow
+        assert_eq!(scalar_from_array(&[0u8; 32]).unwrap(), BigUint::from(0u32));
+
+        let too_wide = BigUint::from(1u32) << (8 * ELEMENT_BYTES);
+        assert!(group_elem_to_array(&too_wide).is_err());
+        assert!(group_elem_from_array(&too_wide.to_bytes_be()).is_err());
+        assert_eq!(
+            OverflowError { width: ELEMENT_BYTES }.to_string(),
+            "value does not fit in 128 bytes"
+        );
+    }
+
+    #[test]
+    fn b64_round_trips() {
+        let zkp = ZKP::new();
+        for value in [BigUint::from(0u32), BigUint::from(1u32), zkp.p.clone(), zkp.q.clone()] {
+            assert_eq!(biguint_from_b64(&biguint_to_b64(&value)).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn b64_handles_leading_zeros() {
+        assert_eq!(biguint_to_b64(&BigUint::from(0u32)), "AA==");
+        // 0x0000ff encodes to "AAD/" and decodes back to 255
+        assert_eq!(biguint_from_b64("AAD/").unwrap(), BigUint::from(255u32));
+        assert!(biguint_from_b64("not base64!").is_err());
+    }
+}
@@ -0,0 +1,227 @@
+//! Fresh MODP groups of a chosen size, with the `group-gen` feature.
+//!
+//! `ZKP::generate_group_with` finds a prime `q` of `q_bits` bits, a prime `p = k * q + 1`
+//! of `p_bits` bits and a generator of the order-q subgroup mod `p`. Primality is
+//! Miller-Rabin, so this is quick for the small groups tests want and slow for real
+//! sizes; use the built-in groups for anything but tests.
+
+use core::fmt;
+
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::One;
+use rand::{CryptoRng, RngCore};
+
+use crate::ZKP;
+
+/// Miller-Rabin rounds per candidate; a composite passes all of them with probability at
+/// most 4^-40.
+const MILLER_RABIN_ROUNDS: usize = 40;
+
+/// Odd primes tried as divisors before Miller-Rabin, which rule out most candidates.
+const SMALL_PRIMES: [u32; 53] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89,
+    97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181,
+    191, 193, 197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+/// How many candidate `p`s are tried for one `q` before drawing another.
+const P_ATTEMPTS_PER_Q: usize = 1000;
+
+/// How many `q`s are drawn before giving up.
+const Q_ATTEMPTS: usize = 1000;
+
+/// Why `ZKP::generate_group` found no group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenError {
+    /// `q_bits` is below 2, or `p_bits` is not larger than `q_bits`.
+    InvalidSizes { p_bits: usize, q_bits: usize },
+    /// No `p = k * q + 1` of `p_bits` bits turned up prime within the attempt limits,
+    /// which happens when `p_bits` is barely above `q_bits` and few `k` fit.
+    NotFound,
+}
+
+impl fmt::Display for GenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenError::InvalidSizes { p_bits, q_bits } => write!(
+                f,
+                "q needs at least 2 bits and p more than q, not {} and {}",
+                q_bits, p_bits
+            ),
+            GenError::NotFound => write!(f, "no prime p = k * q + 1 found"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GenError {}
+
+/// Returns whether `n` is prime, with an error probability of at most 4^-40 for a
+/// composite `n`.
+pub fn is_probable_prime<R: RngCore + CryptoRng>(rng: &mut R, n: &BigUint) -> bool {
+    let two = BigUint::from(2u32);
+    if *n < two {
+        return false;
+    }
+    for p in [2].into_iter().chain(SMALL_PRIMES) {
+        let p = BigUint::from(p);
+        if *n == p {
+            return true;
+        }
+        if (n % &p).bits() == 0 {
+            return false;
+        }
+    }
+
+    // n - 1 = d * 2^r with d odd
+    let n_minus_1 = n - 1u32;
+    let r = n_minus_1.trailing_zeros().unwrap();
+    let d = &n_minus_1 >> r;
+    'witness: for _ in 0..MILLER_RABIN_ROUNDS {
+        let a = rng.gen_biguint_range(&two, &n_minus_1);
+        let mut x = a.modpow(&d, n);
+        if x.is_one() || x == n_minus_1 {
+            continue;
+        }
+        for _ in 1..r {
+            x = (&x * &x) % n;
+            if x == n_minus_1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// A uniform value of exactly `bits` bits: the top bit is always set.
+fn random_of_bits<R: RngCore + CryptoRng>(rng: &mut R, bits: usize) -> BigUint {
+    let mut n = rng.gen_biguint(bits as u64 - 1);
+    n.set_bit(bits as u64 - 1, true);
+    n
+}
+
+impl ZKP {
+    /// Builds a new group with a `p_bits`-bit `p` and a `q_bits`-bit subgroup order `q`,
+    /// drawing from the thread CSPRNG. See `generate_group_with`.
+    #[cfg(feature = "std")]
+    pub fn generate_group(p_bits: usize, q_bits: usize) -> Result<ZKP, GenError> {
+        Self::generate_group_with(&mut rand::thread_rng(), p_bits, q_bits)
+    }
+
+    /// Like `generate_group`, but drawing from a caller-supplied CSPRNG, so a seeded one
+    /// gives the same group every time.
+    ///
+    /// `alpha` is `h^((p - 1) / q)` for the first random `h` that does not give 1, and
+    /// `beta = alpha^e` for a random `e` in `[1, q)`, so both generate the order-q subgroup.
+    pub fn generate_group_with<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        p_bits: usize,
+        q_bits: usize,
+    ) -> Result<ZKP, GenError> {
+        if q_bits < 2 || p_bits <= q_bits {
+            return Err(GenError::InvalidSizes { p_bits, q_bits });
+        }
+
+        for _ in 0..Q_ATTEMPTS {
+            let q = random_of_bits(rng, q_bits);
+            if !is_probable_prime(rng, &q) {
+                continue;
+            }
+            // p = 1 mod 2q, so k is even and p is odd
+            let step = &q * 2u32;
+            for _ in 0..P_ATTEMPTS_PER_Q {
+                let candidate = random_of_bits(rng, p_bits);
+                let p = &candidate - (&candidate - 1u32) % &step;
+                if p.bits() as usize != p_bits {
+                    continue;
+                }
+                if is_probable_prime(rng, &p) {
+                    return Ok(Self::with_subgroup(rng, p, q));
+                }
+            }
+        }
+        Err(GenError::NotFound)
+    }
+
+    /// Picks `alpha` and `beta` of order `q` mod `p`, for a prime `q` dividing `p - 1`.
+    fn with_subgroup<R: RngCore + CryptoRng>(rng: &mut R, p: BigUint, q: BigUint) -> ZKP {
+        let cofactor = (&p - 1u32) / &q;
+        let two = BigUint::from(2u32);
+        let alpha = loop {
+            let h = rng.gen_biguint_range(&two, &(&p - 1u32));
+            let alpha = h.modpow(&cofactor, &p);
+            if !alpha.is_one() {
+                break alpha;
+            }
+        };
+        let exp = Self::generate_nonzero_below_with(rng, &q);
+        let beta = alpha.modpow(&exp, &p);
+        ZKP {
+            alpha,
+            beta,
+            p,
+            q,
+            tables: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn is_probable_prime_matches_known_values() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        let primes = [2u64, 3, 251, 257, 65537, 2147483647, 1000000007];
+        // 561 and 41041 are Carmichael numbers, which fool the Fermat test
+        let composites = [0u64, 1, 4, 561, 41041, 65535, 2147483649, 1000000007 * 3];
+        for n in primes {
+            assert!(is_probable_prime(&mut rng, &BigUint::from(n)), "{}", n);
+        }
+        for n in composites {
+            assert!(!is_probable_prime(&mut rng, &BigUint::from(n)), "{}", n);
+        }
+        let q = BigUint::parse_bytes(b"F518AA8781A8DF278ABA4E7D64B7CB9D49462353", 16).unwrap();
+        assert!(is_probable_prime(&mut rng, &q));
+        assert!(!is_probable_prime(&mut rng, &(&q * &q)));
+    }
+
+    #[test]
+    fn generated_groups_carry_honest_proofs() {
+        for (p_bits, q_bits) in [(5, 3), (64, 32), (256, 64)] {
+            let mut rng = ChaCha20Rng::seed_from_u64(p_bits as u64);
+            let zkp = ZKP::generate_group_with(&mut rng, p_bits, q_bits).unwrap();
+            assert_eq!(zkp.p.bits() as usize, p_bits);
+            assert_eq!(zkp.q.bits() as usize, q_bits);
+            assert!(is_probable_prime(&mut rng, &zkp.p) && is_probable_prime(&mut rng, &zkp.q));
+            assert_eq!((&zkp.p - 1u32) % &zkp.q, BigUint::ZERO);
+            for g in [&zkp.alpha, &zkp.beta] {
+                assert!(!g.is_one() && g.modpow(&zkp.q, &zkp.p).is_one());
+            }
+
+            let x = ZKP::generate_nonzero_below_with(&mut rng, &zkp.q);
+            let k = ZKP::generate_nonzero_below_with(&mut rng, &zkp.q);
+            let c = ZKP::generate_random_number_below_with(&mut rng, &zkp.q);
+            assert!(zkp.verify_transcript(&zkp.honest_transcript(&x, &k, &c)));
+
+            // The same seed finds the same group
+            let mut rng = ChaCha20Rng::seed_from_u64(p_bits as u64);
+            assert_eq!(ZKP::generate_group_with(&mut rng, p_bits, q_bits).unwrap(), zkp);
+        }
+    }
+
+    #[test]
+    fn generate_group_rejects_impossible_sizes() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for (p_bits, q_bits) in [(16, 1), (16, 16), (8, 16)] {
+            assert_eq!(
+                ZKP::generate_group_with(&mut rng, p_bits, q_bits),
+                Err(GenError::InvalidSizes { p_bits, q_bits })
+            );
+        }
+    }
+}
@@ -1,140 +1,1790 @@
-use num_bigint::{BigUint, RandBigInt};
-use rand::Rng;
+//! The Chaum-Pedersen proof over MODP groups, and the `AuthService` a verifier runs.
+//!
+//! The default `std` feature provides the thread RNG, `AuthService` and the `ec`
+//! and `serde` extras. Without it the crate is `no_std` with `alloc`: the group
+//! arithmetic and the `*_with` methods that take a caller's RNG remain.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+extern crate alloc;
+
+use alloc::{string::String, sync::Arc, vec, vec::Vec};
+use core::fmt::Debug;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use num_bigint::{BigInt, BigUint, RandBigInt};
+use rand::{CryptoRng, Rng, RngCore};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+pub mod arith;
+#[cfg(feature = "std")]
+pub mod audit;
+pub mod challenge;
+pub mod cross;
+#[cfg(feature = "ec")]
+pub mod ec;
+pub mod encoding;
+#[cfg(feature = "group-gen")]
+pub mod groupgen;
+pub mod modexp;
+#[cfg(feature = "serde")]
+mod serde_hex;
+pub mod secret;
+#[cfg(feature = "std")]
+pub mod service;
+pub mod types;
+#[cfg(feature = "std")]
+pub mod verify_cache;
+
+pub use challenge::{ChallengeHasher, Sha256Hasher, Sha512Hasher};
+use modexp::FixedBase;
+pub use types::{GroupElement, RangeError, Scalar};
+
+/// The operations a Chaum-Pedersen backend must provide, so provers and verifiers can be
+/// written once and run over either the MODP group (`ZKP`) or an elliptic curve.
+pub trait ZkpGroup {
+    /// Exponents: secrets, nonces, challenges and responses.
+    type Scalar: Clone + Debug + Default + Send + Sync;
+    /// Group elements: public keys and commitments.
+    type Element: Clone + Debug + Default + Send + Sync;
+
+    /// Returns `(alpha^exp, beta^exp)`.
+    fn compute_pair(&self, exp: &Self::Scalar) -> (Self::Element, Self::Element);
+
+    /// Returns `s = k - c * x` in the scalar field.
+    fn solve(&self, k: &Self::Scalar, c: &Self::Scalar, x: &Self::Scalar) -> Self::Scalar;
+
+    /// Checks `r1 == alpha^s * y1^c` and `r2 == beta^s * y2^c`.
+    fn verify(
+        &self,
+        r1: &Self::Element,
+        r2: &Self::Element,
+        y1: &Self::Element,
+        y2: &Self::Element,
+        c: &Self::Scalar,
+        s: &Self::Scalar,
+    ) -> bool;
+
+    /// Draws a fresh random challenge `c`.
+    #[cfg(feature = "std")]
+    fn generate_challenge(&self) -> Self::Scalar;
+
+    /// Draws a random challenge below `2^bits`, or a full-length one from
+    /// `generate_challenge` when `bits` spans the whole scalar range.
+    #[cfg(feature = "std")]
+    fn generate_short_challenge(&self, bits: u64) -> Self::Scalar;
+
+    /// Decodes a scalar received over the wire, or `None` if the bytes are not one.
+    fn scalar_from_bytes(&self, bytes: &[u8]) -> Option<Self::Scalar>;
+
+    fn scalar_to_bytes(&self, scalar: &Self::Scalar) -> Vec<u8>;
+
+    /// Decodes a group element received over the wire, or `None` if the bytes are not one.
+    fn element_from_bytes(&self, bytes: &[u8]) -> Option<Self::Element>;
+
+    fn element_to_bytes(&self, element: &Self::Element) -> Vec<u8>;
+
+    /// Returns whether a commitment `r` received from a prover is an element of the group.
+    fn validate_commitment(&self, r: &Self::Element) -> bool;
+
+    /// Returns whether `(y1, y2)` are the public keys of the secret `x = 0`, for which any
+    /// response verifies.
+    fn is_trivial_public_key(&self, y1: &Self::Element, y2: &Self::Element) -> bool;
+
+    /// Returns whether the public key `y` lies in the subgroup alpha and beta generate.
+    fn in_subgroup(&self, y: &Self::Element) -> bool;
+}
+
+/// Prime of the RFC 5114 1024-bit MODP group.
+const MODP_1024_P: &str =
+    "B10B8F96A080E01DDE92DE5EAE5D54EC52C99FBCFB06A3C69A6A9DCA52D23B6160\
+     73E28675A23D189838EF1E2EE652C013ECB4AEA906112324975C3CD49B83BFACC\
+     BDD7D90C4BD7098488E9C219A73724EFFD6FAE5644738FAA31A4FF55BCCC0A151A\
+     F5F0DC8B4BD45BF37DF365C1A65E68CFDA76D4DA708DF1FB2BC2E4A4371";
+
+/// Order of the RFC 5114 1024-bit group's prime-order subgroup (160 bits).
+const MODP_1024_Q: &str = "F518AA8781A8DF278ABA4E7D64B7CB9D49462353";
+
+/// Generator of the RFC 5114 1024-bit group's order-q subgroup.
+const MODP_1024_ALPHA: &str =
+    "A4D1CBD5C3FD34126765A442EFB99905F8104DD258AC507FD6406CFF14266D312\
+     66FEA1E5C41564B777E690F5504F213160217B4B01B886A5E91547F9E2749F4D7F\
+     BD7D3B9A92EE1909D0D2263F80A76A6A24C087A091F531DBF0A0169B6A28AD662A4\
+     D18E73AFA32D779D5918D08BC8858F4DCEF97C2A24855E6EEB22B3B2E5";
+
+/// Prime of the RFC 3526 2048-bit MODP group (group 14), a safe prime `p = 2q + 1`.
+const MODP_2048_P: &str =
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74\
+     020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437\
+     4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED\
+     EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF05\
+     98DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB\
+     9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3B\
+     E39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF695581718\
+     3995497CEA956AE515D2261898FA051015728E5A8AACAA68FFFFFFFFFFFFFFFF";
+
+/// Prime of the RFC 3526 3072-bit MODP group (group 15), a safe prime `p = 2q + 1`.
+const MODP_3072_P: &str =
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74\
+     020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437\
+     4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED\
+     EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF05\
+     98DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB\
+     9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3B\
+     E39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF695581718\
+     3995497CEA956AE515D2261898FA051015728E5A8AAAC42DAD33170D04507A33\
+     A85521ABDF1CBA64ECFB850458DBEF0A8AEA71575D060C7DB3970F85A6E1E4C7\
+     ABF5AE8CDB0933D71E8C94E04A25619DCEE3D2261AD2EE6BF12FFA06D98A0864\
+     D87602733EC86A64521F2B18177B200CBBE117577A615D6C770988C0BAD946E2\
+     08E24FA074E5AB3143DB5BFCE0FD108E4B82D120A93AD2CAFFFFFFFFFFFFFFFF";
+
+/// Exponent deriving `beta = alpha^BETA_EXP mod p` from `alpha` in every built-in group.
+const BETA_EXP: &str = "266FEA1E5C41564B777E69";
+
+/// The built-in groups `ZKP::constants_for` can return.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GroupSize {
+    /// RFC 5114 1024-bit MODP group with a 160-bit prime-order subgroup.
+    #[default]
+    Modp1024,
+    /// RFC 3526 2048-bit MODP group; the subgroup has order `(p - 1) / 2`.
+    Modp2048,
+    /// RFC 3526 3072-bit MODP group; the subgroup has order `(p - 1) / 2`.
+    Modp3072,
+}
+
+/// Why `ZKP::from_hex` rejected a set of group parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamError {
+    /// The named parameter is not valid hex.
+    InvalidHex(&'static str, hex::FromHexError),
+    /// `q` is zero or `p` is at most 1.
+    InvalidOrder,
+    /// `(p - 1) % q != 0`, so there is no subgroup of order q and proofs are unsound.
+    QNotDivisorOfPMinus1,
+    /// `alpha` is trivial, not below `p`, or `alpha^q != 1 mod p`.
+    NotInSubgroup,
+}
+
+impl core::fmt::Display for ParamError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParamError::InvalidHex(name, e) => write!(f, "'{}' is not valid hex: {}", name, e),
+            ParamError::InvalidOrder => write!(f, "q must be nonzero and p greater than 1"),
+            ParamError::QNotDivisorOfPMinus1 => write!(f, "q does not divide p - 1"),
+            ParamError::NotInSubgroup => {
+                write!(f, "alpha does not generate a subgroup of order q")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParamError::InvalidHex(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// One proof to check in `ZKP::verify_batch`, as `(r1, r2, y1, y2, c, s)`.
+pub type BatchProof = (BigUint, BigUint, BigUint, BigUint, BigUint, BigUint);
+
+/// Group parameters, and the tables `ZkpBuilder::precompute` adds. The tables are shared
+/// between clones, so cloning copies just the four numbers.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ZKP {
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
     pub alpha: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
     pub beta: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
     pub p: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
     pub q: BigUint,
+    /// `FixedBase` tables of alpha and beta for `compute_pair`, if precomputed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    tables: Option<Arc<(FixedBase, FixedBase)>>,
+}
+
+// Whether tables were precomputed does not change the group
+impl PartialEq for ZKP {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_group(other)
+    }
+}
+
+impl Eq for ZKP {}
+
+/// Builds a `ZKP`: `ZkpBuilder::default().group(GroupSize::Modp2048).build()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZkpBuilder {
+    group: GroupSize,
+    precompute: bool,
+}
+
+impl ZkpBuilder {
+    /// The built-in group to use; `GroupSize::Modp1024` unless set.
+    pub fn group(mut self, size: GroupSize) -> Self {
+        self.group = size;
+        self
+    }
+
+    /// Tabulate powers of alpha and beta up front so `compute_pair` needs about a
+    /// quarter as many multiplications and no squarings. The tables take
+    /// `30 * q_bits / 4` group elements: about 150 KiB for the 1024-bit group, but 9 MiB
+    /// for the 3072-bit one, whose q is almost as wide as p. Off unless set.
+    pub fn precompute(mut self, precompute: bool) -> Self {
+        self.precompute = precompute;
+        self
+    }
+
+    pub fn build(self) -> Result<ZKP, ParamError> {
+        let mut zkp = ZKP::for_group(self.group);
+        if self.precompute {
+            let bits = zkp.q.bits();
+            zkp.tables = Some(Arc::new((
+                FixedBase::new(&zkp.alpha, &zkp.p, bits),
+                FixedBase::new(&zkp.beta, &zkp.p, bits),
+            )));
+        }
+        Ok(zkp)
+    }
+}
+
+// The parameters are hundreds of digits long, so only their sizes are printed.
+impl Debug for ZKP {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ZKP")
+            .field("p_bits", &self.p.bits())
+            .field("q_bits", &self.q.bits())
+            .finish_non_exhaustive()
+    }
+}
+
+impl core::fmt::Display for ZKP {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}-bit MODP group, {}-bit subgroup", self.p.bits(), self.q.bits())
+    }
+}
+
+/// A prover's answer to one challenge: commitments `(r1, r2)`, the challenge `c` and the
+/// response `s`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Proof {
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub r1: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub r2: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub c: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub s: BigUint,
+}
+
+#[cfg(feature = "serde")]
+impl Proof {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// One line, `r1:<hex>|r2:<hex>|c:<hex>|s:<hex>`, for logs and test fixtures.
+impl core::fmt::Display for Proof {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "r1:{}|r2:{}|c:{}|s:{}",
+            encoding::biguint_to_hex(&self.r1),
+            encoding::biguint_to_hex(&self.r2),
+            encoding::biguint_to_hex(&self.c),
+            encoding::biguint_to_hex(&self.s),
+        )
+    }
+}
+
+/// Why a string is not a `Proof` in the form its `Display` writes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProofParseError {
+    /// The named field is missing, out of order, or not labelled `name:`.
+    MissingField(&'static str),
+    /// The named field's value is not valid hex.
+    InvalidHex(&'static str, hex::FromHexError),
+    /// Something follows the `s` field.
+    TrailingInput,
+}
+
+impl core::fmt::Display for ProofParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ProofParseError::MissingField(name) => write!(f, "expected a '{}:' field", name),
+            ProofParseError::InvalidHex(name, e) => write!(f, "'{}' is not valid hex: {}", name, e),
+            ProofParseError::TrailingInput => write!(f, "unexpected input after 's'"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProofParseError {}
+
+/// Parses what `Display` writes. The hex may be in either case and of any length.
+impl core::str::FromStr for Proof {
+    type Err = ProofParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut fields = text.trim().split('|');
+        let mut next = |name: &'static str| {
+            let value = fields
+                .next()
+                .and_then(|field| field.strip_prefix(name))
+                .and_then(|field| field.strip_prefix(':'))
+                .ok_or(ProofParseError::MissingField(name))?;
+            encoding::biguint_from_hex(value).map_err(|e| ProofParseError::InvalidHex(name, e))
+        };
+        let proof = Proof {
+            r1: next("r1")?,
+            r2: next("r2")?,
+            c: next("c")?,
+            s: next("s")?,
+        };
+        match fields.next() {
+            Some(_) => Err(ProofParseError::TrailingInput),
+            None => Ok(proof),
+        }
+    }
+}
+
+/// Everything `ZKP::verify_transcript` checks: the commitments, the public keys, the
+/// challenge and the response, named so they cannot be passed in the wrong order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transcript {
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub r1: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub r2: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub y1: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub y2: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub c: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub s: BigUint,
+}
+
+#[cfg(feature = "serde")]
+impl Transcript {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// What a `RegisterRequest` carries, built by `ZKP::registration_bundle` so it can be
+/// generated offline and sent later.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistrationBundle {
+    pub user: String,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub y1: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub y2: BigUint,
+}
+
+#[cfg(feature = "serde")]
+impl RegistrationBundle {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Writes the bundle to `path` as JSON.
+    pub fn to_file(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_json()?)
+    }
+
+    /// Reads a bundle written by `to_file`; malformed JSON is an `InvalidData` error.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        Ok(Self::from_json(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+/// The outcome of each check in `ZKP::verify_detailed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// `r1 = alpha^s * y1^c (mod p)`
+    pub cond1: bool,
+    /// `r2 = beta^s * y2^c (mod p)`
+    pub cond2: bool,
+}
+
+/// A group's parameters in a form other implementations can read: with the `serde`
+/// feature they serialize as lowercase hex strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroupParams {
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub p: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub q: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub alpha: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex"))]
+    pub beta: BigUint,
+}
+
+impl GroupParams {
+    /// SHA-256 of `p || q || alpha || beta`, each big-endian and padded to the byte width
+    /// of `p` (or of `q` for `q`), so two parties can compare groups with one short value.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let p_len = self.p.bits().div_ceil(8) as usize;
+        let q_len = self.q.bits().div_ceil(8) as usize;
+        let mut hasher = Sha256::new();
+        hasher.update(encoding::to_fixed_bytes(&self.p, p_len));
+        hasher.update(encoding::to_fixed_bytes(&self.q, q_len));
+        hasher.update(encoding::to_fixed_bytes(&self.alpha, p_len));
+        hasher.update(encoding::to_fixed_bytes(&self.beta, p_len));
+        hasher.finalize().into()
+    }
+}
+
+/// One proof of knowledge of the same `x` for several generator pairs `(alpha_i, beta_i)`,
+/// with commitments `(r1_i, r2_i)` per pair and a single shared challenge and response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiProof {
+    pub commitments: Vec<(BigUint, BigUint)>,
+    pub c: BigUint,
+    pub s: BigUint,
+}
+
+/// Which of the two statements in an OR-proof the prover knows the secret for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrBranch {
+    Left,
+    Right,
+}
+
+/// A proof that the prover knows `x` for one of two public key pairs, without showing
+/// which. Each branch is an ordinary transcript; their challenges add up to `c` mod q.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrProof {
+    pub c: BigUint,
+    pub branches: [Proof; 2],
+}
+
+impl Default for ZKP {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZKP {
+    /// The 1024-bit group without precomputed tables; the same as
+    /// `ZkpBuilder::default().build().unwrap()`.
+    pub fn new() -> Self {
+        ZkpBuilder::default().build().unwrap()
+    }
+
+    /// Builds a `ZKP` from hex-encoded `p`, `q` and `alpha`, with `beta = alpha^exp mod p`.
+    /// Surrounding whitespace is ignored, so values can be read straight from a text file.
+    pub fn from_hex(
+        p_hex: &str,
+        q_hex: &str,
+        alpha_hex: &str,
+        exp_hex: &str,
+    ) -> Result<Self, ParamError> {
+        let decode = |name, hex: &str| {
+            encoding::biguint_from_hex(hex.trim()).map_err(|e| ParamError::InvalidHex(name, e))
+        };
+        let p = decode("p", p_hex)?;
+        let q = decode("q", q_hex)?;
+        let alpha = decode("alpha", alpha_hex)?;
+        let exp = decode("exp", exp_hex)?;
+
+        let one = BigUint::from(1u32);
+        if q.bits() == 0 || p <= one {
+            return Err(ParamError::InvalidOrder);
+        }
+        if ((&p - 1u32) % &q).bits() != 0 {
+            return Err(ParamError::QNotDivisorOfPMinus1);
+        }
+        if alpha <= one || alpha >= p || alpha.modpow(&q, &p) != one {
+            return Err(ParamError::NotInSubgroup);
+        }
+
+        let beta = alpha.modpow(&exp, &p);
+        Ok(ZKP {
+            alpha,
+            beta,
+            p,
+            q,
+            tables: None,
+        })
+    }
+
+    /// Builds a `ZKP` over one of the built-in groups.
+    pub fn for_group(size: GroupSize) -> Self {
+        let (alpha, beta, p, q) = Self::constants_for(size);
+        ZKP {
+            alpha,
+            beta,
+            p,
+            q,
+            tables: None,
+        }
+    }
+
+    /// The group parameters, for sharing with clients that need to use the same group.
+    pub fn params(&self) -> GroupParams {
+        GroupParams {
+            p: self.p.clone(),
+            q: self.q.clone(),
+            alpha: self.alpha.clone(),
+            beta: self.beta.clone(),
+        }
+    }
+
+    /// Whether `other` has the same `p`, `q`, `alpha` and `beta`, so proofs made with one
+    /// verify with the other. Precomputed tables are not compared; this is also `==`.
+    pub fn same_group(&self, other: &ZKP) -> bool {
+        self.alpha == other.alpha && self.beta == other.beta && self.p == other.p && self.q == other.q
+    }
+
+    /// Returns `(alpha^exp, beta^exp) mod p`.
+    pub fn compute_pair(&self, exp: &Scalar) -> (GroupElement, GroupElement) {
+        let (a, b) = self.raw_pair(exp);
+        (GroupElement(a), GroupElement(b))
+    }
+
+    fn raw_pair(&self, exp: &BigUint) -> (BigUint, BigUint) {
+        match &self.tables {
+            // Tables left over from before a pub field was changed are ignored
+            Some(tables)
+                if tables.0.is_for(&self.alpha, &self.p) && tables.1.is_for(&self.beta, &self.p) =>
+            {
+                (
+                    arith::timed("alpha^exp", || tables.0.pow(exp)),
+                    arith::timed("beta^exp", || tables.1.pow(exp)),
+                )
+            }
+            _ => arith::compute_pair(&self.p, &self.alpha, &self.beta, exp),
+        }
+    }
+
+    /// Returns the public keys `(y1, y2) = (alpha^x, beta^x)` a prover registers for secret `x`.
+    pub fn public_keys(&self, x: &BigUint) -> (BigUint, BigUint) {
+        self.raw_pair(x)
+    }
+
+    /// `public_keys(x)` as the fixed-width big-endian bytes a `RegisterRequest` carries.
+    pub fn register_values(&self, x: &BigUint) -> (Vec<u8>, Vec<u8>) {
+        let (y1, y2) = self.public_keys(x);
+        (self.element_to_bytes(&y1), self.element_to_bytes(&y2))
+    }
+
+    /// The registration of `user` with secret `x`, holding only the public keys.
+    pub fn registration_bundle(&self, user: &str, x: &BigUint) -> RegistrationBundle {
+        let (y1, y2) = self.public_keys(x);
+        RegistrationBundle {
+            user: user.into(),
+            y1,
+            y2,
+        }
+    }
+
+    /// Runs the whole prover side for secret `x`, nonce `k` and challenge `c`, returning the
+    /// commitments and response that `verify` checks against `public_keys(x)`.
+    pub fn prove(&self, x: &BigUint, k: &BigUint, c: &BigUint) -> Proof {
+        let (r1, r2) = self.raw_pair(k);
+        let s = self.raw_solve(k, c, x);
+        Proof {
+            r1,
+            r2,
+            c: c.clone(),
+            s,
+        }
+    }
+
+    /// The transcript of an honest run for secret `x`, nonce `k` and challenge `c`: `prove`
+    /// plus the public keys of `x`. Meant for tests and examples; a real prover never has
+    /// both sides' values in one place.
+    pub fn honest_transcript(&self, x: &BigUint, k: &BigUint, c: &BigUint) -> Transcript {
+        let (y1, y2) = self.public_keys(x);
+        let Proof { r1, r2, c, s } = self.prove(x, k, c);
+        Transcript {
+            r1,
+            r2,
+            y1,
+            y2,
+            c,
+            s,
+        }
+    }
+
+    /// Proves knowledge of `x` with no verifier to pick the challenge: `c` is `hasher`
+    /// applied to the group, the public keys and the commitments, so it is fixed as soon
+    /// as `k` is.
+    pub fn prove_non_interactive<H: ChallengeHasher>(
+        &self,
+        hasher: &H,
+        x: &BigUint,
+        k: &BigUint,
+    ) -> Proof {
+        let (y1, y2) = self.raw_pair(x);
+        let (r1, r2) = self.raw_pair(k);
+        let c = self.fiat_shamir_challenge(hasher, &y1, &y2, &r1, &r2);
+        let s = self.raw_solve(k, &c, x);
+        Proof { r1, r2, c, s }
+    }
+
+    /// Checks a `prove_non_interactive` proof for the public keys `(y1, y2)`, recomputing
+    /// its challenge with `hasher`, which must be the one the prover used.
+    pub fn verify_non_interactive<H: ChallengeHasher>(
+        &self,
+        hasher: &H,
+        y1: &BigUint,
+        y2: &BigUint,
+        proof: &Proof,
+    ) -> bool {
+        let c = self.fiat_shamir_challenge(hasher, y1, y2, &proof.r1, &proof.r2);
+        self.ct_eq(&c, &proof.c) & self.raw_verify(&proof.r1, &proof.r2, y1, y2, &c, &proof.s)
+    }
+
+    /// `hasher` over `p, alpha, beta, y1, y2, r1, r2`, each padded to the byte width of `p`.
+    fn fiat_shamir_challenge<H: ChallengeHasher>(
+        &self,
+        hasher: &H,
+        y1: &BigUint,
+        y2: &BigUint,
+        r1: &BigUint,
+        r2: &BigUint,
+    ) -> BigUint {
+        let inputs = [&self.p, &self.alpha, &self.beta, y1, y2, r1, r2]
+            .map(|value| self.element_to_bytes(value));
+        let inputs: Vec<&[u8]> = inputs.iter().map(Vec::as_slice).collect();
+        hasher.hash_to_scalar(&inputs, &self.q)
+    }
+
+    /// Derives a nonce `k` in `[1, q)` from the secret `x` and `message` (e.g. the
+    /// transcript being proven) following RFC 6979 section 3.2 with HMAC-SHA256, so `k`
+    /// does not depend on the RNG and is distinct for every message signed under `x`.
+    pub fn deterministic_k(&self, x: &BigUint, message: &[u8]) -> BigUint {
+        let qlen = self.q.bits() as usize;
+        let rlen = qlen.div_ceil(8);
+        // The leftmost `qlen` bits of `bytes` as an integer
+        let bits2int = |bytes: &[u8]| {
+            let v = BigUint::from_bytes_be(bytes);
+            let blen = bytes.len() * 8;
+            if blen > qlen {
+                v >> (blen - qlen)
+            } else {
+                v
+            }
+        };
+        // `v < q` as exactly `rlen` big-endian bytes
+        let int2octets = |v: &BigUint| {
+            let bytes = v.to_bytes_be();
+            let mut out = vec![0u8; rlen - bytes.len()];
+            out.extend_from_slice(&bytes);
+            Zeroizing::new(out)
+        };
+        let hmac = |key: &[u8], parts: &[&[u8]]| -> [u8; 32] {
+            let mut mac =
+                Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+            for part in parts {
+                mac.update(part);
+            }
+            mac.finalize().into_bytes().into()
+        };
+
+        let x = int2octets(&(x % &self.q));
+        let h1 = int2octets(&(bits2int(&Sha256::digest(message)) % &self.q));
+
+        let mut v = [0x01u8; 32];
+        let mut key = Zeroizing::new([0x00u8; 32]);
+        *key = hmac(&*key, &[&v, &[0x00], &x, &h1]);
+        v = hmac(&*key, &[&v]);
+        *key = hmac(&*key, &[&v, &[0x01], &x, &h1]);
+        v = hmac(&*key, &[&v]);
+
+        loop {
+            let mut t = Zeroizing::new(Vec::with_capacity(rlen + 32));
+            while t.len() < rlen {
+                v = hmac(&*key, &[&v]);
+                t.extend_from_slice(&v);
+            }
+            let k = bits2int(&t);
+            if k > BigUint::ZERO && k < self.q {
+                return k;
+            }
+            *key = hmac(&*key, &[&v, &[0x00]]);
+            v = hmac(&*key, &[&v]);
+        }
+    }
+
+    /// Computes `(alpha_i^x, beta_i^x) mod p` for every pair in `bases`.
+    pub fn public_keys_multi(
+        &self,
+        bases: &[(BigUint, BigUint)],
+        x: &BigUint,
+    ) -> Vec<(BigUint, BigUint)> {
+        bases
+            .iter()
+            .map(|(alpha, beta)| (alpha.modpow(x, &self.p), beta.modpow(x, &self.p)))
+            .collect()
+    }
+
+    /// Proves knowledge of `x` for every pair in `bases` at once, committing with the same
+    /// nonce `k` so one response `s` answers the shared challenge `c`. Every base must lie in
+    /// the order-q subgroup.
+    pub fn prove_multi(
+        &self,
+        x: &BigUint,
+        k: &BigUint,
+        bases: &[(BigUint, BigUint)],
+        c: &BigUint,
+    ) -> MultiProof {
+        MultiProof {
+            commitments: self.public_keys_multi(bases, k),
+            c: c.clone(),
+            s: self.raw_solve(k, c, x),
+        }
+    }
+
+    /// Checks a `MultiProof` against the public keys `ys`, given in the same order as `bases`.
+    pub fn verify_multi(
+        &self,
+        bases: &[(BigUint, BigUint)],
+        ys: &[(BigUint, BigUint)],
+        proof: &MultiProof,
+    ) -> bool {
+        if bases.len() != ys.len() || bases.len() != proof.commitments.len() {
+            return false;
+        }
+        if proof.c >= self.q || proof.s >= self.q {
+            return false;
+        }
+
+        let expected = |base: &BigUint, y: &BigUint| {
+            (base.modpow(&proof.s, &self.p) * y.modpow(&proof.c, &self.p)) % &self.p
+        };
+
+        bases
+            .iter()
+            .zip(ys)
+            .zip(&proof.commitments)
+            .fold(true, |ok, (((alpha, beta), (y1, y2)), (r1, r2))| {
+                ok & self.ct_eq(r1, &expected(alpha, y1)) & self.ct_eq(r2, &expected(beta, y2))
+            })
+    }
+
+    /// Proves knowledge of `x` for `ys[known]` against the challenge `c`, drawing the nonce
+    /// and the simulated branch from the thread CSPRNG.
+    #[cfg(feature = "std")]
+    pub fn prove_or(
+        &self,
+        x: &BigUint,
+        known: OrBranch,
+        ys: &[(BigUint, BigUint); 2],
+        c: &BigUint,
+    ) -> OrProof {
+        self.prove_or_with(&mut rand::thread_rng(), x, known, ys, c)
+    }
+
+    /// Like `prove_or`, but drawing from a caller-supplied CSPRNG.
+    ///
+    /// The branch the prover cannot answer is simulated: pick `c_sim` and `s_sim` at
+    /// random and set `r_sim = (alpha^s_sim * y1^c_sim, beta^s_sim * y2^c_sim)`, which
+    /// passes `verify` without knowing its secret. The known branch commits to a fresh
+    /// nonce `k` as usual and answers the remaining challenge `c_real = c - c_sim mod q`
+    /// with `s = k - c_real * x mod q`. The verifier only checks that both transcripts
+    /// verify and `c_left + c_right = c`. Both challenges are uniform either way, so the
+    /// proof does not reveal which branch was real. The prover controls only one of the two
+    /// challenges, so it must know a secret for at least one branch.
+    ///
+    /// As with `prove_multi`, `c` must not be chosen by the prover: it has to come from the
+    /// verifier after the commitments are fixed, or from hashing them.
+    pub fn prove_or_with<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        x: &BigUint,
+        known: OrBranch,
+        ys: &[(BigUint, BigUint); 2],
+        c: &BigUint,
+    ) -> OrProof {
+        let (real, sim) = match known {
+            OrBranch::Left => (0, 1),
+            OrBranch::Right => (1, 0),
+        };
+
+        let c_sim = rng.gen_biguint_below(&self.q);
+        let s_sim = rng.gen_biguint_below(&self.q);
+        let (y1, y2) = &ys[sim];
+        let simulated = Proof {
+            r1: (self.alpha.modpow(&s_sim, &self.p) * y1.modpow(&c_sim, &self.p)) % &self.p,
+            r2: (self.beta.modpow(&s_sim, &self.p) * y2.modpow(&c_sim, &self.p)) % &self.p,
+            c: c_sim.clone(),
+            s: s_sim,
+        };
+
+        let k = crate::secret::Secret::from(Self::generate_nonzero_below_with(rng, &self.q));
+        let c_real = (c % &self.q + &self.q - c_sim) % &self.q;
+        let answered = self.prove(x, &k, &c_real);
+
+        let mut branches = [simulated.clone(), simulated];
+        branches[real] = answered;
+        OrProof {
+            c: c.clone(),
+            branches,
+        }
+    }
+
+    /// Checks an `OrProof` for the public key pairs `ys`, in the order they were proven.
+    pub fn verify_or(&self, ys: &[(BigUint, BigUint); 2], proof: &OrProof) -> bool {
+        let [left, right] = &proof.branches;
+        let split = self.ct_eq(&((&left.c + &right.c) % &self.q), &(&proof.c % &self.q));
+
+        proof.branches.iter().zip(ys).fold(split, |ok, (branch, (y1, y2))| {
+            ok & self.raw_verify(&branch.r1, &branch.r2, y1, y2, &branch.c, &branch.s)
+        })
+    }
+
+    /// Returns the response `s = k - c * x mod q`.
+    pub fn solve(&self, k: &Scalar, c: &Scalar, x: &Scalar) -> Scalar {
+        Scalar(self.raw_solve(k, c, x))
+    }
+
+    fn raw_solve(&self, k: &BigUint, c: &BigUint, x: &BigUint) -> BigUint {
+        arith::solve(&self.q, k, c, x)
+    }
+
+    /// The inverse of `a` mod `q` by the extended Euclidean algorithm, or `None` when `a`
+    /// and `q` are not coprime.
+    pub fn mod_inverse(&self, a: &BigUint) -> Option<BigUint> {
+        let q = BigInt::from(self.q.clone());
+        // Invariant: old_r = old_t * a and r = t * a (mod q)
+        let (mut old_r, mut r) = (BigInt::from(a % &self.q), q.clone());
+        let (mut old_t, mut t) = (BigInt::from(1u32), BigInt::from(0u32));
+        while r != BigInt::ZERO {
+            let quotient = &old_r / &r;
+            let next_r = &old_r - &quotient * &r;
+            old_r = core::mem::replace(&mut r, next_r);
+            let next_t = &old_t - &quotient * &t;
+            old_t = core::mem::replace(&mut t, next_t);
+        }
+
+        if old_r != BigInt::from(1u32) {
+            return None;
+        }
+        (((old_t % &q) + &q) % &q).to_biguint()
+    }
+
+    /// Checks `r1 = alpha^s * y1^c` and `r2 = beta^s * y2^c (mod p)`.
+    ///
+    /// Passing a scalar where an element belongs does not compile, but swapping two
+    /// elements or the two scalars still does; `verify_transcript` names every field.
+    pub fn verify(
+        &self,
+        r1: &GroupElement,
+        r2: &GroupElement,
+        y1: &GroupElement,
+        y2: &GroupElement,
+        c: &Scalar,
+        s: &Scalar,
+    ) -> bool {
+        self.raw_verify(r1, r2, y1, y2, c, s)
+    }
+
+    /// Like `verify`, but reporting the alpha-side and beta-side checks separately, to
+    /// tell which half of a failing proof is wrong. If both fail while the commitments
+    /// are right, the public keys may have been passed as `(y2, y1)`.
+    pub fn verify_detailed(
+        &self,
+        r1: &GroupElement,
+        r2: &GroupElement,
+        y1: &GroupElement,
+        y2: &GroupElement,
+        c: &Scalar,
+        s: &Scalar,
+    ) -> VerifyReport {
+        let (cond1, cond2) =
+            arith::verify_each(&self.p, &self.alpha, &self.beta, r1, r2, y1, y2, c, s);
+        VerifyReport { cond1, cond2 }
+    }
+
+    /// `verify` on bare values, refusing a `c` or `s` of `q` or more. Those are not
+    /// scalars, and `c = q` would let anyone pass: `y^q = 1`, so `r = alpha^s` verifies
+    /// for every `s`.
+    fn raw_verify(
+        &self,
+        r1: &BigUint,
+        r2: &BigUint,
+        y1: &BigUint,
+        y2: &BigUint,
+        c: &BigUint,
+        s: &BigUint,
+    ) -> bool {
+        if *c >= self.q || *s >= self.q {
+            return false;
+        }
+        arith::verify(&self.p, &self.alpha, &self.beta, r1, r2, y1, y2, c, s)
+    }
+
+    /// `verify` with its four exponentiations already done, e.g. on an accelerator:
+    /// `ys_c` is `(y1^c, y2^c)` and `alpha_beta_s` is `(alpha^s, beta^s)`, all mod `p`.
+    /// Only the two multiplications and the constant-time comparisons are left.
+    ///
+    /// `c` and `s` never reach this method, so refusing values of `q` or more, as
+    /// `verify` does, is up to whoever computes the parts.
+    pub fn verify_with_parts(
+        &self,
+        r1: &BigUint,
+        r2: &BigUint,
+        ys_c: (BigUint, BigUint),
+        alpha_beta_s: (BigUint, BigUint),
+    ) -> bool {
+        let (y1_c, y2_c) = ys_c;
+        let (alpha_s, beta_s) = alpha_beta_s;
+        let (cond1, cond2) =
+            arith::verify_parts(&self.p, r1, r2, (&y1_c, &y2_c), (&alpha_s, &beta_s));
+        cond1 & cond2
+    }
+
+    /// Checks a full `Transcript`; same result as `verify` with its fields in order.
+    pub fn verify_transcript(&self, t: &Transcript) -> bool {
+        self.raw_verify(&t.r1, &t.r2, &t.y1, &t.y2, &t.c, &t.s)
+    }
+
+    /// Compares two values without an early exit; see `arith::ct_eq`.
+    fn ct_eq(&self, a: &BigUint, b: &BigUint) -> bool {
+        arith::ct_eq(&self.p, a, b)
+    }
+
+    /// Returns whether `r` lies in the multiplicative group `[1, p)`. Values such as `0` or
+    /// `p` are not group elements and must not be accepted as commitments.
+    pub fn validate_commitment(&self, r: &BigUint) -> bool {
+        *r != BigUint::from(0u32) && *r < self.p
+    }
+
+    /// Returns whether `y1` or `y2` is `1 mod p`. Since alpha and beta have order q, either
+    /// one being 1 means `x = 0 mod q`, and then `r = alpha^s` verifies for any `s`.
+    pub fn is_trivial_public_key(&self, y1: &BigUint, y2: &BigUint) -> bool {
+        let one = BigUint::from(1u32);
+        y1 % &self.p == one || y2 % &self.p == one
+    }
+
+    /// Returns whether `y^q = 1 mod p`, i.e. `y` lies in the order-q subgroup. A key
+    /// outside it has a component of some other order that the checks against `p` do not
+    /// pin down, so registering one invites small-subgroup confusion.
+    pub fn in_subgroup(&self, y: &BigUint) -> bool {
+        y.modpow(&self.q, &self.p) == BigUint::from(1u32)
+    }
+
+    /// Verifies many proofs at once, returning one result per proof.
+    ///
+    /// Each proof is weighted by a random 64-bit `w_i` and the checks are folded into
+    /// `prod(r1_i^w_i) == alpha^(sum w_i*s_i) * prod(y1_i^(w_i*c_i))` (and likewise for
+    /// the beta side), so alpha and beta are only raised once for the whole batch and the
+    /// commitments only to short exponents. If the combined check fails, each proof is
+    /// re-verified on its own so the bad ones can be flagged.
+    ///
+    /// A proof with `c` or `s` of `q` or more, or an `r` or `y` outside `[1, p)`, is refused
+    /// up front and left out of the combination, as `verify` refuses it: with `c = q` its
+    /// weighted term `w*c mod q` vanishes, and `r = alpha^s` would pass for any `s`, while
+    /// `r + p` is reduced away by the first multiplication.
+    ///
+    /// This assumes every `r` and `y` lies in the order-q subgroup; an element with a
+    /// small-order component can pass the combined check for some weights.
+    #[cfg(feature = "std")]
+    pub fn verify_batch(&self, proofs: &[BatchProof]) -> Vec<bool> {
+        self.verify_batch_with(&mut rand::thread_rng(), proofs)
+    }
+
+    /// Like `verify_batch`, but drawing the weights from a caller-supplied CSPRNG.
+    pub fn verify_batch_with<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        proofs: &[BatchProof],
+    ) -> Vec<bool> {
+        let in_range: Vec<bool> = proofs
+            .iter()
+            .map(|(r1, r2, y1, y2, c, s)| {
+                self.validate_commitment(r1)
+                    && self.validate_commitment(r2)
+                    && *y1 < self.p
+                    && *y2 < self.p
+                    && *c < self.q
+                    && *s < self.q
+            })
+            .collect();
+        let candidates: Vec<&BatchProof> = proofs
+            .iter()
+            .zip(&in_range)
+            .filter_map(|(proof, ok)| ok.then_some(proof))
+            .collect();
+        if self.verify_combined(rng, &candidates) {
+            return in_range;
+        }
+
+        proofs
+            .iter()
+            .map(|(r1, r2, y1, y2, c, s)| self.raw_verify(r1, r2, y1, y2, c, s))
+            .collect()
+    }
+
+    fn verify_combined<R: RngCore + CryptoRng>(&self, rng: &mut R, proofs: &[&BatchProof]) -> bool {
+        let mut s_sum = BigUint::from(0u32);
+        let mut lhs1 = BigUint::from(1u32);
+        let mut lhs2 = BigUint::from(1u32);
+        let mut rhs1 = BigUint::from(1u32);
+        let mut rhs2 = BigUint::from(1u32);
+
+        for (r1, r2, y1, y2, c, s) in proofs {
+            let w = BigUint::from(rng.gen_range(1..=u64::MAX));
+            let wc = (&w * c) % &self.q;
+            s_sum = (s_sum + &w * s) % &self.q;
+
+            lhs1 = (lhs1 * r1.modpow(&w, &self.p)) % &self.p;
+            lhs2 = (lhs2 * r2.modpow(&w, &self.p)) % &self.p;
+            rhs1 = (rhs1 * y1.modpow(&wc, &self.p)) % &self.p;
+            rhs2 = (rhs2 * y2.modpow(&wc, &self.p)) % &self.p;
+        }
+
+        let cond1 = self.ct_eq(&lhs1, &((self.alpha.modpow(&s_sum, &self.p) * rhs1) % &self.p));
+        let cond2 = self.ct_eq(&lhs2, &((self.beta.modpow(&s_sum, &self.p) * rhs2) % &self.p));
+
+        cond1 & cond2
+    }
+
+    /// Draws a uniform scalar in `[0, q)` from the thread CSPRNG.
+    #[cfg(feature = "std")]
+    pub fn generate_random_scalar(&self) -> Scalar {
+        Scalar(Self::generate_random_number_below(&self.q))
+    }
+
+    /// Draws a uniform scalar in `[1, q)`, for a secret `x` or nonce `k`: `x = 0` gives
+    /// public keys any response verifies against, and `k = 0` makes `s = -cx` give `x` away.
+    #[cfg(feature = "std")]
+    pub fn generate_nonzero_scalar(&self) -> Scalar {
+        Scalar(Self::generate_nonzero_below(&self.q))
+    }
+
+    #[cfg(feature = "std")]
+    pub fn generate_random_number_below(limit: &BigUint) -> BigUint {
+        Self::generate_random_number_below_with(&mut rand::thread_rng(), limit)
+    }
+
+    /// Like `generate_random_number_below`, but drawing from a caller-supplied CSPRNG.
+    pub fn generate_random_number_below_with<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        limit: &BigUint,
+    ) -> BigUint {
+        rng.gen_biguint_below(limit)
+    }
+
+    /// Draws a uniform value in `[1, limit)` from the thread CSPRNG, resampling zeros.
+    /// Panics if `limit` is below 2, as the range is then empty.
+    #[cfg(feature = "std")]
+    pub fn generate_nonzero_below(limit: &BigUint) -> BigUint {
+        Self::generate_nonzero_below_with(&mut rand::thread_rng(), limit)
+    }
+
+    /// Like `generate_nonzero_below`, but drawing from a caller-supplied CSPRNG.
+    pub fn generate_nonzero_below_with<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        limit: &BigUint,
+    ) -> BigUint {
+        assert!(limit.bits() > 1, "no nonzero value below {}", limit);
+        loop {
+            let value = rng.gen_biguint_below(limit);
+            if value.bits() != 0 {
+                return value;
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn generate_random_string(size: usize) -> String {
+        Self::generate_random_string_with(&mut rand::thread_rng(), size)
+    }
+
+    /// Like `generate_random_string`, but drawing from a caller-supplied CSPRNG.
+    pub fn generate_random_string_with<R: RngCore + CryptoRng>(rng: &mut R, size: usize) -> String {
+        const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+                                 abcdefghijklmnopqrstuvwxyz\
+                                 0123456789";
+
+        (0..size)
+            .map(|_| {
+                let idx = rng.gen_range(0..CHARSET.len());
+                CHARSET[idx] as char
+            })
+            .collect()
+    }
+
+    /// An unguessable identifier carrying `byte_entropy` random bytes from the thread CSPRNG,
+    /// encoded as unpadded URL-safe base64 (`ceil(4 * byte_entropy / 3)` characters).
+    #[cfg(feature = "std")]
+    pub fn generate_token(byte_entropy: usize) -> String {
+        Self::generate_token_with(&mut rand::thread_rng(), byte_entropy)
+    }
+
+    /// Like `generate_token`, but drawing from a caller-supplied CSPRNG.
+    pub fn generate_token_with<R: RngCore + CryptoRng>(rng: &mut R, byte_entropy: usize) -> String {
+        let mut bytes = vec![0u8; byte_entropy];
+        rng.fill_bytes(&mut bytes);
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    /// Returns `(alpha, beta, p, q)` for the default RFC 5114 1024-bit group.
+    pub fn get_constants() -> (BigUint, BigUint, BigUint, BigUint) {
+        Self::constants_for(GroupSize::Modp1024)
+    }
+
+    /// Returns `(alpha, beta, p, q)` for the given built-in group.
+    pub fn constants_for(size: GroupSize) -> (BigUint, BigUint, BigUint, BigUint) {
+        match size {
+            GroupSize::Modp1024 => Self::modp_1024_constants(),
+            GroupSize::Modp2048 => Self::safe_prime_constants(MODP_2048_P),
+            GroupSize::Modp3072 => Self::safe_prime_constants(MODP_3072_P),
+        }
+    }
+
+    /// The RFC 3526 groups use `alpha = 2`, which generates the order-q subgroup because
+    /// `p = 7 mod 8` makes 2 a quadratic residue.
+    fn safe_prime_constants(p_hex: &str) -> (BigUint, BigUint, BigUint, BigUint) {
+        let p = BigUint::from_bytes_be(&hex::decode(p_hex).unwrap());
+        let q = (&p - 1u32) / 2u32;
+        let alpha = BigUint::from(2u32);
+
+        let exp = BigUint::from_bytes_be(&hex::decode(BETA_EXP).unwrap());
+        let beta = alpha.modpow(&exp, &p);
+
+        (alpha, beta, p, q)
+    }
+
+    fn modp_1024_constants() -> (BigUint, BigUint, BigUint, BigUint) {
+        let zkp = Self::from_hex(MODP_1024_P, MODP_1024_Q, MODP_1024_ALPHA, BETA_EXP)
+            .expect("the built-in 1024-bit group is valid");
+        (zkp.alpha, zkp.beta, zkp.p, zkp.q)
+    }
+}
+
+impl ZkpGroup for ZKP {
+    type Scalar = BigUint;
+    type Element = BigUint;
+
+    fn compute_pair(&self, exp: &BigUint) -> (BigUint, BigUint) {
+        self.raw_pair(exp)
+    }
+
+    fn solve(&self, k: &BigUint, c: &BigUint, x: &BigUint) -> BigUint {
+        self.raw_solve(k, c, x)
+    }
+
+    fn verify(
+        &self,
+        r1: &BigUint,
+        r2: &BigUint,
+        y1: &BigUint,
+        y2: &BigUint,
+        c: &BigUint,
+        s: &BigUint,
+    ) -> bool {
+        self.raw_verify(r1, r2, y1, y2, c, s)
+    }
+
+    #[cfg(feature = "std")]
+    fn generate_challenge(&self) -> BigUint {
+        Self::generate_random_number_below(&self.q)
+    }
+
+    #[cfg(feature = "std")]
+    fn generate_short_challenge(&self, bits: u64) -> BigUint {
+        if bits >= self.q.bits() {
+            return self.generate_challenge();
+        }
+        rand::thread_rng().gen_biguint(bits)
+    }
+
+    fn scalar_from_bytes(&self, bytes: &[u8]) -> Option<BigUint> {
+        Some(BigUint::from_bytes_be(bytes))
+    }
+
+    /// Padded to the byte width of `q`.
+    fn scalar_to_bytes(&self, scalar: &BigUint) -> Vec<u8> {
+        encoding::to_fixed_bytes(scalar, self.q.bits().div_ceil(8) as usize)
+    }
+
+    fn element_from_bytes(&self, bytes: &[u8]) -> Option<BigUint> {
+        Some(BigUint::from_bytes_be(bytes))
+    }
+
+    /// Padded to the byte width of `p`.
+    fn element_to_bytes(&self, element: &BigUint) -> Vec<u8> {
+        encoding::to_fixed_bytes(element, self.p.bits().div_ceil(8) as usize)
+    }
+
+    fn validate_commitment(&self, r: &BigUint) -> bool {
+        ZKP::validate_commitment(self, r)
+    }
+
+    fn is_trivial_public_key(&self, y1: &BigUint, y2: &BigUint) -> bool {
+        ZKP::is_trivial_public_key(self, y1, y2)
+    }
+
+    fn in_subgroup(&self, y: &BigUint) -> bool {
+        ZKP::in_subgroup(self, y)
+    }
 }
 
-impl ZKP {
-    pub fn new() -> Self {
-        let (alpha, beta, p, q) = Self::get_constants();
-        ZKP { alpha, beta, p, q }
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+    use num_traits::One;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use std::str::FromStr;
+
+    /// `verify` on untyped values, which must all be in range.
+    fn typed_verify(
+        zkp: &ZKP,
+        r1: &BigUint,
+        r2: &BigUint,
+        y1: &BigUint,
+        y2: &BigUint,
+        c: &BigUint,
+        s: &BigUint,
+    ) -> bool {
+        let element = |v: &BigUint| GroupElement::new(zkp, v.clone()).unwrap();
+        let scalar = |v: &BigUint| Scalar::new(zkp, v.clone()).unwrap();
+        zkp.verify(&element(r1), &element(r2), &element(y1), &element(y2), &scalar(c), &scalar(s))
+    }
+
+    #[test]
+    fn deterministic_k_is_stable_per_message() {
+        let zkp = ZKP::new();
+        let x = ZKP::generate_random_number_below(&zkp.q);
+
+        let k = zkp.deterministic_k(&x, b"transcript one");
+        assert_eq!(k, zkp.deterministic_k(&x, b"transcript one"));
+        assert_ne!(k, zkp.deterministic_k(&x, b"transcript two"));
+        assert_ne!(k, zkp.deterministic_k(&(&x + 1u32), b"transcript one"));
+        assert!(k > BigUint::ZERO && k < zkp.q);
+
+        let c = ZKP::generate_random_number_below(&zkp.q);
+        let (y1, y2) = zkp.public_keys(&x);
+        let proof = zkp.prove(&x, &k, &c);
+        assert!(typed_verify(&zkp, &proof.r1, &proof.r2, &y1, &y2, &proof.c, &proof.s));
+    }
+
+    fn honest_batch(zkp: &ZKP, n: usize) -> Vec<BatchProof> {
+        (0..n)
+            .map(|_| {
+                let x = ZKP::generate_random_number_below(&zkp.q);
+                let k = ZKP::generate_random_number_below(&zkp.q);
+                let c = ZKP::generate_random_number_below(&zkp.q);
+                let (y1, y2) = zkp.public_keys(&x);
+                let proof = zkp.prove(&x, &k, &c);
+                (proof.r1, proof.r2, y1, y2, c, proof.s)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn new_creates_zkp_with_constants() {
+        let zkp = ZKP::new();
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        assert_eq!(zkp.alpha, alpha);
+        assert_eq!(zkp.beta, beta);
+        assert_eq!(zkp.p, p);
+        assert_eq!(zkp.q, q);
+    }
+
+    #[test]
+    fn same_group_compares_only_the_parameters() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let plain = ZKP {
+            alpha: alpha.clone(),
+            beta: beta.clone(),
+            p: p.clone(),
+            q: q.clone(),
+            tables: None,
+        };
+        let precomputed = ZkpBuilder::default().precompute(true).build().unwrap();
+        assert!(plain.same_group(&precomputed) && precomputed.same_group(&plain));
+
+        let other = ZKP {
+            alpha: alpha.modpow(&BigUint::from(2u32), &p),
+            beta,
+            p,
+            q,
+            tables: None,
+        };
+        assert!(!plain.same_group(&other));
+        assert!(!plain.same_group(&ZKP::for_group(GroupSize::Modp2048)));
     }
 
-    pub fn compute_pair(&self, exp: &BigUint) -> (BigUint, BigUint) {
-        let a = self.alpha.modpow(exp, &self.p);
-        let b = self.beta.modpow(exp, &self.p);
-        (a, b)
+    #[test]
+    fn built_in_groups_satisfy_the_subgroup_relation() {
+        for size in [GroupSize::Modp1024, GroupSize::Modp2048, GroupSize::Modp3072] {
+            let (alpha, beta, p, q) = ZKP::constants_for(size);
+            assert_eq!(alpha.modpow(&q, &p), BigUint::one(), "alpha^q != 1 for {:?}", size);
+            assert_eq!(beta.modpow(&q, &p), BigUint::one(), "beta^q != 1 for {:?}", size);
+            assert_ne!(alpha, BigUint::one());
+            assert_ne!(beta, BigUint::one());
+        }
     }
 
-    pub fn solve(
-        &self,
-        k: &BigUint,
-        c: &BigUint,
-        x: &BigUint
-    ) -> BigUint {
-        let res = (c * x) % &self.q;
-        (k + &self.q - res) % &self.q
+    #[test]
+    fn from_hex_round_trips_the_built_in_constants() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::from_hex(
+            &format!("{:x}\n", p),
+            &q.to_str_radix(16).to_uppercase(),
+            &format!("  {:x}", alpha),
+            BETA_EXP,
+        )
+        .unwrap();
+        assert_eq!((zkp.alpha, zkp.beta, zkp.p, zkp.q), (alpha, beta, p, q));
     }
 
-    pub fn verify(
-        &self,
-        r1: &BigUint,
-        r2: &BigUint,
-        y1: &BigUint,
-        y2: &BigUint,
-        c: &BigUint,
-        s: &BigUint,
-    ) -> bool {
-        let cond1 = *r1
-            == (&self.alpha.modpow(s, &self.p) * y1.modpow(c, &self.p))
-                .modpow(&BigUint::from(1u32), &self.p);
+    #[test]
+    fn from_hex_rejects_invalid_parameters() {
+        assert!(matches!(
+            ZKP::from_hex("zz", MODP_1024_Q, MODP_1024_ALPHA, BETA_EXP),
+            Err(ParamError::InvalidHex("p", _))
+        ));
+        // 23 = 2 * 11 + 1, so 7 does not divide p - 1
+        assert_eq!(
+            ZKP::from_hex("17", "7", "4", "3").unwrap_err(),
+            ParamError::QNotDivisorOfPMinus1
+        );
+        assert_eq!(
+            ZKP::from_hex("17", "0", "4", "3").unwrap_err(),
+            ParamError::InvalidOrder
+        );
+        // Off by one from the built-in q
+        let q = BigUint::from_bytes_be(&hex::decode(MODP_1024_Q).unwrap());
+        let q_plus_1 = format!("{:x}", q + 1u32);
+        assert_eq!(
+            ZKP::from_hex(MODP_1024_P, &q_plus_1, MODP_1024_ALPHA, BETA_EXP).unwrap_err(),
+            ParamError::QNotDivisorOfPMinus1
+        );
+        assert!(ZKP::from_hex(MODP_1024_P, MODP_1024_Q, MODP_1024_ALPHA, BETA_EXP).is_ok());
+        // 5 has order 22 mod 23, not 11
+        assert_eq!(
+            ZKP::from_hex("17", "b", "5", "3").unwrap_err(),
+            ParamError::NotInSubgroup
+        );
+        assert!(ZKP::from_hex("17", "b", "4", "3").is_ok());
+    }
 
-        let cond2 = *r2
-            == (&self.beta.modpow(s, &self.p) * y2.modpow(c, &self.p))
-                .modpow(&BigUint::from(1u32), &self.p);
+    #[test]
+    fn debug_and_display_show_sizes_not_values() {
+        let zkp = ZKP::new();
+        let debug = format!("{:?}", zkp);
+        assert!(!debug.contains(&zkp.p.to_string()));
+        assert!(!debug.contains(&zkp.alpha.to_string()));
+        assert_eq!(debug, "ZKP { p_bits: 1024, q_bits: 160, .. }");
+        assert_eq!(zkp.to_string(), "1024-bit MODP group, 160-bit subgroup");
+    }
 
-        cond1 && cond2
+    #[test]
+    fn params_fingerprint_is_stable_for_the_built_in_groups() {
+        let zkp = ZKP::new();
+        let params = zkp.params();
+        assert_eq!((&params.p, &params.q), (&zkp.p, &zkp.q));
+        // Pinned so a change to the constants or the encoding shows up here
+        assert_eq!(
+            hex::encode(params.fingerprint()),
+            "badc9a31229e530b65171d2bac178e176c67da8d64e2ac498dabf6eb5ffde35d"
+        );
+        assert_ne!(
+            ZKP::for_group(GroupSize::Modp2048).params().fingerprint(),
+            params.fingerprint()
+        );
     }
 
-    pub fn generate_random_number_below(limit: &BigUint) -> BigUint {
-        let mut r = rand::thread_rng();
-        r.gen_biguint_below(limit)
+    #[test]
+    fn register_values_decode_to_the_public_keys() {
+        let zkp = ZKP::new();
+        let x = ZKP::generate_random_number_below(&zkp.q);
+        let (y1, y2) = zkp.register_values(&x);
+        assert_eq!(y1.len(), 128);
+        assert_eq!(BigUint::from_bytes_be(&y1), zkp.alpha.modpow(&x, &zkp.p));
+        assert_eq!(BigUint::from_bytes_be(&y2), zkp.beta.modpow(&x, &zkp.p));
     }
 
-    pub fn generate_random_string(size: usize) -> String {
-        const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
-                                 abcdefghijklmnopqrstuvwxyz\
-                                 0123456789";
+    #[test]
+    fn clones_compare_equal_and_verify_alike() {
+        let zkp = ZKP::new();
+        assert_eq!(zkp.clone(), zkp);
+        assert_ne!(ZKP::for_group(GroupSize::Modp2048), zkp);
 
-        let mut rng = rand::thread_rng();
-        (0..size)
+        let x = ZKP::generate_random_number_below(&zkp.q);
+        let k = ZKP::generate_random_number_below(&zkp.q);
+        let c = ZKP::generate_random_number_below(&zkp.q);
+        let (y1, y2) = zkp.public_keys(&x);
+        let proof = zkp.prove(&x, &k, &c);
+        let handles: Vec<_> = (0..4)
             .map(|_| {
-                let idx = rng.gen_range(0..CHARSET.len());
-                CHARSET[idx] as char
+                let zkp = zkp.clone();
+                let (y1, y2, proof) = (y1.clone(), y2.clone(), proof.clone());
+                std::thread::spawn(move || {
+                    typed_verify(&zkp, &proof.r1, &proof.r2, &y1, &y2, &proof.c, &proof.s)
+                })
             })
-            .collect()
+            .collect();
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
     }
 
-    pub fn get_constants() -> (BigUint, BigUint, BigUint, BigUint) {
-        let p = BigUint::from_bytes_be(
-            &hex::decode(
-                "B10B8F96A080E01DDE92DE5EAE5D54EC52C99FBCFB06A3C69A6A9DCA52D23B6160\
-                 73E28675A23D189838EF1E2EE652C013ECB4AEA906112324975C3CD49B83BFACC\
-                 BDD7D90C4BD7098488E9C219A73724EFFD6FAE5644738FAA31A4FF55BCCC0A151A\
-                 F5F0DC8B4BD45BF37DF365C1A65E68CFDA76D4DA708DF1FB2BC2E4A4371",
-            )
-            .unwrap(),
-        );
+    #[test]
+    fn verify_transcript_checks_named_fields() {
+        let zkp = ZKP::new();
+        let x = ZKP::generate_random_number_below(&zkp.q);
+        let k = ZKP::generate_random_number_below(&zkp.q);
+        let c = ZKP::generate_random_number_below(&zkp.q);
+        let (y1, y2) = zkp.public_keys(&x);
+        let proof = zkp.prove(&x, &k, &c);
+
+        let mut transcript = Transcript {
+            r1: proof.r1,
+            r2: proof.r2,
+            y1,
+            y2,
+            c: proof.c,
+            s: proof.s,
+        };
+        assert!(zkp.verify_transcript(&transcript));
+
+        std::mem::swap(&mut transcript.y1, &mut transcript.y2);
+        assert!(!zkp.verify_transcript(&transcript));
+    }
 
-        let q = BigUint::from_bytes_be(
-            &hex::decode("F518AA8781A8DF278ABA4E7D64B7CB9D49462353").unwrap(),
+    #[test]
+    fn proof_display_round_trips_through_from_str() {
+        let zkp = ZKP::new();
+        let proof = zkp.prove(
+            &BigUint::from(6u32),
+            &BigUint::from(7u32),
+            &BigUint::from(0x0102u32),
         );
+        let text = proof.to_string();
+        assert!(text.starts_with("r1:") && text.contains("|c:0102|s:"), "{}", text);
+        assert_eq!(Proof::from_str(&text), Ok(proof));
+
+        // Leading zero bytes and upper case parse to the same values
+        let small = Proof::from_str("r1:0001|r2:00FF|c:00|s:000a").unwrap();
+        let values = [&small.r1, &small.r2, &small.c, &small.s].map(Clone::clone);
+        assert_eq!(values, [1u32, 255, 0, 10].map(BigUint::from));
+        assert_eq!(small.to_string(), "r1:01|r2:ff|c:00|s:0a");
+        assert_eq!(Proof::from_str(&small.to_string()), Ok(small));
 
-        let alpha = BigUint::from_bytes_be(
-            &hex::decode(
-                "A4D1CBD5C3FD34126765A442EFB99905F8104DD258AC507FD6406CFF14266D312\
-                 66FEA1E5C41564B777E690F5504F213160217B4B01B886A5E91547F9E2749F4D7F\
-                 BD7D3B9A92EE1909D0D2263F80A76A6A24C087A091F531DBF0A0169B6A28AD662A4\
-                 D18E73AFA32D779D5918D08BC8858F4DCEF97C2A24855E6EEB22B3B2E5",
-            )
-            .unwrap(),
+        for (text, err) in [
+            ("r1:01|r2:02|s:03|c:04", ProofParseError::MissingField("c")),
+            ("r1:01|r2:02|c:03", ProofParseError::MissingField("s")),
+            ("r1:01|r2:02|c:03|s:04|x:05", ProofParseError::TrailingInput),
+        ] {
+            assert_eq!(Proof::from_str(text), Err(err), "{}", text);
+        }
+        assert!(matches!(
+            Proof::from_str("r1:01|r2:zz|c:03|s:04"),
+            Err(ProofParseError::InvalidHex("r2", _))
+        ));
+    }
+
+    #[test]
+    fn builder_with_and_without_precompute_agree() {
+        let plain = ZkpBuilder::default().build().unwrap();
+        let precomputed = ZkpBuilder::default().precompute(true).build().unwrap();
+        assert!(plain.tables.is_none() && precomputed.tables.is_some());
+        assert_eq!(plain, precomputed);
+        assert_eq!(plain, ZKP::new());
+
+        for _ in 0..20 {
+            let x = plain.generate_random_scalar();
+            assert_eq!(precomputed.compute_pair(&x), plain.compute_pair(&x));
+        }
+        // Exponents wider than q, such as an unreduced password, take the fallback
+        let wide = BigUint::from_bytes_be(&[0xab; 40]);
+        assert_eq!(precomputed.public_keys(&wide), plain.public_keys(&wide));
+
+        let built = ZkpBuilder::default().group(GroupSize::Modp2048).build().unwrap();
+        assert_eq!(built, ZKP::for_group(GroupSize::Modp2048));
+    }
+
+    #[test]
+    fn stale_tables_are_not_used_after_a_field_changes() {
+        let mut zkp = ZkpBuilder::default().precompute(true).build().unwrap();
+        zkp.beta = zkp.alpha.modpow(&BigUint::from(3u32), &zkp.p);
+        let x = BigUint::from(5u32);
+        assert_eq!(zkp.public_keys(&x).1, zkp.beta.modpow(&x, &zkp.p));
+    }
+
+    #[test]
+    fn verify_transcript_rejects_out_of_range_c_and_s() {
+        let zkp = ZKP::new();
+        let (x, k) = (BigUint::from(6u32), BigUint::from(7u32));
+        let (y1, y2) = zkp.public_keys(&x);
+        let s = BigUint::from(5u32);
+        let (r1, r2) = zkp.public_keys(&s);
+
+        // c = q needs no secret at all: y^q = 1 turns the check into r = alpha^s
+        let forged = Transcript {
+            r1,
+            r2,
+            y1: y1.clone(),
+            y2: y2.clone(),
+            c: zkp.q.clone(),
+            s,
+        };
+        assert!(!zkp.verify_transcript(&forged));
+
+        // An honest proof for c = 1, presented with c = q + 1 or s + q
+        let proof = zkp.prove(&x, &k, &BigUint::from(1u32));
+        let honest = Transcript {
+            r1: proof.r1,
+            r2: proof.r2,
+            y1,
+            y2,
+            c: proof.c,
+            s: proof.s,
+        };
+        assert!(zkp.verify_transcript(&honest));
+        assert!(!zkp.verify_transcript(&Transcript {
+            c: &zkp.q + 1u32,
+            ..honest.clone()
+        }));
+        assert!(!zkp.verify_transcript(&Transcript {
+            s: &honest.s + &zkp.q,
+            ..honest
+        }));
+    }
+
+    #[test]
+    fn solve_reduces_an_oversized_nonce() {
+        let zkp = ZKP::new();
+        let x = ZKP::generate_random_number_below(&zkp.q);
+        let k = ZKP::generate_random_number_below(&zkp.q);
+        let c = ZKP::generate_random_number_below(&zkp.q);
+        let (y1, y2) = zkp.public_keys(&x);
+
+        // alpha and beta have order q, so k + 1000q commits to the same r1, r2 as k
+        let big_k = &k + &zkp.q * 1000u32;
+        let proof = zkp.prove(&x, &big_k, &c);
+        assert!(proof.s < zkp.q);
+        assert_eq!(proof, zkp.prove(&x, &k, &c));
+        assert!(zkp.verify_transcript(&Transcript {
+            r1: proof.r1,
+            r2: proof.r2,
+            y1,
+            y2,
+            c: proof.c,
+            s: proof.s,
+        }));
+    }
+
+    #[test]
+    fn verify_detailed_reports_each_side() {
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let k = zkp.generate_random_scalar();
+        let c = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x);
+
+        let report = zkp.verify_detailed(&r1, &r2, &y1, &y2, &c, &s);
+        assert_eq!(report, VerifyReport { cond1: true, cond2: true });
+        assert_eq!(
+            zkp.verify_detailed(&r1, &r2, &y2, &y1, &c, &s),
+            VerifyReport { cond1: false, cond2: false }
+        );
+        // A wrong r2 only breaks the beta side
+        assert_eq!(
+            zkp.verify_detailed(&r1, &r1, &y1, &y2, &c, &s),
+            VerifyReport { cond1: true, cond2: false }
         );
+    }
+
+    #[test]
+    fn non_interactive_proof_verifies_only_under_its_hasher() {
+        let zkp = ZKP::new();
+        let x = ZKP::generate_random_number_below(&zkp.q);
+        let k = ZKP::generate_random_number_below(&zkp.q);
+        let (y1, y2) = zkp.public_keys(&x);
 
-        let exp = BigUint::from_bytes_be(&hex::decode("266FEA1E5C41564B777E69").unwrap());
+        let proof = zkp.prove_non_interactive(&Sha512Hasher, &x, &k);
+        assert!(zkp.verify_non_interactive(&Sha512Hasher, &y1, &y2, &proof));
+        assert!(!zkp.verify_non_interactive(&Sha256Hasher, &y1, &y2, &proof));
+        assert!(!zkp.verify_non_interactive(&Sha512Hasher, &y2, &y1, &proof));
 
-        let beta = alpha.modpow(&exp, &p);
+        let proof = zkp.prove_non_interactive(&Sha256Hasher, &x, &k);
+        assert!(zkp.verify_non_interactive(&Sha256Hasher, &y1, &y2, &proof));
+        assert!(!zkp.verify_non_interactive(&Sha512Hasher, &y1, &y2, &proof));
+    }
 
-        (alpha, beta, p, q)
+    #[test]
+    fn or_proof_verifies_for_the_known_branch() {
+        let zkp = ZKP::new();
+        let x = ZKP::generate_random_number_below(&zkp.q);
+        let other = ZKP::generate_random_number_below(&zkp.q);
+        let ys = [zkp.public_keys(&x), zkp.public_keys(&other)];
+        let c = ZKP::generate_random_number_below(&zkp.q);
+
+        let proof = zkp.prove_or(&x, OrBranch::Left, &ys, &c);
+        assert!(zkp.verify_or(&ys, &proof));
+
+        let swapped = [ys[1].clone(), ys[0].clone()];
+        assert!(zkp.verify_or(&swapped, &zkp.prove_or(&x, OrBranch::Right, &swapped, &c)));
+
+        let mut tampered = proof.clone();
+        tampered.c = (&proof.c + 1u32) % &zkp.q;
+        assert!(!zkp.verify_or(&ys, &tampered));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use num_bigint::BigUint;
-    use num_traits::One;
+    #[test]
+    fn or_proof_fails_when_neither_secret_is_known() {
+        let zkp = ZKP::new();
+        let ys = [
+            zkp.public_keys(&ZKP::generate_random_number_below(&zkp.q)),
+            zkp.public_keys(&ZKP::generate_random_number_below(&zkp.q)),
+        ];
+        let c = ZKP::generate_random_number_below(&zkp.q);
+        let guess = ZKP::generate_random_number_below(&zkp.q);
+
+        for known in [OrBranch::Left, OrBranch::Right] {
+            assert!(!zkp.verify_or(&ys, &zkp.prove_or(&guess, known, &ys, &c)));
+        }
+    }
 
     #[test]
-    fn new_creates_zkp_with_constants() {
+    fn mod_inverse_inverts_units_mod_q() {
         let zkp = ZKP::new();
-        let (alpha, beta, p, q) = ZKP::get_constants();
-        assert_eq!(zkp.alpha, alpha);
-        assert_eq!(zkp.beta, beta);
-        assert_eq!(zkp.p, p);
-        assert_eq!(zkp.q, q);
+        let one = BigUint::from(1u32);
+        for a in [
+            one.clone(),
+            BigUint::from(2u32),
+            &zkp.q - 1u32,
+            &zkp.q + 5u32,
+            ZKP::generate_random_number_below(&zkp.q),
+        ] {
+            let inverse = zkp.mod_inverse(&a).unwrap();
+            assert!(inverse < zkp.q);
+            assert_eq!(a * inverse % &zkp.q, one);
+        }
+        assert_eq!(zkp.mod_inverse(&BigUint::from(0u32)), None);
+        assert_eq!(zkp.mod_inverse(&(&zkp.q * 3u32)), None);
+
+        let composite = ZKP {
+            q: BigUint::from(12u32),
+            ..ZKP::new()
+        };
+        assert_eq!(composite.mod_inverse(&BigUint::from(5u32)), Some(BigUint::from(5u32)));
+        assert_eq!(composite.mod_inverse(&BigUint::from(7u32)), Some(BigUint::from(7u32)));
+        for a in [2u32, 3, 8, 9] {
+            assert_eq!(composite.mod_inverse(&BigUint::from(a)), None);
+        }
+    }
+
+    #[test]
+    fn nonce_reuse_reveals_the_secret() {
+        // s_i = k - c_i * x, so x = (s1 - s2) / (c2 - c1) mod q
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let k = zkp.generate_random_scalar();
+        let c1 = Scalar::new(&zkp, BigUint::from(3u32)).unwrap();
+        let c2 = Scalar::new(&zkp, BigUint::from(10u32)).unwrap();
+        let s1 = zkp.solve(&k, &c1, &x);
+        let s2 = zkp.solve(&k, &c2, &x);
+
+        let ds = (&*s1 + &zkp.q - &*s2) % &zkp.q;
+        let recovered = ds * zkp.mod_inverse(&(&*c2 - &*c1)).unwrap() % &zkp.q;
+        assert_eq!(recovered, *x);
+    }
+
+    #[test]
+    fn multi_proof_over_two_base_pairs() {
+        let zkp = ZKP::new();
+        let gamma = zkp.alpha.modpow(&BigUint::from(7u32), &zkp.p);
+        let delta = zkp.beta.modpow(&BigUint::from(11u32), &zkp.p);
+        let bases = vec![(zkp.alpha.clone(), zkp.beta.clone()), (gamma, delta)];
+
+        let x = ZKP::generate_random_number_below(&zkp.q);
+        let k = ZKP::generate_random_number_below(&zkp.q);
+        let c = ZKP::generate_random_number_below(&zkp.q);
+        let mut ys = zkp.public_keys_multi(&bases, &x);
+        let proof = zkp.prove_multi(&x, &k, &bases, &c);
+        assert!(zkp.verify_multi(&bases, &ys, &proof));
+
+        ys.swap(0, 1);
+        assert!(!zkp.verify_multi(&bases, &ys, &proof));
+        assert!(!zkp.verify_multi(&bases[..1], &ys[..1], &proof));
+    }
+
+    #[test]
+    fn group_sizes_have_expected_bit_lengths() {
+        assert_eq!(ZKP::for_group(GroupSize::Modp1024).p.bits(), 1024);
+        assert_eq!(ZKP::for_group(GroupSize::Modp2048).p.bits(), 2048);
+        assert_eq!(ZKP::for_group(GroupSize::Modp3072).p.bits(), 3072);
+        assert_eq!(ZKP::constants_for(GroupSize::default()), ZKP::get_constants());
+    }
+
+    // Also runs in the browser: `wasm-pack test --headless --firefox -- --features wasm`.
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn compute_pair_with_a_fixed_exponent() {
+        let zkp = ZKP::new();
+        let scalar = |v: u32| Scalar::new(&zkp, BigUint::from(v)).unwrap();
+        let (y1, y2) = zkp.compute_pair(&scalar(3));
+        assert_eq!(*y1, (&zkp.alpha * &zkp.alpha * &zkp.alpha) % &zkp.p);
+        assert_eq!(*y2, (&zkp.beta * &zkp.beta * &zkp.beta) % &zkp.p);
+
+        let s = zkp.solve(&scalar(10), &scalar(2), &scalar(3));
+        assert_eq!(*s, BigUint::from(4u32));
+        let (r1, r2) = zkp.compute_pair(&scalar(10));
+        assert!(zkp.verify(&r1, &r2, &y1, &y2, &scalar(2), &s));
+        assert!(!zkp.verify(&r1, &r2, &y1, &y2, &scalar(2), &scalar(0)));
     }
 
     #[test]
     fn compute_pair_returns_correct_values() {
         let zkp = ZKP::new();
-        let exp = BigUint::one();
+        let exp = Scalar::new(&zkp, BigUint::one()).unwrap();
         let (a, b) = zkp.compute_pair(&exp);
-        assert_eq!(a, zkp.alpha.modpow(&exp, &zkp.p));
-        assert_eq!(b, zkp.beta.modpow(&exp, &zkp.p));
+        assert_eq!(*a, zkp.alpha.modpow(&exp, &zkp.p));
+        assert_eq!(*b, zkp.beta.modpow(&exp, &zkp.p));
     }
 
     #[test]
     fn solve_returns_correct_value() {
         let zkp = ZKP::new();
-        let k = BigUint::one();
-        let c = BigUint::one();
-        let x = BigUint::one();
+        let k = zkp.generate_random_scalar();
+        let c = zkp.generate_random_scalar();
+        let x = zkp.generate_random_scalar();
         let result = zkp.solve(&k, &c, &x);
-        assert_eq!(result, (k + &zkp.q - (c * x) % &zkp.q) % &zkp.q);
+        assert_eq!(*result, (&*k + &zkp.q - (&*c * &*x) % &zkp.q) % &zkp.q);
     }
 
     #[test]
@@ -142,20 +1792,20 @@ mod tests {
         let zkp = ZKP::new();
 
         // 1) Pick a random secret exponent x
-        let x = ZKP::generate_random_number_below(&zkp.q);
+        let x = zkp.generate_random_scalar();
 
         // 2) Compute y1 = alpha^x mod p and y2 = beta^x mod p
-        let y1 = zkp.alpha.modpow(&x, &zkp.p);
-        let y2 = zkp.beta.modpow(&x, &zkp.p);
+        let y1 = GroupElement::new(&zkp, zkp.alpha.modpow(&x, &zkp.p)).unwrap();
+        let y2 = GroupElement::new(&zkp, zkp.beta.modpow(&x, &zkp.p)).unwrap();
 
         // 3) Pick an ephemeral k
-        let k = ZKP::generate_random_number_below(&zkp.q);
+        let k = zkp.generate_random_scalar();
 
         // 4) Compute (r1, r2) = (alpha^k mod p, beta^k mod p)
         let (r1, r2) = zkp.compute_pair(&k);
 
         // 5) Pick a challenge c
-        let c = ZKP::generate_random_number_below(&zkp.q);
+        let c = zkp.generate_random_scalar();
 
         // 6) Compute the response s = k - c*x (mod q)
         let s = zkp.solve(&k, &c, &x);
@@ -164,6 +1814,177 @@ mod tests {
         assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &s));
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// A scalar from 32 arbitrary bytes, reduced mod q.
+        fn scalar(zkp: &ZKP, bytes: &[u8; 32]) -> Scalar {
+            Scalar::reduce(zkp, &BigUint::from_bytes_be(bytes))
+        }
+
+        // 256 cases each, proptest's default
+        proptest! {
+            #[test]
+            fn honest_transcripts_always_verify(
+                x in any::<[u8; 32]>(),
+                k in any::<[u8; 32]>(),
+                c in any::<[u8; 32]>(),
+            ) {
+                let zkp = ZKP::new();
+                let (x, k, c) = (scalar(&zkp, &x), scalar(&zkp, &k), scalar(&zkp, &c));
+                let (y1, y2) = zkp.compute_pair(&x);
+                let (r1, r2) = zkp.compute_pair(&k);
+                let s = zkp.solve(&k, &c, &x);
+                prop_assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &s));
+
+                // `honest_transcript` builds the same values
+                let transcript = zkp.honest_transcript(&x, &k, &c);
+                prop_assert!(zkp.verify_transcript(&transcript));
+                let expected = Transcript {
+                    r1: r1.into_inner(),
+                    r2: r2.into_inner(),
+                    y1: y1.into_inner(),
+                    y2: y2.into_inner(),
+                    c: c.into_inner(),
+                    s: s.into_inner(),
+                };
+                prop_assert_eq!(transcript, expected);
+            }
+
+            #[test]
+            fn perturbed_responses_never_verify(
+                x in any::<[u8; 32]>(),
+                k in any::<[u8; 32]>(),
+                c in any::<[u8; 32]>(),
+                delta in any::<[u8; 32]>(),
+            ) {
+                let zkp = ZKP::new();
+                let (x, k, c) = (scalar(&zkp, &x), scalar(&zkp, &k), scalar(&zkp, &c));
+                // A delta in [1, q), so s + delta differs from s mod q
+                let delta = BigUint::from_bytes_be(&delta) % (&zkp.q - 1u32) + 1u32;
+                let (y1, y2) = zkp.compute_pair(&x);
+                let (r1, r2) = zkp.compute_pair(&k);
+                let s = zkp.solve(&k, &c, &x);
+                let perturbed = Scalar::reduce(&zkp, &(&*s + delta));
+                prop_assert!(!zkp.verify(&r1, &r2, &y1, &y2, &c, &perturbed));
+            }
+        }
+    }
+
+    #[test]
+    fn public_keys_of_zero_are_trivial() {
+        let zkp = ZKP::new();
+        let (y1, y2) = zkp.public_keys(&BigUint::from(0u32));
+        assert!(zkp.is_trivial_public_key(&y1, &y2));
+        assert!(zkp.is_trivial_public_key(&(&zkp.p + 1u32), &zkp.beta));
+        assert!(zkp.is_trivial_public_key(&zkp.alpha, &BigUint::one()));
+
+        let (y1, y2) = zkp.public_keys(&BigUint::from(5u32));
+        assert!(!zkp.is_trivial_public_key(&y1, &y2));
+    }
+
+    #[test]
+    fn in_subgroup_accepts_only_order_q_elements() {
+        let zkp = ZKP::new();
+        let (y1, y2) = zkp.public_keys(&BigUint::from(5u32));
+        assert!(zkp.in_subgroup(&y1) && zkp.in_subgroup(&y2));
+        // p - 1 has order 2, and 2 generates far more than the 160-bit subgroup here
+        assert!(!zkp.in_subgroup(&(&zkp.p - 1u32)));
+        assert!(!zkp.in_subgroup(&BigUint::from(2u32)));
+        assert!(!zkp.in_subgroup(&BigUint::from(0u32)));
+    }
+
+    #[test]
+    fn validate_commitment_rejects_values_outside_the_group() {
+        let zkp = ZKP::new();
+        assert!(zkp.validate_commitment(&BigUint::one()));
+        assert!(zkp.validate_commitment(&(&zkp.p - 1u32)));
+        assert!(!zkp.validate_commitment(&BigUint::from(0u32)));
+        assert!(!zkp.validate_commitment(&zkp.p));
+        assert!(!zkp.validate_commitment(&(&zkp.p + 1u32)));
+    }
+
+    #[test]
+    fn verify_rejects_tampered_transcripts_after_constant_time_comparison() {
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let k = zkp.generate_random_scalar();
+        let c = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x);
+        let bump = |r: &GroupElement| GroupElement::new(&zkp, &**r % (&zkp.p - 1u32) + 1u32).unwrap();
+
+        assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &s));
+        assert!(!zkp.verify(&bump(&r1), &r2, &y1, &y2, &c, &s));
+        assert!(!zkp.verify(&r1, &bump(&r2), &y1, &y2, &c, &s));
+        // Same value modulo p but a different width must still compare unequal; such a
+        // value cannot be a `GroupElement`, so it goes through `verify_transcript`
+        assert!(GroupElement::new(&zkp, &*r1 + &zkp.p).is_err());
+        let transcript = Transcript {
+            r1: &*r1 + &zkp.p,
+            r2: r2.into_inner(),
+            y1: y1.into_inner(),
+            y2: y2.into_inner(),
+            c: c.into_inner(),
+            s: s.into_inner(),
+        };
+        assert!(!zkp.verify_transcript(&transcript));
+    }
+
+    #[test]
+    fn verify_accepts_output_of_prove() {
+        let zkp = ZKP::new();
+        let x = ZKP::generate_random_number_below(&zkp.q);
+        let k = ZKP::generate_random_number_below(&zkp.q);
+        let c = ZKP::generate_random_number_below(&zkp.q);
+
+        let (y1, y2) = zkp.public_keys(&x);
+        let proof = zkp.prove(&x, &k, &c);
+        assert_eq!(proof.c, c);
+        assert!(typed_verify(&zkp, &proof.r1, &proof.r2, &y1, &y2, &proof.c, &proof.s));
+
+        let (other_y1, other_y2) = zkp.public_keys(&(&x + 1u32));
+        assert!(!typed_verify(&zkp, &proof.r1, &proof.r2, &other_y1, &other_y2, &proof.c, &proof.s));
+    }
+
+    #[test]
+    fn verify_with_parts_matches_verify() {
+        let zkp = ZKP::new();
+        let x = ZKP::generate_random_number_below(&zkp.q);
+        let k = ZKP::generate_random_number_below(&zkp.q);
+        let c = ZKP::generate_random_number_below(&zkp.q);
+        let honest = zkp.honest_transcript(&x, &k, &c);
+        let wrong_s = Transcript {
+            s: (&honest.s + 1u32) % &zkp.q,
+            ..honest.clone()
+        };
+        let wrong_key = Transcript {
+            y1: zkp.public_keys(&(&x + 1u32)).0,
+            ..honest.clone()
+        };
+
+        let parts = |t: &Transcript| {
+            let ys_c = (t.y1.modpow(&t.c, &zkp.p), t.y2.modpow(&t.c, &zkp.p));
+            let alpha_beta_s = (zkp.alpha.modpow(&t.s, &zkp.p), zkp.beta.modpow(&t.s, &zkp.p));
+            (ys_c, alpha_beta_s)
+        };
+        for t in [&honest, &wrong_s, &wrong_key] {
+            let (ys_c, alpha_beta_s) = parts(t);
+            assert_eq!(
+                zkp.verify_with_parts(&t.r1, &t.r2, ys_c, alpha_beta_s),
+                zkp.verify_transcript(t)
+            );
+        }
+        assert!(zkp.verify_transcript(&honest));
+
+        // Each pair is (alpha side, beta side); given beta side first, the check fails
+        let ((y1_c, y2_c), (alpha_s, beta_s)) = parts(&honest);
+        assert!(!zkp.verify_with_parts(&honest.r1, &honest.r2, (y2_c, y1_c), (beta_s, alpha_s)));
+    }
+
     #[test]
     fn generate_random_number_below_returns_value_below_limit() {
         let limit = BigUint::from(100u32);
@@ -171,10 +1992,203 @@ mod tests {
         assert!(random_number < limit);
     }
 
+    #[test]
+    fn generate_nonzero_below_never_returns_zero() {
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        for limit in [2u32, 3, 17] {
+            let limit = BigUint::from(limit);
+            let mut seen = vec![0u32; 17];
+            for _ in 0..2000 {
+                let value = ZKP::generate_nonzero_below_with(&mut rng, &limit);
+                assert!(value.bits() != 0 && value < limit, "{} for limit {}", value, limit);
+                seen[value.to_u32_digits()[0] as usize] += 1;
+            }
+            // Every value in [1, limit) turns up, not just a few
+            let limit = limit.to_u32_digits()[0] as usize;
+            assert!(seen[1..limit].iter().all(|&count| count > 0), "{:?}", seen);
+        }
+
+        let zkp = ZKP::new();
+        for _ in 0..100 {
+            let x = zkp.generate_nonzero_scalar();
+            assert!(x.bits() != 0 && *x < zkp.q);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "no nonzero value below 1")]
+    fn generate_nonzero_below_rejects_an_empty_range() {
+        ZKP::generate_nonzero_below(&BigUint::from(1u32));
+    }
+
     #[test]
     fn generate_random_string_returns_string_of_correct_length() {
         let size = 10;
         let random_string = ZKP::generate_random_string(size);
         assert_eq!(random_string.len(), size);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn generate_token_is_unique_and_sized_by_entropy() {
+        let a = ZKP::generate_token(16);
+        let b = ZKP::generate_token(16);
+        assert_ne!(a, b);
+        assert_eq!(a.len(), 22);
+        assert_eq!(b.len(), 22);
+        assert!(a
+            .bytes()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == b'-' || ch == b'_'));
+        assert_eq!(ZKP::generate_token(32).len(), 43);
+    }
+
+    #[test]
+    fn identically_seeded_rngs_produce_identical_challenges() {
+        let zkp = ZKP::new();
+        let mut rng_a = ChaCha20Rng::seed_from_u64(42);
+        let mut rng_b = ChaCha20Rng::seed_from_u64(42);
+
+        let c_a = ZKP::generate_random_number_below_with(&mut rng_a, &zkp.q);
+        let c_b = ZKP::generate_random_number_below_with(&mut rng_b, &zkp.q);
+        assert_eq!(c_a, c_b);
+
+        let id_a = ZKP::generate_random_string_with(&mut rng_a, 12);
+        let id_b = ZKP::generate_random_string_with(&mut rng_b, 12);
+        assert_eq!(id_a, id_b);
+    }
+
+    #[test]
+    fn verify_batch_flags_the_single_invalid_proof() {
+        let zkp = ZKP::new();
+        let mut proofs = honest_batch(&zkp, 100);
+        proofs[37].5 += 1u32;
+
+        let results = zkp.verify_batch(&proofs);
+        for (i, ok) in results.iter().enumerate() {
+            assert_eq!(*ok, i != 37, "unexpected result for proof {}", i);
+        }
+    }
+
+    #[test]
+    fn verify_batch_refuses_a_challenge_of_q() {
+        let zkp = ZKP::new();
+        let mut proofs = honest_batch(&zkp, 10);
+        // c = q makes y^c = 1, so r = alpha^s, beta^s balances the combined check
+        let s = BigUint::from(5u32);
+        let ((r1, r2), (y1, y2)) = (zkp.public_keys(&s), zkp.public_keys(&BigUint::from(9u32)));
+        proofs[3] = (r1, r2, y1, y2, zkp.q.clone(), s);
+        let (r1, r2, y1, y2, c, s) = &proofs[3];
+        assert!(!zkp.verify_transcript(&Transcript {
+            r1: r1.clone(),
+            r2: r2.clone(),
+            y1: y1.clone(),
+            y2: y2.clone(),
+            c: c.clone(),
+            s: s.clone(),
+        }));
+
+        let results = zkp.verify_batch(&proofs);
+        for (i, ok) in results.iter().enumerate() {
+            assert_eq!(*ok, i != 3, "unexpected result for proof {}", i);
+        }
+    }
+
+    #[test]
+    fn verify_batch_refuses_a_commitment_of_p_or_more() {
+        let zkp = ZKP::new();
+        let mut proofs = honest_batch(&zkp, 10);
+        // r1 + p is r1 again once reduced, but not the value the proof was checked for
+        proofs[6].0 += &zkp.p;
+        let (r1, r2, y1, y2, c, s) = &proofs[6];
+        assert!(!zkp.verify_transcript(&Transcript {
+            r1: r1.clone(),
+            r2: r2.clone(),
+            y1: y1.clone(),
+            y2: y2.clone(),
+            c: c.clone(),
+            s: s.clone(),
+        }));
+
+        let results = zkp.verify_batch(&proofs);
+        for (i, ok) in results.iter().enumerate() {
+            assert_eq!(*ok, i != 6, "unexpected result for proof {}", i);
+        }
+    }
+
+    #[test]
+    fn verify_batch_compared_to_single_verifies() {
+        let zkp = ZKP::new();
+        let proofs = honest_batch(&zkp, 100);
+
+        // The timing comparison is the `batch` benchmark
+        let batch = zkp.verify_batch(&proofs);
+        let single: Vec<bool> = proofs
+            .iter()
+            .map(|(r1, r2, y1, y2, c, s)| typed_verify(&zkp, r1, r2, y1, y2, c, s))
+            .collect();
+        assert_eq!(batch, single);
+        assert!(batch.iter().all(|ok| *ok));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn proof_round_trips_through_json() {
+        let zkp = ZKP::new();
+        let proof = Proof {
+            r1: ZKP::generate_random_number_below(&zkp.p),
+            r2: ZKP::generate_random_number_below(&zkp.p),
+            c: ZKP::generate_random_number_below(&zkp.q),
+            s: BigUint::from(0x0au32),
+        };
+
+        let json = proof.to_json().unwrap();
+        assert!(json.contains("\"s\":\"a\""));
+        assert_eq!(Proof::from_json(&json).unwrap(), proof);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn zkp_round_trips_through_json() {
+        let zkp = ZKP::new();
+        let json = serde_json::to_string(&zkp).unwrap();
+        assert!(json.contains(&format!("\"q\":\"{:x}\"", zkp.q)));
+
+        let decoded: ZKP = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.alpha, zkp.alpha);
+        assert_eq!(decoded.beta, zkp.beta);
+        assert_eq!(decoded.p, zkp.p);
+        assert_eq!(decoded.q, zkp.q);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn group_params_serialize_as_hex() {
+        let params = ZKP::new().params();
+        let json = serde_json::to_string(&params).unwrap();
+        assert!(json.contains(&format!("\"alpha\":\"{:x}\"", params.alpha)));
+        assert_eq!(serde_json::from_str::<GroupParams>(&json).unwrap(), params);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn registration_bundle_round_trips_through_json() {
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let bundle = zkp.registration_bundle("alice", &x);
+
+        let path = std::env::temp_dir().join(format!("bundle-{}.json", std::process::id()));
+        bundle.to_file(&path).unwrap();
+        let decoded = RegistrationBundle::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(decoded.user, "alice");
+        assert_eq!((decoded.y1, decoded.y2), zkp.public_keys(&x));
+        assert!(RegistrationBundle::from_json(r#"{"user":"alice","y1":"1"}"#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn proof_from_json_rejects_non_hex_fields() {
+        let json = r#"{"r1":"zz","r2":"1","c":"1","s":"1"}"#;
+        assert!(Proof::from_json(json).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+//! Modular exponentiation for `arith::verify`, which makes four per proof, and the
+//! fixed-base tables `ZkpBuilder::precompute` builds for `compute_pair`.
+//!
+//! With the `sliding-window` feature, `modpow` uses the sliding-window method below;
+//! without it, `BigUint::modpow`. Compare the two with `cargo bench -p chaum_pedersen
+//! --bench zkp -- modexp`. Neither runs in constant time, so only `verify`, whose
+//! exponents `s` and `c` are public, goes through `modpow`.
+
+use alloc::vec::Vec;
+
+use num_bigint::BigUint;
+use num_traits::One;
+
+/// Returns `base^exp mod modulus`. Panics if `modulus` is zero, as `BigUint::modpow` does.
+pub fn modpow(base: &BigUint, exp: &BigUint, modulus: &BigUint) -> BigUint {
+    #[cfg(feature = "sliding-window")]
+    {
+        sliding_window(base, exp, modulus)
+    }
+    #[cfg(not(feature = "sliding-window"))]
+    {
+        base.modpow(exp, modulus)
+    }
+}
+
+/// Left-to-right sliding-window exponentiation.
+///
+/// The odd powers `base^1, base^3, ..., base^(2^w - 1)` are computed up front, then each
+/// run of up to `w` exponent bits that starts and ends with a 1 costs one multiplication
+/// instead of one per set bit.
+pub fn sliding_window(base: &BigUint, exp: &BigUint, modulus: &BigUint) -> BigUint {
+    assert!(*modulus != BigUint::ZERO, "divide by zero!");
+    let one = BigUint::one() % modulus;
+    let bits = exp.bits();
+    if bits == 0 {
+        return one;
+    }
+
+    let w = window_bits(bits);
+    let base = base % modulus;
+    let square = (&base * &base) % modulus;
+    let mut odd_powers = Vec::with_capacity(1 << (w - 1));
+    odd_powers.push(base);
+    for i in 1..1 << (w - 1) {
+        let next = (&odd_powers[i - 1] * &square) % modulus;
+        odd_powers.push(next);
+    }
+
+    let mut result = one;
+    let mut i = bits;
+    while i > 0 {
+        if !exp.bit(i - 1) {
+            result = (&result * &result) % modulus;
+            i -= 1;
+            continue;
+        }
+        // The longest window [low, i) of at most `w` bits whose lowest bit is set
+        let mut low = i.saturating_sub(w);
+        while !exp.bit(low) {
+            low += 1;
+        }
+        let mut window = 0usize;
+        for bit in (low..i).rev() {
+            result = (&result * &result) % modulus;
+            window = (window << 1) | exp.bit(bit) as usize;
+        }
+        result = (&result * &odd_powers[window >> 1]) % modulus;
+        i = low;
+    }
+    result
+}
+
+/// Bits of the exponent each `FixedBase` row covers.
+const FIXED_WINDOW: usize = 4;
+
+/// Powers of one base, so raising it to an exponent of up to `bits` bits takes one
+/// multiplication per 4 exponent bits and no squarings.
+///
+/// Row `i` holds `base^(j * 16^i)` for `j` in `1..16`, so `base^e` is the product of one
+/// entry per nonzero hex digit of `e`. The table takes `15 * bits / 4` group elements.
+/// Which entries are read depends on the exponent, as with `BigUint::modpow`'s window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedBase {
+    base: BigUint,
+    modulus: BigUint,
+    rows: Vec<Vec<BigUint>>,
+}
+
+impl FixedBase {
+    /// Tabulates `base` mod `modulus` for exponents below `2^bits`.
+    pub fn new(base: &BigUint, modulus: &BigUint, bits: u64) -> Self {
+        let mut rows = Vec::new();
+        let mut row_base = base % modulus;
+        for _ in 0..bits.div_ceil(FIXED_WINDOW as u64) {
+            let mut row = Vec::with_capacity((1 << FIXED_WINDOW) - 1);
+            row.push(row_base.clone());
+            for j in 1..(1 << FIXED_WINDOW) - 1 {
+                let next = (&row[j - 1] * &row_base) % modulus;
+                row.push(next);
+            }
+            // base^(16^(i + 1)) = base^(15 * 16^i) * base^(16^i)
+            row_base = (&row[row.len() - 1] * &row_base) % modulus;
+            rows.push(row);
+        }
+        FixedBase {
+            base: base.clone(),
+            modulus: modulus.clone(),
+            rows,
+        }
+    }
+
+    /// Returns whether this is the table of `base` mod `modulus`.
+    pub fn is_for(&self, base: &BigUint, modulus: &BigUint) -> bool {
+        self.base == *base && self.modulus == *modulus
+    }
+
+    /// Returns `base^exp mod modulus`, falling back to `BigUint::modpow` for an exponent
+    /// wider than the table.
+    pub fn pow(&self, exp: &BigUint) -> BigUint {
+        if exp.bits() > (self.rows.len() * FIXED_WINDOW) as u64 {
+            return self.base.modpow(exp, &self.modulus);
+        }
+        let mut result = BigUint::one() % &self.modulus;
+        let digits = exp
+            .to_bytes_le()
+            .into_iter()
+            .flat_map(|byte| [byte & 0xf, byte >> 4]);
+        for (row, digit) in self.rows.iter().zip(digits) {
+            if digit != 0 {
+                result = (result * &row[digit as usize - 1]) % &self.modulus;
+            }
+        }
+        result
+    }
+}
+
+/// The window width for an exponent of `bits` bits, trading the table's size against
+/// the multiplications it saves.
+fn window_bits(bits: u64) -> u64 {
+    match bits {
+        0..=32 => 3,
+        33..=128 => 4,
+        129..=512 => 5,
+        _ => 6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::RandBigInt;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn sliding_window_matches_biguint_modpow() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0x5eed);
+        for _ in 0..2000 {
+            let modulus_bits = rng.gen_range(1..=1024);
+            let modulus = rng.gen_biguint(modulus_bits) + 1u32;
+            let base_bits = rng.gen_range(0..=1100);
+            let base = rng.gen_biguint(base_bits);
+            let exp_bits = rng.gen_range(0..=200);
+            let exp = rng.gen_biguint(exp_bits);
+            assert_eq!(
+                sliding_window(&base, &exp, &modulus),
+                base.modpow(&exp, &modulus),
+                "{}^{} mod {}",
+                base,
+                exp,
+                modulus
+            );
+        }
+    }
+
+    #[test]
+    fn fixed_base_matches_biguint_modpow() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0x5eed);
+        let modulus = rng.gen_biguint(1024) + 1u32;
+        let base = rng.gen_biguint(1024);
+        let table = FixedBase::new(&base, &modulus, 160);
+        assert!(table.is_for(&base, &modulus));
+        for bits in [0, 1, 4, 63, 159, 160, 161, 300] {
+            let exp = rng.gen_biguint(bits);
+            assert_eq!(table.pow(&exp), base.modpow(&exp, &modulus), "{} bits", bits);
+        }
+        assert_eq!(table.pow(&BigUint::ZERO), BigUint::one());
+        assert_eq!(FixedBase::new(&base, &BigUint::one(), 8).pow(&BigUint::from(3u32)), BigUint::ZERO);
+    }
+
+    #[test]
+    fn sliding_window_edge_cases() {
+        let (zero, one, seven) = (BigUint::ZERO, BigUint::one(), BigUint::from(7u32));
+        assert_eq!(sliding_window(&seven, &zero, &one), zero);
+        assert_eq!(sliding_window(&seven, &zero, &seven), one);
+        assert_eq!(sliding_window(&zero, &zero, &seven), one);
+        assert_eq!(sliding_window(&zero, &seven, &seven), zero);
+        assert_eq!(sliding_window(&seven, &seven, &seven), zero);
+    }
+}
@@ -0,0 +1,78 @@
+//! A `BigUint` holder for secret exponents (`x`, `k`) that is wiped when dropped.
+
+use alloc::vec;
+use core::{fmt, ops::Deref};
+
+use num_bigint::BigUint;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// A secret exponent that overwrites its limbs with zeros on drop.
+///
+/// It derefs to `BigUint`, so it can be passed wherever `&BigUint` is expected. `BigUint`
+/// keeps its limbs private, so the wipe goes through `assign_from_slice` rather than
+/// volatile writes, and copies made by arithmetic on the value are not covered.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret(BigUint);
+
+impl Secret {
+    pub fn new(value: BigUint) -> Self {
+        Secret(value)
+    }
+}
+
+impl From<BigUint> for Secret {
+    fn from(value: BigUint) -> Self {
+        Secret(value)
+    }
+}
+
+impl Deref for Secret {
+    type Target = BigUint;
+
+    fn deref(&self) -> &BigUint {
+        &self.0
+    }
+}
+
+impl Zeroize for Secret {
+    fn zeroize(&mut self) {
+        let zeros = vec![0u32; self.0.iter_u32_digits().len()];
+        self.0.assign_from_slice(&zeros);
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Secret {}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+
+    #[test]
+    fn derefs_for_arithmetic_and_wipes_on_zeroize() {
+        assert_zeroize_on_drop::<Secret>();
+
+        let mut secret = Secret::from(BigUint::from(u64::MAX) * 3u32);
+        let doubled: BigUint = &*secret * 2u32;
+        assert_eq!(doubled, BigUint::from(u64::MAX) * 6u32);
+        let squared_mod_7 = secret.modpow(&BigUint::from(2u32), &BigUint::from(7u32));
+        assert_eq!(squared_mod_7, BigUint::from(2u32));
+        assert_eq!(format!("{:?}", secret), "Secret(<redacted>)");
+
+        secret.zeroize();
+        assert_eq!(*secret, BigUint::default());
+    }
+}
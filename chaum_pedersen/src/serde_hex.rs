@@ -0,0 +1,15 @@
+//! Serde helpers encoding `BigUint` fields as lowercase hex strings, since `BigUint`'s own
+//! serde impl is a list of limbs that other languages can't easily read.
+
+use num_bigint::BigUint;
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(value: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:x}", value))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    BigUint::parse_bytes(s.as_bytes(), 16)
+        .ok_or_else(|| D::Error::custom(format!("invalid hex integer '{}'", s)))
+}
@@ -0,0 +1,1429 @@
+//! The register / challenge / verify flow, independent of any transport.
+//!
+//! `AuthService` keeps users, pending challenges and sessions in memory and takes the same
+//! byte encodings a `ZkpGroup` produces, so a gRPC, WebSocket or in-process front end only
+//! has to move bytes and map `AuthError` onto its own error type.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::{self, Future},
+    pin::Pin,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{ZkpGroup, ZKP};
+
+/// How long a session stays valid after a successful login.
+pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long the refresh token issued with a login can be exchanged for new sessions.
+pub const DEFAULT_REFRESH_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How long a session stays elevated after a step-up proof.
+pub const DEFAULT_STEP_UP_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// How long an issued challenge can still be answered.
+pub const DEFAULT_CHALLENGE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// How many challenges a single user may request within `DEFAULT_CHALLENGE_WINDOW`.
+pub const DEFAULT_CHALLENGE_LIMIT: usize = 10;
+
+/// Length of the sliding window used to rate-limit challenge requests.
+pub const DEFAULT_CHALLENGE_WINDOW: Duration = Duration::from_secs(60);
+
+/// The key slot `register`, `create_challenge` and `rotate_keys` use, and the one a
+/// request naming no slot means.
+pub const DEFAULT_SLOT: &str = "default";
+
+/// Source of the current time, so expiry can be tested without sleeping.
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Why an `AuthService` call was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// A required field was sent with no bytes, which would otherwise decode to zero.
+    EmptyField(String),
+    /// The field does not decode to a group element.
+    InvalidElement(String),
+    /// The commitment decodes but lies outside the group.
+    InvalidCommitment(String),
+    /// The field does not decode to a scalar.
+    InvalidScalar(String),
+    /// The public keys belong to the secret `x = 0`, which any response would satisfy.
+    TrivialPublicKey,
+    /// The public key is a group element but outside the subgroup alpha and beta generate.
+    NotInSubgroup(String),
+    UserAlreadyExists(String),
+    UserNotFound(String),
+    /// The user already has public keys under this slot name.
+    SlotAlreadyExists(String),
+    /// The user has no public keys under this slot name.
+    SlotNotFound(String),
+    /// The user asked for more than `challenge_limit` challenges within `challenge_window`.
+    RateLimited(String),
+    ChallengeNotFound(String),
+    ChallengeExpired(String),
+    /// The client echoed a different challenge than the one issued for this auth id.
+    ChallengeMismatch(String),
+    /// The user already sent these commitments for an earlier challenge. Answering two
+    /// challenges for the same nonce reveals the secret.
+    CommitmentReused(String),
+    IncorrectSolution(String),
+    /// The solution is wrong but would verify with `y1` and `y2` exchanged, which points at
+    /// a client that registered its keys in the wrong order. Only reported when
+    /// `integrity_checks` is on.
+    SwappedPublicKeys(String),
+    SessionNotFound,
+    SessionExpired,
+    RefreshTokenNotFound,
+    RefreshTokenExpired,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::EmptyField(field) => write!(f, "'{}' must not be empty", field),
+            AuthError::InvalidElement(field) => {
+                write!(f, "'{}' is not a valid group element", field)
+            }
+            AuthError::InvalidCommitment(field) => {
+                write!(f, "'{}' is not an element of the group", field)
+            }
+            AuthError::InvalidScalar(field) => write!(f, "'{}' is not a valid scalar", field),
+            AuthError::TrivialPublicKey => {
+                write!(f, "Public keys for the secret x = 0 are not allowed")
+            }
+            AuthError::NotInSubgroup(field) => {
+                write!(f, "'{}' is not in the order-q subgroup", field)
+            }
+            AuthError::UserAlreadyExists(user) => {
+                write!(f, "User '{}' is already registered", user)
+            }
+            AuthError::UserNotFound(user) => write!(f, "User '{}' not found", user),
+            AuthError::SlotAlreadyExists(slot) => {
+                write!(f, "Key slot '{}' is already registered", slot)
+            }
+            AuthError::SlotNotFound(slot) => write!(f, "Key slot '{}' not found", slot),
+            AuthError::RateLimited(user) => {
+                write!(f, "Too many challenge requests for user '{}'", user)
+            }
+            AuthError::ChallengeNotFound(auth_id) => write!(f, "AuthId '{}' not found", auth_id),
+            AuthError::ChallengeExpired(auth_id) => write!(f, "AuthId '{}' has expired", auth_id),
+            AuthError::ChallengeMismatch(auth_id) => {
+                write!(f, "AuthId '{}' was issued a different challenge", auth_id)
+            }
+            AuthError::CommitmentReused(user) => {
+                write!(f, "User '{}' already sent these commitments", user)
+            }
+            AuthError::IncorrectSolution(auth_id) => {
+                write!(f, "AuthId '{}' has an incorrect challenge solution", auth_id)
+            }
+            AuthError::SwappedPublicKeys(auth_id) => write!(
+                f,
+                "AuthId '{}' has an incorrect challenge solution, which would verify with y1 \
+                 and y2 swapped",
+                auth_id
+            ),
+            // Session ids and refresh tokens are bearer credentials, so they are not echoed back
+            AuthError::SessionNotFound => write!(f, "Session not found"),
+            AuthError::SessionExpired => write!(f, "Session has expired"),
+            AuthError::RefreshTokenNotFound => write!(f, "Refresh token not found"),
+            AuthError::RefreshTokenExpired => write!(f, "Refresh token has expired"),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Settings for an `AuthService`. `AuthConfig::default()` gives the stock server behavior.
+#[derive(Debug)]
+pub struct AuthConfig<G: ZkpGroup = ZKP> {
+    pub group: G,
+    pub challenge_ttl: Duration,
+    pub session_ttl: Duration,
+    pub refresh_ttl: Duration,
+    /// How long a session stays elevated after `AuthService::verify_step_up`.
+    pub step_up_ttl: Duration,
+    pub challenge_limit: usize,
+    pub challenge_window: Duration,
+    pub clock: Arc<dyn Clock>,
+    /// Issue challenges for names nobody registered; the proof is then checked against
+    /// public keys supplied with the answer (see `AuthService::verify_with_keys`).
+    pub allow_unregistered_challenges: bool,
+    /// Draw challenges below `2^bits` instead of over the full scalar range, which makes
+    /// the prover's `c * x` and the verifier's `y^c` cheaper. A prover who does not know
+    /// `x` can still pass by guessing `c` before committing, with probability `2^-bits`
+    /// per attempt, so this is only sound for interactive use with `bits` well above the
+    /// number of attempts an attacker gets (128 is a common choice). `None` keeps
+    /// full-length challenges.
+    pub challenge_bits: Option<u64>,
+    /// On a failed verification, also check the solution against the registered keys in
+    /// swapped order and report `SwappedPublicKeys` if that passes. This costs two extra
+    /// exponentiations per failure and is meant for diagnosing client bugs. Commitments
+    /// outside `[1, p)` are rejected when the challenge is issued either way.
+    pub integrity_checks: bool,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig::with_group(ZKP::new())
+    }
+}
+
+impl<G: ZkpGroup> AuthConfig<G> {
+    /// The default settings, computing proofs in `group`.
+    pub fn with_group(group: G) -> Self {
+        AuthConfig {
+            group,
+            challenge_ttl: DEFAULT_CHALLENGE_TTL,
+            session_ttl: DEFAULT_SESSION_TTL,
+            refresh_ttl: DEFAULT_REFRESH_TTL,
+            step_up_ttl: DEFAULT_STEP_UP_TTL,
+            challenge_limit: DEFAULT_CHALLENGE_LIMIT,
+            challenge_window: DEFAULT_CHALLENGE_WINDOW,
+            clock: Arc::new(SystemClock),
+            allow_unregistered_challenges: false,
+            challenge_bits: None,
+            integrity_checks: false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct UserInfo<G: ZkpGroup = ZKP> {
+    pub user_name: String,
+    /// The user's public keys `(y1, y2)` by slot name, e.g. one pair per device. Each slot
+    /// has its own secret, and a proof for any of them logs the user in.
+    pub slots: HashMap<String, (G::Element, G::Element)>,
+}
+
+impl<G: ZkpGroup> fmt::Debug for UserInfo<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserInfo")
+            .field("user_name", &self.user_name)
+            .field("slots", &self.slots)
+            .finish()
+    }
+}
+
+/// A challenge waiting for its answer, with the commitments it was issued for. Each
+/// `auth_id` has its own, so one user can have several logins in flight.
+#[derive(Debug)]
+pub struct PendingChallenge<G: ZkpGroup = ZKP> {
+    pub user_name: String,
+    pub issued_at: Instant,
+    pub r1: G::Element,
+    pub r2: G::Element,
+    pub c: G::Scalar,
+    /// The key slot whose keys the answer is checked against.
+    pub slot: String,
+    /// The name was not registered, so the answer is checked against keys sent with it.
+    pub unregistered: bool,
+    /// For a step-up challenge, the session it elevates. These cannot be answered to log in.
+    pub step_up: Option<String>,
+}
+
+impl<G: ZkpGroup> Clone for PendingChallenge<G> {
+    fn clone(&self) -> Self {
+        PendingChallenge {
+            user_name: self.user_name.clone(),
+            issued_at: self.issued_at,
+            r1: self.r1.clone(),
+            r2: self.r2.clone(),
+            c: self.c.clone(),
+            slot: self.slot.clone(),
+            unregistered: self.unregistered,
+            step_up: self.step_up.clone(),
+        }
+    }
+}
+
+/// The future a `ChallengeStore` call returns.
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Where issued challenges wait for their answer, keyed by `auth_id`.
+///
+/// Verifier instances behind a load balancer that share one store (backed by Redis, say)
+/// can each check the answer to a challenge another one issued. Users and sessions are
+/// not shared this way. `issued_at` is an `Instant`, which means nothing on another host,
+/// so a store spanning hosts should expire entries itself using the `ttl` `put` is given.
+pub trait ChallengeStore<G: ZkpGroup = ZKP>: fmt::Debug + Send + Sync {
+    /// Stores a new challenge, which may be dropped once `ttl` has passed.
+    fn put(&self, auth_id: String, pending: PendingChallenge<G>, ttl: Duration)
+        -> StoreFuture<'_, ()>;
+
+    fn get<'a>(&'a self, auth_id: &'a str) -> StoreFuture<'a, Option<PendingChallenge<G>>>;
+
+    /// Takes a challenge out of the store, so it can be answered only once.
+    fn remove<'a>(&'a self, auth_id: &'a str) -> StoreFuture<'a, Option<PendingChallenge<G>>>;
+}
+
+/// The default `ChallengeStore`, local to one process. `AuthService::auth_id_to_user` is
+/// one.
+pub type InMemoryChallengeStore<G = ZKP> = Mutex<HashMap<String, PendingChallenge<G>>>;
+
+/// Adds `pending`, first dropping every challenge older than `ttl` at its `issued_at`.
+fn insert_unexpired<G: ZkpGroup>(
+    challenges: &mut HashMap<String, PendingChallenge<G>>,
+    auth_id: String,
+    pending: PendingChallenge<G>,
+    ttl: Duration,
+) {
+    let now = pending.issued_at;
+    challenges.retain(|_, earlier| now - earlier.issued_at < ttl);
+    challenges.insert(auth_id, pending);
+}
+
+impl<G: ZkpGroup + fmt::Debug> ChallengeStore<G> for InMemoryChallengeStore<G> {
+    fn put(
+        &self,
+        auth_id: String,
+        pending: PendingChallenge<G>,
+        ttl: Duration,
+    ) -> StoreFuture<'_, ()> {
+        insert_unexpired(&mut self.lock().unwrap(), auth_id, pending, ttl);
+        Box::pin(future::ready(()))
+    }
+
+    fn get<'a>(&'a self, auth_id: &'a str) -> StoreFuture<'a, Option<PendingChallenge<G>>> {
+        Box::pin(future::ready(self.lock().unwrap().get(auth_id).cloned()))
+    }
+
+    fn remove<'a>(&'a self, auth_id: &'a str) -> StoreFuture<'a, Option<PendingChallenge<G>>> {
+        Box::pin(future::ready(self.lock().unwrap().remove(auth_id)))
+    }
+}
+
+#[derive(Debug)]
+pub struct Session {
+    pub user_name: String,
+    pub created_at: Instant,
+    /// When the session last passed a step-up proof, if ever.
+    pub elevated_at: Option<Instant>,
+    /// For a session, the refresh token of the login it came from, so revoking the session
+    /// can revoke that too. `None` in `refresh_tokens` itself.
+    pub refresh_token: Option<String>,
+}
+
+/// A challenge issued by `AuthService::create_challenge`.
+#[derive(Debug)]
+pub struct Challenge<G: ZkpGroup = ZKP> {
+    pub auth_id: String,
+    pub c: G::Scalar,
+}
+
+/// The session opened by a successful `AuthService::verify`, and the refresh token that
+/// `AuthService::refresh_session` accepts for further sessions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Login {
+    pub user_name: String,
+    pub session_id: String,
+    pub refresh_token: String,
+}
+
+/// What a correct answer to `AuthService::verify` proved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// A registered user logged in.
+    Login(Login),
+    /// The answer to a challenge for an unregistered name checked out against the public
+    /// keys sent with it. No session or refresh token is issued: the name belongs to no
+    /// one, and whoever registers it later must not find someone else already signed in.
+    ProofOnly { user_name: String },
+}
+
+impl Verification {
+    /// The name the verified challenge was issued to.
+    pub fn user_name(&self) -> &str {
+        match self {
+            Verification::Login(login) => &login.user_name,
+            Verification::ProofOnly { user_name } => user_name,
+        }
+    }
+}
+
+/// A live session as `AuthService::list_sessions` reports it. The session id itself is
+/// left out, since it is a bearer token; `session_ref` names the session instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionSummary {
+    /// Hex SHA-256 of the session id; see `AuthService::session_ref`.
+    pub session_ref: String,
+    pub age: Duration,
+    /// Whether the session passed a step-up proof within `step_up_ttl`.
+    pub elevated: bool,
+}
+
+/// Counts reported by `AuthService::stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub user_count: usize,
+    /// Challenges issued but neither answered nor expired.
+    pub pending_challenges: usize,
+}
+
+/// In-memory verifier state and the protocol steps that act on it.
+///
+/// Users sit behind a read-mostly map with a lock per user, so challenges and proof checks
+/// for different users run concurrently; only registration takes the map's write lock.
+#[derive(Debug)]
+pub struct AuthService<G: ZkpGroup = ZKP> {
+    pub group: G,
+    pub user_info: RwLock<HashMap<String, Mutex<UserInfo<G>>>>,
+    pub auth_id_to_user: Arc<InMemoryChallengeStore<G>>,
+    pub challenge_ttl: Duration,
+    pub sessions: RwLock<HashMap<String, Session>>,
+    pub session_ttl: Duration,
+    /// Refresh tokens, with `created_at` the login that issued them.
+    pub refresh_tokens: RwLock<HashMap<String, Session>>,
+    pub refresh_ttl: Duration,
+    pub step_up_ttl: Duration,
+    pub challenge_requests: Mutex<HashMap<String, VecDeque<Instant>>>,
+    /// SHA-256 of each `(r1, r2)` a registered user was issued a challenge for within the
+    /// last `challenge_ttl`, with when it was issued. Older ones are forgotten to bound the
+    /// map, so only a nonce repeated within that window is caught.
+    pub used_commitments: Mutex<HashMap<String, HashMap<[u8; 32], Instant>>>,
+    pub challenge_limit: usize,
+    pub challenge_window: Duration,
+    pub clock: Arc<dyn Clock>,
+    pub allow_unregistered_challenges: bool,
+    pub challenge_bits: Option<u64>,
+    pub integrity_checks: bool,
+}
+
+impl Default for AuthService {
+    fn default() -> Self {
+        AuthService::new(AuthConfig::default())
+    }
+}
+
+impl<G: ZkpGroup> AuthService<G> {
+    /// Creates a service with no registered users, configured by `config`.
+    pub fn new(config: AuthConfig<G>) -> Self {
+        AuthService {
+            group: config.group,
+            user_info: RwLock::default(),
+            auth_id_to_user: Arc::default(),
+            challenge_ttl: config.challenge_ttl,
+            sessions: RwLock::default(),
+            session_ttl: config.session_ttl,
+            refresh_tokens: RwLock::default(),
+            refresh_ttl: config.refresh_ttl,
+            step_up_ttl: config.step_up_ttl,
+            challenge_requests: Mutex::default(),
+            used_commitments: Mutex::default(),
+            challenge_limit: config.challenge_limit,
+            challenge_window: config.challenge_window,
+            clock: config.clock,
+            allow_unregistered_challenges: config.allow_unregistered_challenges,
+            challenge_bits: config.challenge_bits,
+            integrity_checks: config.integrity_checks,
+        }
+    }
+
+    pub fn with_group(group: G) -> Self {
+        AuthService::new(AuthConfig::with_group(group))
+    }
+
+    /// Stores the public keys `y1`, `y2` for a new user, in `DEFAULT_SLOT`.
+    pub fn register(&self, user: &str, y1: &[u8], y2: &[u8]) -> Result<(), AuthError> {
+        self.register_slot(user, DEFAULT_SLOT, y1, y2)
+    }
+
+    /// Stores the public keys `y1`, `y2` for a new user, in `slot`. Further slots are
+    /// added with `add_slot`. Any session or refresh token still held under the name is
+    /// revoked, since it was not opened with these keys.
+    pub fn register_slot(
+        &self,
+        user: &str,
+        slot: &str,
+        y1: &[u8],
+        y2: &[u8],
+    ) -> Result<(), AuthError> {
+        let keys = self.decode_public_keys(y1, y2)?;
+        let user_info = UserInfo {
+            user_name: user.to_string(),
+            slots: HashMap::from([(slot.to_string(), keys)]),
+        };
+
+        let mut user_info_map = self.user_info.write().unwrap();
+        if user_info_map.contains_key(user) {
+            return Err(AuthError::UserAlreadyExists(user.to_string()));
+        }
+        user_info_map.insert(user.to_string(), Mutex::new(user_info));
+        drop(user_info_map);
+        self.revoke_all_sessions(user);
+        Ok(())
+    }
+
+    /// Stores the public keys `y1`, `y2` under a new `slot` of a registered user, checking
+    /// them as `register` does. Sessions opened with the other slots stay live. The caller
+    /// must have authenticated the user first.
+    pub fn add_slot(&self, user: &str, slot: &str, y1: &[u8], y2: &[u8]) -> Result<(), AuthError> {
+        let keys = self.decode_public_keys(y1, y2)?;
+        let user_info_map = self.user_info.read().unwrap();
+        let mut user_info = user_info_map
+            .get(user)
+            .ok_or_else(|| AuthError::UserNotFound(user.to_string()))?
+            .lock()
+            .unwrap();
+        if user_info.slots.contains_key(slot) {
+            return Err(AuthError::SlotAlreadyExists(slot.to_string()));
+        }
+        user_info.slots.insert(slot.to_string(), keys);
+        Ok(())
+    }
+
+    /// Replaces the public keys of a registered user in `DEFAULT_SLOT`; see
+    /// `rotate_slot_keys`.
+    pub fn rotate_keys(&self, user: &str, y1: &[u8], y2: &[u8]) -> Result<(), AuthError> {
+        self.rotate_slot_keys(user, DEFAULT_SLOT, y1, y2)
+    }
+
+    /// Replaces the public keys in `slot` of a registered user, checking the new ones as
+    /// `register` does, and ends every session and refresh token of the user, whichever
+    /// slot they were opened with. The caller must have authenticated the user first.
+    pub fn rotate_slot_keys(
+        &self,
+        user: &str,
+        slot: &str,
+        y1: &[u8],
+        y2: &[u8],
+    ) -> Result<(), AuthError> {
+        let keys = self.decode_public_keys(y1, y2)?;
+        {
+            let user_info_map = self.user_info.read().unwrap();
+            let mut user_info = user_info_map
+                .get(user)
+                .ok_or_else(|| AuthError::UserNotFound(user.to_string()))?
+                .lock()
+                .unwrap();
+            *user_info
+                .slots
+                .get_mut(slot)
+                .ok_or_else(|| AuthError::SlotNotFound(slot.to_string()))? = keys;
+        }
+        self.sessions
+            .write()
+            .unwrap()
+            .retain(|_, session| session.user_name != user);
+        self.refresh_tokens
+            .write()
+            .unwrap()
+            .retain(|_, token| token.user_name != user);
+        Ok(())
+    }
+
+    /// Decodes public keys sent to `register`, `add_slot` or `rotate_keys`, refusing
+    /// trivial keys and keys outside the subgroup.
+    fn decode_public_keys(
+        &self,
+        y1: &[u8],
+        y2: &[u8],
+    ) -> Result<(G::Element, G::Element), AuthError> {
+        let y1 = self.decode_element("y1", y1)?;
+        let y2 = self.decode_element("y2", y2)?;
+        if self.group.is_trivial_public_key(&y1, &y2) {
+            return Err(AuthError::TrivialPublicKey);
+        }
+        self.check_subgroup(&y1, &y2)?;
+        Ok((y1, y2))
+    }
+
+    /// Records the commitments `r1`, `r2` and issues a fresh challenge for them, to be
+    /// answered with the secret of `DEFAULT_SLOT`. Commitments the user sent before are
+    /// refused, whether or not that challenge was answered.
+    pub fn create_challenge(
+        &self,
+        user: &str,
+        r1: &[u8],
+        r2: &[u8],
+    ) -> Result<Challenge<G>, AuthError> {
+        self.create_slot_challenge(user, DEFAULT_SLOT, r1, r2)
+    }
+
+    /// `create_challenge` for the keys in `slot`. Commitments are tracked per user, so
+    /// they may not repeat across slots either.
+    pub fn create_slot_challenge(
+        &self,
+        user: &str,
+        slot: &str,
+        r1: &[u8],
+        r2: &[u8],
+    ) -> Result<Challenge<G>, AuthError> {
+        let (challenge, pending) = self.issue_slot_challenge(user, slot, r1, r2)?;
+        // Drop challenges nobody answered in time before adding a new one
+        insert_unexpired(
+            &mut self.auth_id_to_user.lock().unwrap(),
+            challenge.auth_id.clone(),
+            pending,
+            self.challenge_ttl,
+        );
+        Ok(challenge)
+    }
+
+    /// `create_challenge` without storing the challenge, for a caller that keeps pending
+    /// challenges in a `ChallengeStore` of its own.
+    pub fn issue_challenge(
+        &self,
+        user: &str,
+        r1: &[u8],
+        r2: &[u8],
+    ) -> Result<(Challenge<G>, PendingChallenge<G>), AuthError> {
+        self.issue_slot_challenge(user, DEFAULT_SLOT, r1, r2)
+    }
+
+    /// `create_slot_challenge` without storing the challenge.
+    pub fn issue_slot_challenge(
+        &self,
+        user: &str,
+        slot: &str,
+        r1: &[u8],
+        r2: &[u8],
+    ) -> Result<(Challenge<G>, PendingChallenge<G>), AuthError> {
+        let unregistered = match self.user_info.read().unwrap().get(user) {
+            Some(user_info) if user_info.lock().unwrap().slots.contains_key(slot) => false,
+            Some(_) => return Err(AuthError::SlotNotFound(slot.to_string())),
+            None if self.allow_unregistered_challenges => true,
+            None => return Err(AuthError::UserNotFound(user.to_string())),
+        };
+
+        self.check_challenge_rate(user)?;
+
+        let r1 = self.decode_commitment("r1", r1)?;
+        let r2 = self.decode_commitment("r2", r2)?;
+        if !unregistered {
+            self.record_commitments(user, &r1, &r2)?;
+        }
+        let c = match self.challenge_bits {
+            Some(bits) => self.group.generate_short_challenge(bits),
+            None => self.group.generate_challenge(),
+        };
+        let pending = PendingChallenge {
+            user_name: user.to_string(),
+            issued_at: self.clock.now(),
+            r1,
+            r2,
+            c: c.clone(),
+            slot: slot.to_string(),
+            unregistered,
+            step_up: None,
+        };
+
+        Ok((Challenge { auth_id: ZKP::generate_token(16), c }, pending))
+    }
+
+    /// Remembers `(r1, r2)` for `user`, failing if they were recorded within the last
+    /// `challenge_ttl`. Expired records of every user are dropped first.
+    fn record_commitments(
+        &self,
+        user: &str,
+        r1: &G::Element,
+        r2: &G::Element,
+    ) -> Result<(), AuthError> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.group.element_to_bytes(r1));
+        hasher.update(self.group.element_to_bytes(r2));
+        let now = self.clock.now();
+        let mut used_commitments = self.used_commitments.lock().unwrap();
+        used_commitments.retain(|_, issued| {
+            issued.retain(|_, issued_at| now - *issued_at < self.challenge_ttl);
+            !issued.is_empty()
+        });
+        let issued = used_commitments.entry(user.to_string()).or_default();
+        let hash = hasher.finalize().into();
+        if issued.contains_key(&hash) {
+            return Err(AuthError::CommitmentReused(user.to_string()));
+        }
+        issued.insert(hash, now);
+        Ok(())
+    }
+
+    /// Checks that `c` is the challenge issued as `auth_id`, so a client that answers some
+    /// other challenge is told so rather than failing verification. The challenge is left
+    /// pending.
+    pub fn check_challenge(&self, auth_id: &str, c: &[u8]) -> Result<(), AuthError> {
+        let c = self.decode_scalar("c", c)?;
+
+        let auth_map = self.auth_id_to_user.lock().unwrap();
+        let pending = auth_map
+            .get(auth_id)
+            .ok_or_else(|| AuthError::ChallengeNotFound(auth_id.to_string()))?;
+        self.check_pending(auth_id, pending, &c)
+    }
+
+    /// The comparison `check_challenge` makes, against a challenge the caller looked up.
+    pub fn check_pending(
+        &self,
+        auth_id: &str,
+        pending: &PendingChallenge<G>,
+        c: &G::Scalar,
+    ) -> Result<(), AuthError> {
+        if self.group.scalar_to_bytes(c) != self.group.scalar_to_bytes(&pending.c) {
+            return Err(AuthError::ChallengeMismatch(auth_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Checks the response `s` to challenge `auth_id` and opens a session if it is correct.
+    /// The challenge is consumed by the attempt, so the same `(auth_id, s)` cannot be replayed.
+    pub fn verify(&self, auth_id: &str, s: &[u8]) -> Result<Verification, AuthError> {
+        self.verify_against(auth_id, s, None)
+    }
+
+    /// Like `verify`, but a challenge issued to an unregistered name is checked against the
+    /// public keys `y1`, `y2` given here, and opens no session
+    /// (`Verification::ProofOnly`). Registered users are still checked against the keys
+    /// stored at registration.
+    pub fn verify_with_keys(
+        &self,
+        auth_id: &str,
+        s: &[u8],
+        y1: &[u8],
+        y2: &[u8],
+    ) -> Result<Verification, AuthError> {
+        self.verify_against(auth_id, s, Some((y1, y2)))
+    }
+
+    fn verify_against(
+        &self,
+        auth_id: &str,
+        s: &[u8],
+        keys: Option<(&[u8], &[u8])>,
+    ) -> Result<Verification, AuthError> {
+        let s = self.decode_scalar("s", s)?;
+
+        let pending = self
+            .auth_id_to_user
+            .lock()
+            .unwrap()
+            .remove(auth_id)
+            .ok_or_else(|| AuthError::ChallengeNotFound(auth_id.to_string()))?;
+        self.verify_pending(auth_id, pending, &s, keys)
+    }
+
+    /// The checks `verify` and `verify_with_keys` make, against a challenge the caller has
+    /// already taken out of its store.
+    pub fn verify_pending(
+        &self,
+        auth_id: &str,
+        pending: PendingChallenge<G>,
+        s: &G::Scalar,
+        keys: Option<(&[u8], &[u8])>,
+    ) -> Result<Verification, AuthError> {
+        self.check_answer(auth_id, &pending, s, keys)?;
+        if pending.unregistered {
+            return Ok(Verification::ProofOnly {
+                user_name: pending.user_name,
+            });
+        }
+
+        let user_name = &pending.user_name;
+        let refresh_token = ZKP::generate_token(32);
+        self.refresh_tokens.write().unwrap().insert(
+            refresh_token.clone(),
+            Session {
+                user_name: user_name.clone(),
+                created_at: self.clock.now(),
+                elevated_at: None,
+                refresh_token: None,
+            },
+        );
+
+        Ok(Verification::Login(Login {
+            user_name: user_name.clone(),
+            session_id: self.open_session(user_name, &refresh_token),
+            refresh_token,
+        }))
+    }
+
+    /// The checks of `verify_pending` without opening a session, for a proof that
+    /// authorizes only the request carrying it. A challenge for an unregistered name passes
+    /// too; the caller decides what `pending.unregistered` allows.
+    pub fn check_answer(
+        &self,
+        auth_id: &str,
+        pending: &PendingChallenge<G>,
+        s: &G::Scalar,
+        keys: Option<(&[u8], &[u8])>,
+    ) -> Result<(), AuthError> {
+        if pending.step_up.is_some() {
+            return Err(AuthError::ChallengeNotFound(auth_id.to_string()));
+        }
+        self.check_proof(auth_id, pending, s, keys)
+    }
+
+    /// Issues a challenge to the owner of `session_id`, checked against the keys in `slot`,
+    /// whose correct answer elevates that session for `step_up_ttl` (see `verify_step_up`).
+    /// Like `issue_challenge`, the challenge is returned for the caller to store. Only
+    /// registered users can step up.
+    pub fn issue_step_up_challenge(
+        &self,
+        session_id: &str,
+        slot: &str,
+        r1: &[u8],
+        r2: &[u8],
+    ) -> Result<(Challenge<G>, PendingChallenge<G>), AuthError> {
+        let user = self.validate_session(session_id)?;
+        if !self.user_info.read().unwrap().contains_key(&user) {
+            return Err(AuthError::UserNotFound(user));
+        }
+        let (challenge, mut pending) = self.issue_slot_challenge(&user, slot, r1, r2)?;
+        pending.step_up = Some(session_id.to_string());
+        Ok((challenge, pending))
+    }
+
+    /// Checks the answer `s` to a challenge from `issue_step_up_challenge` for
+    /// `session_id`, and marks the session elevated if it is correct. A wrong answer leaves
+    /// the session as it was.
+    pub fn verify_step_up(
+        &self,
+        session_id: &str,
+        auth_id: &str,
+        pending: PendingChallenge<G>,
+        s: &G::Scalar,
+    ) -> Result<(), AuthError> {
+        if pending.step_up.as_deref() != Some(session_id) {
+            return Err(AuthError::ChallengeNotFound(auth_id.to_string()));
+        }
+        self.check_proof(auth_id, &pending, s, None)?;
+
+        self.validate_session(session_id)?;
+        let mut sessions = self.sessions.write().unwrap();
+        let session = sessions.get_mut(session_id).ok_or(AuthError::SessionNotFound)?;
+        session.elevated_at = Some(self.clock.now());
+        Ok(())
+    }
+
+    /// Returns whether `session_id` is live and passed a step-up proof within
+    /// `step_up_ttl`.
+    pub fn is_elevated(&self, session_id: &str) -> bool {
+        let now = self.clock.now();
+        self.sessions
+            .read()
+            .unwrap()
+            .get(session_id)
+            .filter(|session| now - session.created_at < self.session_ttl)
+            .and_then(|session| session.elevated_at)
+            .is_some_and(|elevated_at| now - elevated_at < self.step_up_ttl)
+    }
+
+    /// The expiry and proof checks of `verify_pending`, leaving the sessions alone.
+    fn check_proof(
+        &self,
+        auth_id: &str,
+        pending: &PendingChallenge<G>,
+        s: &G::Scalar,
+        keys: Option<(&[u8], &[u8])>,
+    ) -> Result<(), AuthError> {
+        if self.clock.now() - pending.issued_at >= self.challenge_ttl {
+            return Err(AuthError::ChallengeExpired(auth_id.to_string()));
+        }
+
+        let user_name = &pending.user_name;
+        let (r1, r2, c) = (&pending.r1, &pending.r2, &pending.c);
+        let verified = if pending.unregistered {
+            let (y1, y2) = keys.unwrap_or_default();
+            let y1 = self.decode_element("y1", y1)?;
+            let y2 = self.decode_element("y2", y2)?;
+            if self.group.is_trivial_public_key(&y1, &y2) {
+                return Err(AuthError::TrivialPublicKey);
+            }
+            self.check_subgroup(&y1, &y2)?;
+            self.group.verify(r1, r2, &y1, &y2, c, s)
+        } else {
+            let user_info_map = self.user_info.read().unwrap();
+            let user_info = user_info_map
+                .get(user_name)
+                .ok_or_else(|| AuthError::ChallengeNotFound(auth_id.to_string()))?
+                .lock()
+                .unwrap();
+            let (y1, y2) = user_info
+                .slots
+                .get(&pending.slot)
+                .ok_or_else(|| AuthError::ChallengeNotFound(auth_id.to_string()))?;
+
+            let verified = self.group.verify(r1, r2, y1, y2, c, s);
+            if !verified && self.integrity_checks && self.group.verify(r1, r2, y2, y1, c, s) {
+                return Err(AuthError::SwappedPublicKeys(auth_id.to_string()));
+            }
+            verified
+        };
+        if !verified {
+            return Err(AuthError::IncorrectSolution(auth_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Opens a new session for the owner of `refresh_token` without another proof. The
+    /// token stays valid until `refresh_ttl` after the login that issued it.
+    pub fn refresh_session(&self, refresh_token: &str) -> Result<Login, AuthError> {
+        match self.refresh_tokens.read().unwrap().get(refresh_token) {
+            Some(token) if self.clock.now() - token.created_at < self.refresh_ttl => {
+                return Ok(Login {
+                    user_name: token.user_name.clone(),
+                    session_id: self.open_session(&token.user_name, refresh_token),
+                    refresh_token: refresh_token.to_string(),
+                });
+            }
+            Some(_) => {}
+            None => return Err(AuthError::RefreshTokenNotFound),
+        }
+
+        self.refresh_tokens.write().unwrap().remove(refresh_token);
+        Err(AuthError::RefreshTokenExpired)
+    }
+
+    /// Removes `user` along with every session and refresh token issued to it, and the
+    /// commitments it used.
+    pub fn revoke_user(&self, user: &str) -> Result<(), AuthError> {
+        if self.user_info.write().unwrap().remove(user).is_none() {
+            return Err(AuthError::UserNotFound(user.to_string()));
+        }
+        self.sessions
+            .write()
+            .unwrap()
+            .retain(|_, session| session.user_name != user);
+        self.refresh_tokens
+            .write()
+            .unwrap()
+            .retain(|_, token| token.user_name != user);
+        self.used_commitments.lock().unwrap().remove(user);
+        Ok(())
+    }
+
+    fn open_session(&self, user_name: &str, refresh_token: &str) -> String {
+        let session_id = ZKP::generate_token(16);
+        self.sessions.write().unwrap().insert(
+            session_id.clone(),
+            Session {
+                user_name: user_name.to_string(),
+                created_at: self.clock.now(),
+                elevated_at: None,
+                refresh_token: Some(refresh_token.to_string()),
+            },
+        );
+        session_id
+    }
+
+    /// Returns the user a live session belongs to, forgetting the session once it expires.
+    pub fn validate_session(&self, session_id: &str) -> Result<String, AuthError> {
+        match self.sessions.read().unwrap().get(session_id) {
+            Some(session) if self.clock.now() - session.created_at < self.session_ttl => {
+                return Ok(session.user_name.clone());
+            }
+            Some(_) => {}
+            None => return Err(AuthError::SessionNotFound),
+        }
+
+        self.sessions.write().unwrap().remove(session_id);
+        Err(AuthError::SessionExpired)
+    }
+
+    /// Ends a session.
+    pub fn logout(&self, session_id: &str) -> Result<(), AuthError> {
+        match self.sessions.write().unwrap().remove(session_id) {
+            Some(_) => Ok(()),
+            None => Err(AuthError::SessionNotFound),
+        }
+    }
+
+    /// The name `list_sessions` gives `session_id`: its SHA-256 in hex. Anyone holding a
+    /// session can compute its reference, but not the other way round.
+    pub fn session_ref(session_id: &str) -> String {
+        hex::encode(Sha256::digest(session_id.as_bytes()))
+    }
+
+    /// The live sessions of `user`, oldest first.
+    pub fn list_sessions(&self, user: &str) -> Vec<SessionSummary> {
+        let now = self.clock.now();
+        let sessions = self.sessions.read().unwrap();
+        let mut live: Vec<_> = sessions
+            .iter()
+            .filter(|(_, session)| {
+                session.user_name == user && now - session.created_at < self.session_ttl
+            })
+            .map(|(session_id, session)| {
+                let elevated = session
+                    .elevated_at
+                    .is_some_and(|elevated_at| now - elevated_at < self.step_up_ttl);
+                (
+                    session.created_at,
+                    SessionSummary {
+                        session_ref: Self::session_ref(session_id),
+                        age: now - session.created_at,
+                        elevated,
+                    },
+                )
+            })
+            .collect();
+        live.sort_by_key(|(created_at, _)| *created_at);
+        live.into_iter().map(|(_, summary)| summary).collect()
+    }
+
+    /// Ends the session of `user` that `session_ref` names, and the refresh token of the
+    /// login it came from, which could otherwise open a new session at once. Other sessions
+    /// already opened with that token stay live until revoked themselves.
+    pub fn revoke_session(&self, user: &str, session_ref: &str) -> Result<(), AuthError> {
+        let mut sessions = self.sessions.write().unwrap();
+        let session_id = sessions
+            .iter()
+            .find(|(session_id, session)| {
+                session.user_name == user && Self::session_ref(session_id) == session_ref
+            })
+            .map(|(session_id, _)| session_id.clone())
+            .ok_or(AuthError::SessionNotFound)?;
+        let session = sessions.remove(&session_id).unwrap();
+        drop(sessions);
+        if let Some(refresh_token) = session.refresh_token {
+            self.refresh_tokens.write().unwrap().remove(&refresh_token);
+        }
+        Ok(())
+    }
+
+    /// Ends every session and refresh token of `user`, returning how many sessions there
+    /// were.
+    pub fn revoke_all_sessions(&self, user: &str) -> usize {
+        let mut sessions = self.sessions.write().unwrap();
+        let before = sessions.len();
+        sessions.retain(|_, session| session.user_name != user);
+        let revoked = before - sessions.len();
+        drop(sessions);
+        self.refresh_tokens
+            .write()
+            .unwrap()
+            .retain(|_, token| token.user_name != user);
+        revoked
+    }
+
+    /// Drops a challenge that will not be answered, e.g. because the client went away.
+    /// Returns whether it was still pending.
+    pub fn cancel_challenge(&self, auth_id: &str) -> bool {
+        self.auth_id_to_user.lock().unwrap().remove(auth_id).is_some()
+    }
+
+    /// The public keys `user` registered in `DEFAULT_SLOT`, as decoded once at
+    /// registration.
+    pub fn public_keys(&self, user: &str) -> Option<(G::Element, G::Element)> {
+        self.slot_public_keys(user, DEFAULT_SLOT)
+    }
+
+    /// The public keys `user` registered in `slot`.
+    pub fn slot_public_keys(&self, user: &str, slot: &str) -> Option<(G::Element, G::Element)> {
+        let user_info_map = self.user_info.read().unwrap();
+        let user_info = user_info_map.get(user)?.lock().unwrap();
+        user_info.slots.get(slot).cloned()
+    }
+
+    pub fn stats(&self) -> Stats {
+        let now = self.clock.now();
+        let pending_challenges = self
+            .auth_id_to_user
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|pending| now - pending.issued_at < self.challenge_ttl)
+            .count();
+        Stats {
+            user_count: self.user_info.read().unwrap().len(),
+            pending_challenges,
+        }
+    }
+
+    /// Records a challenge request for `user`, rejecting it if the user has already made
+    /// `challenge_limit` requests within the last `challenge_window`. Users with no request
+    /// left inside their window are dropped first, so the map only holds recent names.
+    fn check_challenge_rate(&self, user: &str) -> Result<(), AuthError> {
+        let now = self.clock.now();
+        let mut challenge_requests = self.challenge_requests.lock().unwrap();
+        challenge_requests.retain(|_, timestamps| {
+            timestamps
+                .back()
+                .is_some_and(|newest| now - *newest < self.challenge_window)
+        });
+        let timestamps = challenge_requests.entry(user.to_string()).or_default();
+
+        while let Some(oldest) = timestamps.front() {
+            if now - *oldest < self.challenge_window {
+                break;
+            }
+            timestamps.pop_front();
+        }
+
+        if timestamps.len() >= self.challenge_limit {
+            return Err(AuthError::RateLimited(user.to_string()));
+        }
+
+        timestamps.push_back(now);
+        Ok(())
+    }
+
+    /// Refuses public keys outside the subgroup, naming the first one that is.
+    fn check_subgroup(&self, y1: &G::Element, y2: &G::Element) -> Result<(), AuthError> {
+        for (field, y) in [("y1", y1), ("y2", y2)] {
+            if !self.group.in_subgroup(y) {
+                return Err(AuthError::NotInSubgroup(field.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_element(&self, field: &str, bytes: &[u8]) -> Result<G::Element, AuthError> {
+        non_empty(field, bytes)?;
+        self.group
+            .element_from_bytes(bytes)
+            .ok_or_else(|| AuthError::InvalidElement(field.to_string()))
+    }
+
+    fn decode_commitment(&self, field: &str, bytes: &[u8]) -> Result<G::Element, AuthError> {
+        let r = self.decode_element(field, bytes)?;
+        if !self.group.validate_commitment(&r) {
+            return Err(AuthError::InvalidCommitment(field.to_string()));
+        }
+        Ok(r)
+    }
+
+    /// Decodes the scalar sent as `field`, such as `c` for `check_pending` or `s` for
+    /// `verify_pending`.
+    pub fn decode_scalar(&self, field: &str, bytes: &[u8]) -> Result<G::Scalar, AuthError> {
+        non_empty(field, bytes)?;
+        self.group
+            .scalar_from_bytes(bytes)
+            .ok_or_else(|| AuthError::InvalidScalar(field.to_string()))
+    }
+}
+
+/// An empty field would otherwise decode to zero; reject it outright.
+fn non_empty(field: &str, bytes: &[u8]) -> Result<(), AuthError> {
+    if bytes.is_empty() {
+        return Err(AuthError::EmptyField(field.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Scalar;
+    use num_bigint::BigUint;
+
+    fn register(service: &AuthService, user: &str, x: &Scalar) {
+        let (y1, y2) = service.group.compute_pair(x);
+        service
+            .register(user, &y1.to_bytes_be(), &y2.to_bytes_be())
+            .unwrap();
+    }
+
+    #[test]
+    fn full_flow_opens_a_session() {
+        let service = AuthService::default();
+        let zkp = &service.group;
+        let x = zkp.generate_random_scalar();
+        register(&service, "alice", &x);
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge = service
+            .create_challenge("alice", &r1.to_bytes_be(), &r2.to_bytes_be())
+            .unwrap();
+
+        let s = zkp.solve(&k, &Scalar::new(zkp, challenge.c.clone()).unwrap(), &x);
+        let verification = service.verify(&challenge.auth_id, &s.to_bytes_be()).unwrap();
+        let Verification::Login(login) = verification else {
+            panic!("expected a session");
+        };
+        assert_eq!(login.user_name, "alice");
+        assert_eq!(service.validate_session(&login.session_id).unwrap(), "alice");
+
+        service.logout(&login.session_id).unwrap();
+        let err = service.validate_session(&login.session_id).unwrap_err();
+        assert_eq!(err, AuthError::SessionNotFound);
+        // The session id is a bearer token, so it stays out of the message
+        assert!(!err.to_string().contains(&login.session_id), "{}", err);
+    }
+
+    #[test]
+    fn repeated_logins_reuse_the_decoded_keys() {
+        let service = AuthService::default();
+        let zkp = &service.group;
+        let x = zkp.generate_random_scalar();
+        register(&service, "alice", &x);
+        assert_eq!(service.public_keys("alice"), Some(zkp.public_keys(&x)));
+        assert_eq!(service.public_keys("bob"), None);
+
+        for _ in 0..3 {
+            let k = zkp.generate_random_scalar();
+            let (r1, r2) = zkp.compute_pair(&k);
+            let challenge = service
+                .create_challenge("alice", &r1.to_bytes_be(), &r2.to_bytes_be())
+                .unwrap();
+            let s = zkp.solve(&k, &Scalar::new(zkp, challenge.c.clone()).unwrap(), &x);
+            assert!(service.verify(&challenge.auth_id, &s.to_bytes_be()).is_ok());
+        }
+        assert_eq!(service.public_keys("alice"), Some(zkp.public_keys(&x)));
+    }
+
+    #[test]
+    fn concurrent_challenges_for_one_user_verify_independently() {
+        let service = AuthService::default();
+        let zkp = &service.group;
+        let x = zkp.generate_random_scalar();
+        register(&service, "alice", &x);
+
+        let (k1, k2) = (zkp.generate_random_scalar(), zkp.generate_random_scalar());
+        let challenges = [&k1, &k2].map(|k| {
+            let (r1, r2) = zkp.compute_pair(k);
+            service
+                .create_challenge("alice", &r1.to_bytes_be(), &r2.to_bytes_be())
+                .unwrap()
+        });
+
+        // Answer the second challenge first; the first must not have been overwritten
+        for (k, challenge) in [(&k2, &challenges[1]), (&k1, &challenges[0])] {
+            let s = zkp.solve(k, &Scalar::new(zkp, challenge.c.clone()).unwrap(), &x);
+            let verification = service.verify(&challenge.auth_id, &s.to_bytes_be()).unwrap();
+            let Verification::Login(login) = verification else {
+                panic!("expected a session");
+            };
+            assert_eq!(login.user_name, "alice");
+        }
+        assert_eq!(service.sessions.read().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn revoking_a_user_invalidates_its_refresh_token() {
+        let service = AuthService::default();
+        let zkp = &service.group;
+        let x = zkp.generate_random_scalar();
+        register(&service, "alice", &x);
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge = service
+            .create_challenge("alice", &r1.to_bytes_be(), &r2.to_bytes_be())
+            .unwrap();
+        let s = zkp.solve(&k, &Scalar::new(zkp, challenge.c.clone()).unwrap(), &x);
+        let verification = service.verify(&challenge.auth_id, &s.to_bytes_be()).unwrap();
+        let Verification::Login(login) = verification else {
+            panic!("expected a session");
+        };
+        let refreshed = service.refresh_session(&login.refresh_token).unwrap();
+        assert_eq!(refreshed.user_name, "alice");
+
+        service.revoke_user("alice").unwrap();
+        assert_eq!(
+            service.refresh_session(&login.refresh_token),
+            Err(AuthError::RefreshTokenNotFound)
+        );
+        assert_eq!(
+            service.validate_session(&refreshed.session_id),
+            Err(AuthError::SessionNotFound)
+        );
+        assert_eq!(service.public_keys("alice"), None);
+    }
+
+    #[test]
+    fn rotating_keys_replaces_them_and_ends_sessions() {
+        let service = AuthService::default();
+        let zkp = &service.group;
+        let x = zkp.generate_random_scalar();
+        register(&service, "alice", &x);
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge = service
+            .create_challenge("alice", &r1.to_bytes_be(), &r2.to_bytes_be())
+            .unwrap();
+        let s = zkp.solve(&k, &Scalar::new(zkp, challenge.c.clone()).unwrap(), &x);
+        let verification = service.verify(&challenge.auth_id, &s.to_bytes_be()).unwrap();
+        let Verification::Login(login) = verification else {
+            panic!("expected a session");
+        };
+
+        let new_x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.register_values(&new_x);
+        service.rotate_keys("alice", &y1, &y2).unwrap();
+        assert_eq!(service.public_keys("alice"), Some(zkp.public_keys(&new_x)));
+        assert_eq!(
+            service.validate_session(&login.session_id),
+            Err(AuthError::SessionNotFound)
+        );
+        assert_eq!(
+            service.refresh_session(&login.refresh_token),
+            Err(AuthError::RefreshTokenNotFound)
+        );
+
+        assert_eq!(
+            service.rotate_keys("bob", &y1, &y2),
+            Err(AuthError::UserNotFound("bob".to_string()))
+        );
+        let one = [1u8];
+        assert_eq!(
+            service.rotate_keys("alice", &one, &one),
+            Err(AuthError::TrivialPublicKey)
+        );
+        assert_eq!(service.public_keys("alice"), Some(zkp.public_keys(&new_x)));
+    }
+
+    #[test]
+    fn each_key_slot_answers_its_own_challenges() {
+        let service = AuthService::default();
+        let zkp = &service.group;
+        let (laptop_x, phone_x) = (zkp.generate_random_scalar(), zkp.generate_random_scalar());
+        register(&service, "alice", &laptop_x);
+        let (y1, y2) = zkp.register_values(&phone_x);
+        service.add_slot("alice", "phone", &y1, &y2).unwrap();
+        assert_eq!(service.public_keys("alice"), Some(zkp.public_keys(&laptop_x)));
+        assert_eq!(
+            service.slot_public_keys("alice", "phone"),
+            Some(zkp.public_keys(&phone_x))
+        );
+
+        let login = |slot: &str, x: &Scalar| {
+            let k = zkp.generate_random_scalar();
+            let (r1, r2) = zkp.compute_pair(&k);
+            let challenge = service
+                .create_slot_challenge("alice", slot, &r1.to_bytes_be(), &r2.to_bytes_be())
+                .unwrap();
+            let s = zkp.solve(&k, &Scalar::new(zkp, challenge.c.clone()).unwrap(), x);
+            service
+                .verify(&challenge.auth_id, &s.to_bytes_be())
+                .map(|verification| verification.user_name().to_string())
+                .map_err(|e| matches!(e, AuthError::IncorrectSolution(_)))
+        };
+        assert_eq!(login(DEFAULT_SLOT, &laptop_x), Ok("alice".to_string()));
+        assert_eq!(login("phone", &phone_x), Ok("alice".to_string()));
+        // A slot's challenge is checked against that slot's keys only
+        assert_eq!(login("phone", &laptop_x), Err(true));
+        assert_eq!(login(DEFAULT_SLOT, &phone_x), Err(true));
+
+        assert_eq!(
+            service.create_slot_challenge("alice", "tablet", &[4], &[4]).unwrap_err(),
+            AuthError::SlotNotFound("tablet".to_string())
+        );
+        assert_eq!(
+            service.add_slot("alice", "phone", &y1, &y2),
+            Err(AuthError::SlotAlreadyExists("phone".to_string()))
+        );
+        assert_eq!(
+            service.register_slot("alice", "tablet", &y1, &y2),
+            Err(AuthError::UserAlreadyExists("alice".to_string()))
+        );
+        assert_eq!(
+            service.add_slot("bob", "phone", &y1, &y2),
+            Err(AuthError::UserNotFound("bob".to_string()))
+        );
+    }
+
+    #[test]
+    fn wrong_secret_is_an_incorrect_solution() {
+        let service = AuthService::default();
+        let zkp = &service.group;
+        register(&service, "alice", &zkp.generate_random_scalar());
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge = service
+            .create_challenge("alice", &r1.to_bytes_be(), &r2.to_bytes_be())
+            .unwrap();
+
+        let wrong_x = zkp.generate_random_scalar();
+        let s = zkp.solve(&k, &Scalar::new(zkp, challenge.c.clone()).unwrap(), &wrong_x);
+        assert_eq!(
+            service.verify(&challenge.auth_id, &s.to_bytes_be()),
+            Err(AuthError::IncorrectSolution(challenge.auth_id.clone()))
+        );
+        assert!(service.sessions.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn rate_limit_entries_are_dropped_once_their_window_passes() {
+        let service = AuthService::new(AuthConfig {
+            allow_unregistered_challenges: true,
+            challenge_window: Duration::ZERO,
+            ..AuthConfig::default()
+        });
+        let zkp = &service.group;
+        for user in ["bob", "carol", "dave"] {
+            let (r1, r2) = zkp.compute_pair(&zkp.generate_random_scalar());
+            service
+                .create_challenge(user, &r1.to_bytes_be(), &r2.to_bytes_be())
+                .unwrap();
+        }
+        // With a zero window every earlier request is already outside it
+        let challenge_requests = service.challenge_requests.lock().unwrap();
+        assert_eq!(challenge_requests.keys().collect::<Vec<_>>(), ["dave"]);
+    }
+
+    #[test]
+    fn registering_a_name_revokes_its_earlier_sessions() {
+        let service = AuthService::default();
+        let refresh_token = ZKP::generate_token(32);
+        let session_id = service.open_session("alice", &refresh_token);
+        service.refresh_tokens.write().unwrap().insert(
+            refresh_token.clone(),
+            Session {
+                user_name: "alice".to_string(),
+                created_at: service.clock.now(),
+                elevated_at: None,
+                refresh_token: None,
+            },
+        );
+
+        register(&service, "alice", &service.group.generate_random_scalar());
+        assert_eq!(
+            service.validate_session(&session_id),
+            Err(AuthError::SessionNotFound)
+        );
+        assert_eq!(
+            service.refresh_session(&refresh_token),
+            Err(AuthError::RefreshTokenNotFound)
+        );
+    }
+
+    #[test]
+    fn user_info_debug_shows_only_public_values() {
+        let slot = (BigUint::from(987654321u32), BigUint::from(123456789u32));
+        let user_info: UserInfo = UserInfo {
+            user_name: "alice".to_string(),
+            slots: HashMap::from([("phone".to_string(), slot)]),
+        };
+        let debug = format!("{:?}", user_info);
+        for public in ["alice", "phone", "987654321", "123456789"] {
+            assert!(debug.contains(public), "{}", debug);
+        }
+    }
+
+    #[test]
+    fn unknown_user_and_duplicate_registration_are_refused() {
+        let service = AuthService::default();
+        assert_eq!(
+            service.create_challenge("bob", &[4], &[4]).unwrap_err(),
+            AuthError::UserNotFound("bob".to_string())
+        );
+
+        register(&service, "alice", &Scalar::new(&service.group, BigUint::from(3u32)).unwrap());
+        let (y1, y2) = service.group.register_values(&BigUint::from(7u32));
+        assert_eq!(
+            service.register("alice", &y1, &y2),
+            Err(AuthError::UserAlreadyExists("alice".to_string()))
+        );
+        assert_eq!(
+            service.register("carol", &[], &[4]),
+            Err(AuthError::EmptyField("y1".to_string()))
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Range-checked wrappers for the two kinds of number `ZKP` works with, so an exponent
+//! mod q cannot be passed where a residue mod p is expected, or the other way round.
+
+use alloc::vec;
+use core::{fmt, ops::Deref};
+
+use num_bigint::BigUint;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::ZKP;
+
+/// Why a value could not be wrapped as a `Scalar` or `GroupElement`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeError {
+    /// The value is not below `q`.
+    ScalarOutOfRange,
+    /// The value is `0` or not below `p`.
+    ElementOutOfRange,
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeError::ScalarOutOfRange => write!(f, "scalar is not below q"),
+            RangeError::ElementOutOfRange => write!(f, "group element is not in [1, p)"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RangeError {}
+
+/// An exponent in `[0, q)`: a secret, nonce, challenge or response.
+///
+/// Secrets and nonces are scalars too, so like `Secret` it is wiped on drop and its
+/// `Debug` output is redacted.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Scalar(pub(crate) BigUint);
+
+impl Scalar {
+    /// Wraps `value` as a scalar of `zkp`'s subgroup.
+    pub fn new(zkp: &ZKP, value: BigUint) -> Result<Self, RangeError> {
+        if value < zkp.q {
+            Ok(Scalar(value))
+        } else {
+            Err(RangeError::ScalarOutOfRange)
+        }
+    }
+
+    /// `value mod q`, for numbers such as password-derived secrets that may exceed q.
+    pub fn reduce(zkp: &ZKP, value: &BigUint) -> Self {
+        Scalar(value % &zkp.q)
+    }
+
+    pub fn into_inner(mut self) -> BigUint {
+        core::mem::take(&mut self.0)
+    }
+}
+
+impl Deref for Scalar {
+    type Target = BigUint;
+
+    fn deref(&self) -> &BigUint {
+        &self.0
+    }
+}
+
+impl Zeroize for Scalar {
+    fn zeroize(&mut self) {
+        let zeros = vec![0u32; self.0.iter_u32_digits().len()];
+        self.0.assign_from_slice(&zeros);
+    }
+}
+
+impl Drop for Scalar {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Scalar {}
+
+impl fmt::Debug for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Scalar(<redacted>)")
+    }
+}
+
+/// A residue in `[1, p)`: a public key or commitment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupElement(pub(crate) BigUint);
+
+impl GroupElement {
+    /// Wraps `value` as an element of `zkp`'s group. Membership of the order-q subgroup
+    /// is not checked, as that costs a full exponentiation.
+    pub fn new(zkp: &ZKP, value: BigUint) -> Result<Self, RangeError> {
+        if zkp.validate_commitment(&value) {
+            Ok(GroupElement(value))
+        } else {
+            Err(RangeError::ElementOutOfRange)
+        }
+    }
+
+    pub fn into_inner(self) -> BigUint {
+        self.0
+    }
+}
+
+impl Deref for GroupElement {
+    type Target = BigUint;
+
+    fn deref(&self) -> &BigUint {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constructors_reject_values_out_of_range() {
+        let zkp = ZKP::new();
+        assert_eq!(
+            Scalar::new(&zkp, zkp.q.clone()),
+            Err(RangeError::ScalarOutOfRange)
+        );
+        assert!(Scalar::new(&zkp, &zkp.q + 1u32).is_err());
+        assert_eq!(*Scalar::new(&zkp, &zkp.q - 1u32).unwrap(), &zkp.q - 1u32);
+        assert_eq!(*Scalar::reduce(&zkp, &(&zkp.q + 7u32)), BigUint::from(7u32));
+
+        assert_eq!(
+            GroupElement::new(&zkp, BigUint::ZERO),
+            Err(RangeError::ElementOutOfRange)
+        );
+        assert!(GroupElement::new(&zkp, zkp.p.clone()).is_err());
+        assert!(GroupElement::new(&zkp, zkp.alpha.clone()).is_ok());
+        assert_eq!(format!("{:?}", Scalar::default()), "Scalar(<redacted>)");
+    }
+}
@@ -0,0 +1,203 @@
+//! A bounded cache of verification results, for callers that check the same transcript
+//! more than once, such as a client retrying a request or a job re-checking stored proofs.
+//!
+//! Verification is a pure function of `(r1, r2, y1, y2, c, s)`, so an earlier answer can
+//! stand in for the four exponentiations. Entries are keyed by a SHA-256 of that tuple in
+//! the group's wire encodings, so one cache must only be used with one group.
+//!
+//! `AuthService` does not consult a cache: its challenges are single-use and commitments
+//! may not repeat, so a transcript it has already seen is refused before it reaches
+//! verification. Behind those checks a cache would never be hit; in front of them it
+//! would let a replayed transcript through.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::ZkpGroup;
+
+/// Up to `capacity` verification results, evicting the least recently used.
+#[derive(Debug)]
+pub struct VerifyCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// Each key's result and the tick it was last used at.
+    results: HashMap<[u8; 32], (bool, u64)>,
+    /// Keys by the tick they were last used at, oldest first.
+    by_use: BTreeMap<u64, [u8; 32]>,
+    tick: u64,
+}
+
+impl Entries {
+    /// Marks `key` as just used.
+    fn touch(&mut self, key: [u8; 32], result: bool) {
+        self.tick += 1;
+        if let Some((_, last_used)) = self.results.insert(key, (result, self.tick)) {
+            self.by_use.remove(&last_used);
+        }
+        self.by_use.insert(self.tick, key);
+    }
+}
+
+impl VerifyCache {
+    /// A cache holding at most `capacity` results; with `0` it remembers nothing.
+    pub fn new(capacity: usize) -> Self {
+        VerifyCache {
+            capacity,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// The cache key of a transcript: SHA-256 of `r1 || r2 || y1 || y2 || c || s`, each in
+    /// `group`'s fixed-width wire encoding.
+    #[allow(clippy::too_many_arguments)]
+    pub fn key<G: ZkpGroup>(
+        group: &G,
+        r1: &G::Element,
+        r2: &G::Element,
+        y1: &G::Element,
+        y2: &G::Element,
+        c: &G::Scalar,
+        s: &G::Scalar,
+    ) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for element in [r1, r2, y1, y2] {
+            hasher.update(group.element_to_bytes(element));
+        }
+        hasher.update(group.scalar_to_bytes(c));
+        hasher.update(group.scalar_to_bytes(s));
+        hasher.finalize().into()
+    }
+
+    /// `group.verify(r1, r2, y1, y2, c, s)`, answered from the cache when this transcript
+    /// was verified before.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify<G: ZkpGroup>(
+        &self,
+        group: &G,
+        r1: &G::Element,
+        r2: &G::Element,
+        y1: &G::Element,
+        y2: &G::Element,
+        c: &G::Scalar,
+        s: &G::Scalar,
+    ) -> bool {
+        let key = Self::key(group, r1, r2, y1, y2, c, s);
+        self.get_or_insert_with(key, || group.verify(r1, r2, y1, y2, c, s))
+    }
+
+    /// The result stored under `key`, or else `verify()`, which is stored for next time.
+    /// `verify` runs without the cache locked, so two callers missing on the same key at
+    /// once may both run it.
+    pub fn get_or_insert_with(&self, key: [u8; 32], verify: impl FnOnce() -> bool) -> bool {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            if let Some(&(result, _)) = entries.results.get(&key) {
+                entries.touch(key, result);
+                return result;
+            }
+        }
+
+        let result = verify();
+        if self.capacity == 0 {
+            return result;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.touch(key, result);
+        while entries.results.len() > self.capacity {
+            let (_, oldest) = entries.by_use.pop_first().unwrap();
+            entries.results.remove(&oldest);
+        }
+        result
+    }
+
+    /// How many results are stored.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets every stored result.
+    pub fn clear(&self) {
+        *self.entries.lock().unwrap() = Entries::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZKP;
+    use num_bigint::BigUint;
+    use std::cell::Cell;
+
+    #[test]
+    fn a_cached_transcript_is_not_verified_again() {
+        let zkp = ZKP::new();
+        let (x, k, c) = (BigUint::from(6u32), BigUint::from(7u32), BigUint::from(4u32));
+        let t = zkp.honest_transcript(&x, &k, &c);
+        let key = |s: &BigUint| VerifyCache::key(&zkp, &t.r1, &t.r2, &t.y1, &t.y2, &t.c, s);
+        let cache = VerifyCache::new(8);
+
+        // Counts the calls that actually reach the exponentiations
+        let calls = Cell::new(0);
+        let verify = |s: &BigUint| {
+            calls.set(calls.get() + 1);
+            ZkpGroup::verify(&zkp, &t.r1, &t.r2, &t.y1, &t.y2, &t.c, s)
+        };
+        for _ in 0..3 {
+            assert!(cache.get_or_insert_with(key(&t.s), || verify(&t.s)));
+        }
+        assert_eq!(calls.get(), 1);
+
+        // A failing result is remembered as well, under its own key
+        let wrong = &t.s + 1u32;
+        for _ in 0..3 {
+            assert!(!cache.get_or_insert_with(key(&wrong), || verify(&wrong)));
+        }
+        assert_eq!(calls.get(), 2);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.verify(&zkp, &t.r1, &t.r2, &t.y1, &t.y2, &t.c, &t.s));
+        assert_eq!(cache.len(), 2);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert!(cache.get_or_insert_with(key(&t.s), || verify(&t.s)));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn the_least_recently_used_result_is_evicted() {
+        let cache = VerifyCache::new(2);
+        let calls = Cell::new(0);
+        let lookup = |key: u8| {
+            cache.get_or_insert_with([key; 32], || {
+                calls.set(calls.get() + 1);
+                true
+            })
+        };
+        lookup(1);
+        lookup(2);
+        lookup(1);
+        // 2 is now the oldest, so adding 3 evicts it
+        lookup(3);
+        assert_eq!((cache.len(), calls.get()), (2, 3));
+        lookup(1);
+        assert_eq!(calls.get(), 3);
+        lookup(2);
+        assert_eq!(calls.get(), 4);
+
+        let off = VerifyCache::new(0);
+        assert!(off.get_or_insert_with([0; 32], || true));
+        assert!(off.is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+//! Feeds arbitrary bytes as `y1`, `y2`, `r1`, `r2` and `s` through `AuthService`'s
+//! register, challenge and verify paths, which decode and validate them.
+//!
+//! The input is five fields, each a 2-byte big-endian length followed by that many bytes;
+//! a short input leaves the remaining fields empty.
+
+#![no_main]
+
+use chaum_pedersen::service::{AuthError, AuthService};
+use libfuzzer_sys::fuzz_target;
+use num_bigint::BigUint;
+
+fn fields(mut data: &[u8]) -> [&[u8]; 5] {
+    let mut fields = [&[][..]; 5];
+    for field in &mut fields {
+        if data.len() < 2 {
+            break;
+        }
+        let len = usize::from(u16::from_be_bytes([data[0], data[1]])).min(data.len() - 2);
+        *field = &data[2..2 + len];
+        data = &data[2 + len..];
+    }
+    fields
+}
+
+fuzz_target!(|data: &[u8]| {
+    let [y1, y2, r1, r2, s] = fields(data);
+    let service = AuthService::default();
+    let zkp = &service.group;
+
+    match service.register("fuzz", y1, y2) {
+        Ok(()) => {
+            let (y1, y2) = (BigUint::from_bytes_be(y1), BigUint::from_bytes_be(y2));
+            assert!(!zkp.is_trivial_public_key(&y1, &y2));
+            assert!(zkp.in_subgroup(&y1) && zkp.in_subgroup(&y2));
+        }
+        Err(e) => {
+            if y1.is_empty() || y2.is_empty() {
+                assert!(matches!(e, AuthError::EmptyField(_)), "{:?}", e);
+            }
+            // Still exercise the challenge and verify paths with keys that are accepted
+            let (y1, y2) = zkp.public_keys(&BigUint::from(2u32));
+            service
+                .register("fuzz", &y1.to_bytes_be(), &y2.to_bytes_be())
+                .unwrap();
+        }
+    }
+
+    let in_range = |bytes: &[u8]| {
+        let value = BigUint::from_bytes_be(bytes);
+        !bytes.is_empty() && value != BigUint::ZERO && value < zkp.p
+    };
+    let challenge = service.create_challenge("fuzz", r1, r2);
+    assert_eq!(challenge.is_ok(), in_range(r1) && in_range(r2), "{:?}", challenge);
+
+    if let Ok(challenge) = challenge {
+        let first = service.verify(&challenge.auth_id, s);
+        if s.is_empty() {
+            assert!(matches!(first, Err(AuthError::EmptyField(_))), "{:?}", first);
+        } else {
+            // Any decodable answer consumes the challenge, right or wrong
+            assert!(matches!(
+                service.verify(&challenge.auth_id, s),
+                Err(AuthError::ChallengeNotFound(_))
+            ));
+        }
+    }
+});
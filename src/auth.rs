@@ -11,6 +11,18 @@ pub struct RegisterRequest {
     /// Public value y2 = beta^x mod p
     #[prost(bytes = "vec", tag = "3")]
     pub y2: ::prost::alloc::vec::Vec<u8>,
+    /// Optional hex encodings of y1 and y2; when set they are used instead of the bytes
+    #[prost(string, optional, tag = "4")]
+    pub y1_hex: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "5")]
+    pub y2_hex: ::core::option::Option<::prost::alloc::string::String>,
+    /// The key slot y1 and y2 are stored under; unset means "default"
+    #[prost(string, optional, tag = "6")]
+    pub slot: ::core::option::Option<::prost::alloc::string::String>,
+    /// A live session of the user, to add the slot to an existing user rather than
+    /// register a new one
+    #[prost(string, optional, tag = "7")]
+    pub session_id: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// Empty response indicating registration success
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
@@ -27,6 +39,14 @@ pub struct CreateAuthenticationChallengeRequest {
     /// Second part of the commitment r2 = beta^k mod p
     #[prost(bytes = "vec", tag = "3")]
     pub r2: ::prost::alloc::vec::Vec<u8>,
+    /// Optional hex encodings of r1 and r2; when set they are used instead of the bytes
+    #[prost(string, optional, tag = "4")]
+    pub r1_hex: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "5")]
+    pub r2_hex: ::core::option::Option<::prost::alloc::string::String>,
+    /// The key slot whose keys the answer is checked against; unset means "default"
+    #[prost(string, optional, tag = "6")]
+    pub slot: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// Server responds with an auth_id to track the session and the challenge c
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -47,12 +67,261 @@ pub struct VerifyAuthenticationRequest {
     /// The computed solution s to the challenge
     #[prost(bytes = "vec", tag = "2")]
     pub s: ::prost::alloc::vec::Vec<u8>,
+    /// Optional hex encoding of s; when set it is used instead of the bytes
+    #[prost(string, optional, tag = "3")]
+    pub s_hex: ::core::option::Option<::prost::alloc::string::String>,
+    /// Public keys to check the proof against when the user never registered; only
+    /// accepted by servers that issue challenges to unregistered names
+    #[prost(bytes = "vec", optional, tag = "4")]
+    pub y1: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", optional, tag = "5")]
+    pub y2: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// The challenge c this answer was computed for, echoed back; when set, an answer to any
+    /// other challenge is refused with FAILED_PRECONDITION before the proof is checked
+    #[prost(bytes = "vec", optional, tag = "6")]
+    pub c: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
-/// Server responds with a session_id if the solution is correct
+/// Server responds with a session_id if the solution is correct. Both fields are empty
+/// when the challenge was for an unregistered name: the proof checked out, but no
+/// session is opened for a name no one holds
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct VerifyAuthenticationResponse {
     #[prost(string, tag = "1")]
     pub session_id: ::prost::alloc::string::String,
+    /// Longer-lived token that RefreshSession exchanges for a new session_id
+    #[prost(string, tag = "2")]
+    pub refresh_token: ::prost::alloc::string::String,
+}
+/// The prover's answer within an Authenticate stream, which already knows the auth_id
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuthenticateAnswer {
+    /// The computed solution s to the challenge
+    #[prost(bytes = "vec", tag = "1")]
+    pub s: ::prost::alloc::vec::Vec<u8>,
+    /// Optional hex encoding of s; when set it is used instead of the bytes
+    #[prost(string, optional, tag = "2")]
+    pub s_hex: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Prover messages on an Authenticate stream: first the commitments, then the answer
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuthenticateRequest {
+    #[prost(oneof = "authenticate_request::Step", tags = "1, 2")]
+    pub step: ::core::option::Option<authenticate_request::Step>,
+}
+/// Nested message and enum types in `AuthenticateRequest`.
+pub mod authenticate_request {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Step {
+        #[prost(message, tag = "1")]
+        Commitments(super::CreateAuthenticationChallengeRequest),
+        #[prost(message, tag = "2")]
+        Answer(super::AuthenticateAnswer),
+    }
+}
+/// Server messages on an Authenticate stream: first the challenge, then the session
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuthenticateResponse {
+    #[prost(oneof = "authenticate_response::Step", tags = "1, 2")]
+    pub step: ::core::option::Option<authenticate_response::Step>,
+}
+/// Nested message and enum types in `AuthenticateResponse`.
+pub mod authenticate_response {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Step {
+        #[prost(message, tag = "1")]
+        Challenge(super::CreateAuthenticationChallengeResponse),
+        #[prost(message, tag = "2")]
+        Session(super::VerifyAuthenticationResponse),
+    }
+}
+/// Client asks whether a previously issued session is still live
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidateSessionRequest {
+    #[prost(string, tag = "1")]
+    pub session_id: ::prost::alloc::string::String,
+}
+/// Server responds with the owner of the session if it has not expired
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidateSessionResponse {
+    #[prost(string, tag = "1")]
+    pub user: ::prost::alloc::string::String,
+    /// Whether the session passed a step-up proof recently enough to still be elevated
+    #[prost(bool, tag = "2")]
+    pub elevated: bool,
+}
+/// Client trades the refresh token from a login for a new session, without another proof
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RefreshSessionRequest {
+    #[prost(string, tag = "1")]
+    pub refresh_token: ::prost::alloc::string::String,
+}
+/// Server responds with the new session_id if the refresh token is still valid
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RefreshSessionResponse {
+    #[prost(string, tag = "1")]
+    pub session_id: ::prost::alloc::string::String,
+}
+/// Client ends a session before it expires
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LogoutRequest {
+    #[prost(string, tag = "1")]
+    pub session_id: ::prost::alloc::string::String,
+}
+/// Empty response indicating the session was removed
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct LogoutResponse {}
+/// Client or operator lists the live sessions of a user. The caller must send a live
+/// session_id of that user, or the admin token in `x-admin-token` request metadata; a
+/// session of another user is refused with PERMISSION_DENIED, and neither with
+/// UNAUTHENTICATED. RevokeSession and RevokeAllSessions are authorized the same way.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSessionsRequest {
+    #[prost(string, tag = "1")]
+    pub user: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub session_id: ::prost::alloc::string::String,
+}
+/// A live session, named by a reference rather than its session_id
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SessionInfo {
+    /// Hex SHA-256 of the session_id
+    #[prost(string, tag = "1")]
+    pub session_ref: ::prost::alloc::string::String,
+    /// Seconds since the session was opened
+    #[prost(uint64, tag = "2")]
+    pub age_secs: u64,
+    /// Whether the session passed a step-up proof recently enough to still be elevated
+    #[prost(bool, tag = "3")]
+    pub elevated: bool,
+}
+/// The user's live sessions, oldest first
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSessionsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub sessions: ::prost::alloc::vec::Vec<SessionInfo>,
+}
+/// Client or operator ends one session of a user, named by its session_ref
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevokeSessionRequest {
+    #[prost(string, tag = "1")]
+    pub user: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub session_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub session_ref: ::prost::alloc::string::String,
+}
+/// Empty response indicating the session was removed, along with the refresh token of
+/// the login it came from
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RevokeSessionResponse {}
+/// Client or operator ends every session and refresh token of a user, including the
+/// session_id the request was authorized with
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevokeAllSessionsRequest {
+    #[prost(string, tag = "1")]
+    pub user: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub session_id: ::prost::alloc::string::String,
+}
+/// How many sessions were ended
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RevokeAllSessionsResponse {
+    #[prost(uint64, tag = "1")]
+    pub revoked: u64,
+}
+/// Client replaces its public keys after proving it holds the current secret
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RotateKeysRequest {
+    /// The user whose keys are replaced
+    #[prost(string, tag = "1")]
+    pub user: ::prost::alloc::string::String,
+    /// New public values y1 = alpha^x' mod p and y2 = beta^x' mod p
+    #[prost(bytes = "vec", tag = "2")]
+    pub y1: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub y2: ::prost::alloc::vec::Vec<u8>,
+    /// Optional hex encodings of y1 and y2; when set they are used instead of the bytes
+    #[prost(string, optional, tag = "4")]
+    pub y1_hex: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "5")]
+    pub y2_hex: ::core::option::Option<::prost::alloc::string::String>,
+    /// The key slot whose keys are replaced; unset means "default"
+    #[prost(string, optional, tag = "8")]
+    pub slot: ::core::option::Option<::prost::alloc::string::String>,
+    /// Proof that the caller is the user; without one the request is refused with
+    /// UNAUTHENTICATED, and with one for another user with PERMISSION_DENIED
+    #[prost(oneof = "rotate_keys_request::Authorization", tags = "6, 7")]
+    pub authorization: ::core::option::Option<rotate_keys_request::Authorization>,
+}
+/// Nested message and enum types in `RotateKeysRequest`.
+pub mod rotate_keys_request {
+    /// Proof that the caller is the user; without one the request is refused with
+    /// UNAUTHENTICATED, and with one for another user with PERMISSION_DENIED
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Authorization {
+        /// A live session issued to the user
+        #[prost(string, tag = "6")]
+        SessionId(::prost::alloc::string::String),
+        /// An answer to a challenge issued for the user under the current keys
+        #[prost(message, tag = "7")]
+        Proof(super::VerifyAuthenticationRequest),
+    }
+}
+/// Empty response indicating the keys were replaced; every session and refresh token
+/// issued under the old keys has ended
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RotateKeysResponse {}
+/// Client with a live session asks for a step-up challenge, sending fresh commitments
+/// r1 = alpha^k mod p and r2 = beta^k mod p
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StepUpChallengeRequest {
+    #[prost(string, tag = "1")]
+    pub session_id: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub r1: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub r2: ::prost::alloc::vec::Vec<u8>,
+    /// Optional hex encodings of r1 and r2; when set they are used instead of the bytes
+    #[prost(string, optional, tag = "4")]
+    pub r1_hex: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "5")]
+    pub r2_hex: ::core::option::Option<::prost::alloc::string::String>,
+    /// The key slot whose keys the answer is checked against; unset means "default"
+    #[prost(string, optional, tag = "6")]
+    pub slot: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Client answers the step-up challenge on the same session
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StepUpVerifyRequest {
+    #[prost(string, tag = "1")]
+    pub session_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub auth_id: ::prost::alloc::string::String,
+    /// The computed solution s = k - c * x mod q
+    #[prost(bytes = "vec", tag = "3")]
+    pub s: ::prost::alloc::vec::Vec<u8>,
+    /// Optional hex encoding of s; when set it is used instead of the bytes
+    #[prost(string, optional, tag = "4")]
+    pub s_hex: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Server confirms the session is elevated
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct StepUpVerifyResponse {
+    /// How many seconds the session stays elevated
+    #[prost(uint64, tag = "1")]
+    pub elevated_for_secs: u64,
+}
+/// Operator asks for server statistics; requires the admin token in the
+/// `x-admin-token` request metadata
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct GetStatsRequest {}
+/// Current counts of registered users and unanswered, unexpired challenges
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct GetStatsResponse {
+    #[prost(uint64, tag = "1")]
+    pub user_count: u64,
+    #[prost(uint64, tag = "2")]
+    pub pending_challenges: u64,
 }
 /// Generated client implementations.
 pub mod auth_client {
@@ -217,117 +486,460 @@ pub mod auth_client {
                 .insert(GrpcMethod::new("auth.Auth", "VerifyAuthentication"));
             self.inner.unary(req, path, codec).await
         }
-    }
-}
-/// Generated server implementations.
-pub mod auth_server {
-    #![allow(
-        unused_variables,
-        dead_code,
-        missing_docs,
-        clippy::wildcard_imports,
-        clippy::let_unit_value,
-    )]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with AuthServer.
-    #[async_trait]
-    pub trait Auth: std::marker::Send + std::marker::Sync + 'static {
-        /// Register the user on the server
-        async fn register(
-            &self,
-            request: tonic::Request<super::RegisterRequest>,
+        /// Run the challenge and answer on one stream; the challenge is dropped if the
+        /// stream ends before it is answered
+        pub async fn authenticate(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::AuthenticateRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<super::RegisterResponse>,
+            tonic::Response<tonic::codec::Streaming<super::AuthenticateResponse>>,
             tonic::Status,
-        >;
-        /// Create (issue) a challenge for the user
-        async fn create_authentication_challenge(
-            &self,
-            request: tonic::Request<super::CreateAuthenticationChallengeRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/auth.Auth/Authenticate");
+            let mut req = request.into_streaming_request();
+            req.extensions_mut().insert(GrpcMethod::new("auth.Auth", "Authenticate"));
+            self.inner.streaming(req, path, codec).await
+        }
+        /// Check that a session is still live
+        pub async fn validate_session(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ValidateSessionRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::CreateAuthenticationChallengeResponse>,
+            tonic::Response<super::ValidateSessionResponse>,
             tonic::Status,
-        >;
-        /// Verify the prover’s response to the challenge
-        async fn verify_authentication(
-            &self,
-            request: tonic::Request<super::VerifyAuthenticationRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/auth.Auth/ValidateSession",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("auth.Auth", "ValidateSession"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Open a new session with a refresh token
+        pub async fn refresh_session(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RefreshSessionRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::VerifyAuthenticationResponse>,
+            tonic::Response<super::RefreshSessionResponse>,
             tonic::Status,
-        >;
-    }
-    #[derive(Debug)]
-    pub struct AuthServer<T> {
-        inner: Arc<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
-        max_decoding_message_size: Option<usize>,
-        max_encoding_message_size: Option<usize>,
-    }
-    impl<T> AuthServer<T> {
-        pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
-        }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
-                max_decoding_message_size: None,
-                max_encoding_message_size: None,
-            }
-        }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> InterceptedService<Self, F>
-        where
-            F: tonic::service::Interceptor,
-        {
-            InterceptedService::new(Self::new(inner), interceptor)
-        }
-        /// Enable decompressing requests with the given encoding.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.accept_compression_encodings.enable(encoding);
-            self
-        }
-        /// Compress responses with the given encoding, if the client supports it.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
-            self
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/auth.Auth/RefreshSession");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("auth.Auth", "RefreshSession"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Limits the maximum size of a decoded message.
-        ///
-        /// Default: `4MB`
-        #[must_use]
-        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
-            self.max_decoding_message_size = Some(limit);
-            self
+        /// End a session
+        pub async fn logout(
+            &mut self,
+            request: impl tonic::IntoRequest<super::LogoutRequest>,
+        ) -> std::result::Result<tonic::Response<super::LogoutResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/auth.Auth/Logout");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("auth.Auth", "Logout"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Limits the maximum size of an encoded message.
-        ///
-        /// Default: `usize::MAX`
-        #[must_use]
-        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
-            self.max_encoding_message_size = Some(limit);
-            self
+        /// List the live sessions of a user (the user's session or the admin token required)
+        pub async fn list_sessions(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListSessionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSessionsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/auth.Auth/ListSessions");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("auth.Auth", "ListSessions"));
+            self.inner.unary(req, path, codec).await
         }
-    }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for AuthServer<T>
-    where
-        T: Auth,
-        B: Body + std::marker::Send + 'static,
-        B::Error: Into<StdError> + std::marker::Send + 'static,
-    {
-        type Response = http::Response<tonic::body::BoxBody>;
-        type Error = std::convert::Infallible;
-        type Future = BoxFuture<Self::Response, Self::Error>;
-        fn poll_ready(
+        /// End one session of a user (the user's session or the admin token required)
+        pub async fn revoke_session(
             &mut self,
-            _cx: &mut Context<'_>,
+            request: impl tonic::IntoRequest<super::RevokeSessionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RevokeSessionResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/auth.Auth/RevokeSession");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("auth.Auth", "RevokeSession"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// End every session of a user (the user's session or the admin token required)
+        pub async fn revoke_all_sessions(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RevokeAllSessionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RevokeAllSessionsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/auth.Auth/RevokeAllSessions",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("auth.Auth", "RevokeAllSessions"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Replace the public keys of an authenticated user
+        pub async fn rotate_keys(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RotateKeysRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RotateKeysResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/auth.Auth/RotateKeys");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("auth.Auth", "RotateKeys"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Issue a step-up challenge bound to a live session; the challenge cannot be used to
+        /// log in
+        pub async fn step_up_challenge(
+            &mut self,
+            request: impl tonic::IntoRequest<super::StepUpChallengeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateAuthenticationChallengeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/auth.Auth/StepUpChallenge",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("auth.Auth", "StepUpChallenge"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Answer a step-up challenge, elevating the session for a short window
+        pub async fn step_up_verify(
+            &mut self,
+            request: impl tonic::IntoRequest<super::StepUpVerifyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::StepUpVerifyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/auth.Auth/StepUpVerify");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("auth.Auth", "StepUpVerify"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Report server statistics (admin token required)
+        pub async fn get_stats(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetStatsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetStatsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/auth.Auth/GetStats");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("auth.Auth", "GetStats"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod auth_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with AuthServer.
+    #[async_trait]
+    pub trait Auth: std::marker::Send + std::marker::Sync + 'static {
+        /// Register the user on the server
+        async fn register(
+            &self,
+            request: tonic::Request<super::RegisterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RegisterResponse>,
+            tonic::Status,
+        >;
+        /// Create (issue) a challenge for the user
+        async fn create_authentication_challenge(
+            &self,
+            request: tonic::Request<super::CreateAuthenticationChallengeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateAuthenticationChallengeResponse>,
+            tonic::Status,
+        >;
+        /// Verify the prover’s response to the challenge
+        async fn verify_authentication(
+            &self,
+            request: tonic::Request<super::VerifyAuthenticationRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::VerifyAuthenticationResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the Authenticate method.
+        type AuthenticateStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::AuthenticateResponse, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Run the challenge and answer on one stream; the challenge is dropped if the
+        /// stream ends before it is answered
+        async fn authenticate(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::AuthenticateRequest>>,
+        ) -> std::result::Result<
+            tonic::Response<Self::AuthenticateStream>,
+            tonic::Status,
+        >;
+        /// Check that a session is still live
+        async fn validate_session(
+            &self,
+            request: tonic::Request<super::ValidateSessionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ValidateSessionResponse>,
+            tonic::Status,
+        >;
+        /// Open a new session with a refresh token
+        async fn refresh_session(
+            &self,
+            request: tonic::Request<super::RefreshSessionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RefreshSessionResponse>,
+            tonic::Status,
+        >;
+        /// End a session
+        async fn logout(
+            &self,
+            request: tonic::Request<super::LogoutRequest>,
+        ) -> std::result::Result<tonic::Response<super::LogoutResponse>, tonic::Status>;
+        /// List the live sessions of a user (the user's session or the admin token required)
+        async fn list_sessions(
+            &self,
+            request: tonic::Request<super::ListSessionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSessionsResponse>,
+            tonic::Status,
+        >;
+        /// End one session of a user (the user's session or the admin token required)
+        async fn revoke_session(
+            &self,
+            request: tonic::Request<super::RevokeSessionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RevokeSessionResponse>,
+            tonic::Status,
+        >;
+        /// End every session of a user (the user's session or the admin token required)
+        async fn revoke_all_sessions(
+            &self,
+            request: tonic::Request<super::RevokeAllSessionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RevokeAllSessionsResponse>,
+            tonic::Status,
+        >;
+        /// Replace the public keys of an authenticated user
+        async fn rotate_keys(
+            &self,
+            request: tonic::Request<super::RotateKeysRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RotateKeysResponse>,
+            tonic::Status,
+        >;
+        /// Issue a step-up challenge bound to a live session; the challenge cannot be used to
+        /// log in
+        async fn step_up_challenge(
+            &self,
+            request: tonic::Request<super::StepUpChallengeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateAuthenticationChallengeResponse>,
+            tonic::Status,
+        >;
+        /// Answer a step-up challenge, elevating the session for a short window
+        async fn step_up_verify(
+            &self,
+            request: tonic::Request<super::StepUpVerifyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::StepUpVerifyResponse>,
+            tonic::Status,
+        >;
+        /// Report server statistics (admin token required)
+        async fn get_stats(
+            &self,
+            request: tonic::Request<super::GetStatsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetStatsResponse>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct AuthServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> AuthServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for AuthServer<T>
+    where
+        T: Auth,
+        B: Body + std::marker::Send + 'static,
+        B::Error: Into<StdError> + std::marker::Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
         ) -> Poll<std::result::Result<(), Self::Error>> {
             Poll::Ready(Ok(()))
         }
@@ -473,6 +1085,494 @@ pub mod auth_server {
                     };
                     Box::pin(fut)
                 }
+                "/auth.Auth/Authenticate" => {
+                    #[allow(non_camel_case_types)]
+                    struct AuthenticateSvc<T: Auth>(pub Arc<T>);
+                    impl<
+                        T: Auth,
+                    > tonic::server::StreamingService<super::AuthenticateRequest>
+                    for AuthenticateSvc<T> {
+                        type Response = super::AuthenticateResponse;
+                        type ResponseStream = T::AuthenticateStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::AuthenticateRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Auth>::authenticate(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = AuthenticateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.Auth/ValidateSession" => {
+                    #[allow(non_camel_case_types)]
+                    struct ValidateSessionSvc<T: Auth>(pub Arc<T>);
+                    impl<
+                        T: Auth,
+                    > tonic::server::UnaryService<super::ValidateSessionRequest>
+                    for ValidateSessionSvc<T> {
+                        type Response = super::ValidateSessionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ValidateSessionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Auth>::validate_session(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ValidateSessionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.Auth/RefreshSession" => {
+                    #[allow(non_camel_case_types)]
+                    struct RefreshSessionSvc<T: Auth>(pub Arc<T>);
+                    impl<
+                        T: Auth,
+                    > tonic::server::UnaryService<super::RefreshSessionRequest>
+                    for RefreshSessionSvc<T> {
+                        type Response = super::RefreshSessionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RefreshSessionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Auth>::refresh_session(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RefreshSessionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.Auth/Logout" => {
+                    #[allow(non_camel_case_types)]
+                    struct LogoutSvc<T: Auth>(pub Arc<T>);
+                    impl<T: Auth> tonic::server::UnaryService<super::LogoutRequest>
+                    for LogoutSvc<T> {
+                        type Response = super::LogoutResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::LogoutRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Auth>::logout(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = LogoutSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.Auth/ListSessions" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListSessionsSvc<T: Auth>(pub Arc<T>);
+                    impl<T: Auth> tonic::server::UnaryService<super::ListSessionsRequest>
+                    for ListSessionsSvc<T> {
+                        type Response = super::ListSessionsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListSessionsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Auth>::list_sessions(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListSessionsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.Auth/RevokeSession" => {
+                    #[allow(non_camel_case_types)]
+                    struct RevokeSessionSvc<T: Auth>(pub Arc<T>);
+                    impl<
+                        T: Auth,
+                    > tonic::server::UnaryService<super::RevokeSessionRequest>
+                    for RevokeSessionSvc<T> {
+                        type Response = super::RevokeSessionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RevokeSessionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Auth>::revoke_session(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RevokeSessionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.Auth/RevokeAllSessions" => {
+                    #[allow(non_camel_case_types)]
+                    struct RevokeAllSessionsSvc<T: Auth>(pub Arc<T>);
+                    impl<
+                        T: Auth,
+                    > tonic::server::UnaryService<super::RevokeAllSessionsRequest>
+                    for RevokeAllSessionsSvc<T> {
+                        type Response = super::RevokeAllSessionsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RevokeAllSessionsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Auth>::revoke_all_sessions(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RevokeAllSessionsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.Auth/RotateKeys" => {
+                    #[allow(non_camel_case_types)]
+                    struct RotateKeysSvc<T: Auth>(pub Arc<T>);
+                    impl<T: Auth> tonic::server::UnaryService<super::RotateKeysRequest>
+                    for RotateKeysSvc<T> {
+                        type Response = super::RotateKeysResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RotateKeysRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Auth>::rotate_keys(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RotateKeysSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.Auth/StepUpChallenge" => {
+                    #[allow(non_camel_case_types)]
+                    struct StepUpChallengeSvc<T: Auth>(pub Arc<T>);
+                    impl<
+                        T: Auth,
+                    > tonic::server::UnaryService<super::StepUpChallengeRequest>
+                    for StepUpChallengeSvc<T> {
+                        type Response = super::CreateAuthenticationChallengeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StepUpChallengeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Auth>::step_up_challenge(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = StepUpChallengeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.Auth/StepUpVerify" => {
+                    #[allow(non_camel_case_types)]
+                    struct StepUpVerifySvc<T: Auth>(pub Arc<T>);
+                    impl<T: Auth> tonic::server::UnaryService<super::StepUpVerifyRequest>
+                    for StepUpVerifySvc<T> {
+                        type Response = super::StepUpVerifyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StepUpVerifyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Auth>::step_up_verify(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = StepUpVerifySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.Auth/GetStats" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetStatsSvc<T: Auth>(pub Arc<T>);
+                    impl<T: Auth> tonic::server::UnaryService<super::GetStatsRequest>
+                    for GetStatsSvc<T> {
+                        type Response = super::GetStatsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetStatsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Auth>::get_stats(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetStatsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(empty_body());
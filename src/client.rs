@@ -0,0 +1,170 @@
+//! `AuthSession`, a client that keeps one channel to a verifier open for every register
+//! and login it makes, for applications that log in more than once.
+//!
+//! The prover binary connects once per run; a long-lived process would otherwise pay a
+//! TCP and HTTP/2 handshake per login. `AuthSession` is cheap to clone, and clones share
+//! the channel.
+
+use std::fmt;
+
+use chaum_pedersen::{Scalar, ZkpGroup, ZKP};
+use num_bigint::BigUint;
+use tonic::{
+    transport::{Channel, Uri},
+    Status,
+};
+
+use crate::{
+    auth::{
+        auth_client::AuthClient, CreateAuthenticationChallengeRequest, RegisterRequest,
+        VerifyAuthenticationRequest,
+    },
+    messages::{Challenge, MessageError, Response},
+};
+
+/// Why an `AuthSession` call failed.
+#[derive(Debug)]
+pub enum SessionError {
+    /// The verifier could not be reached.
+    Connect(tonic::transport::Error),
+    /// The verifier answered with an error status.
+    Rpc(Status),
+    /// The verifier's challenge could not be decoded.
+    Message(MessageError),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Connect(e) => write!(f, "could not connect to the verifier: {}", e),
+            SessionError::Rpc(status) => {
+                write!(f, "the verifier returned {:?}: {}", status.code(), status.message())
+            }
+            SessionError::Message(e) => write!(f, "malformed challenge: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<Status> for SessionError {
+    fn from(status: Status) -> Self {
+        SessionError::Rpc(status)
+    }
+}
+
+impl From<MessageError> for SessionError {
+    fn from(e: MessageError) -> Self {
+        SessionError::Message(e)
+    }
+}
+
+/// A connected client and the group its proofs are computed in.
+#[derive(Debug, Clone)]
+pub struct AuthSession {
+    client: AuthClient<Channel>,
+    zkp: ZKP,
+}
+
+impl AuthSession {
+    /// Connects to the verifier at `uri`, which must use the group of `zkp`.
+    pub async fn connect(uri: Uri, zkp: ZKP) -> Result<Self, SessionError> {
+        let client = AuthClient::connect(uri).await.map_err(SessionError::Connect)?;
+        Ok(Self::new(client, zkp))
+    }
+
+    /// Wraps an already connected client, e.g. one built over TLS or a Unix socket.
+    pub fn new(client: AuthClient<Channel>, zkp: ZKP) -> Self {
+        AuthSession { client, zkp }
+    }
+
+    /// The underlying client, for the RPCs `AuthSession` does not wrap.
+    pub fn client(&mut self) -> &mut AuthClient<Channel> {
+        &mut self.client
+    }
+
+    /// Registers `user` with the public keys of secret `x`.
+    pub async fn register(&mut self, user: &str, x: &BigUint) -> Result<(), SessionError> {
+        let (y1, y2) = self.zkp.register_values(x);
+        self.client
+            .register(RegisterRequest {
+                user: user.to_string(),
+                y1,
+                y2,
+                ..Default::default()
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Proves knowledge of `x` to log in as `user`, returning the new session id.
+    pub async fn login(&mut self, user: &str, x: &BigUint) -> Result<String, SessionError> {
+        let zkp = &self.zkp;
+        let k = zkp.generate_nonzero_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge = self
+            .client
+            .create_authentication_challenge(CreateAuthenticationChallengeRequest {
+                user: user.to_string(),
+                r1: zkp.element_to_bytes(&r1),
+                r2: zkp.element_to_bytes(&r2),
+                ..Default::default()
+            })
+            .await?
+            .into_inner();
+        let Challenge { auth_id, c } = Challenge::try_from(challenge)?;
+
+        let s = zkp.solve(&k, &Scalar::reduce(zkp, &c), &Scalar::reduce(zkp, x));
+        let response = self
+            .client
+            .verify_authentication(VerifyAuthenticationRequest::from(Response {
+                auth_id,
+                s: s.into_inner(),
+            }))
+            .await?;
+        Ok(response.into_inner().session_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{auth::ValidateSessionRequest, testing::spawn_test_server};
+
+    #[tokio::test]
+    async fn test_one_session_serves_many_logins() {
+        let (uri, server) = spawn_test_server().await;
+        let mut session = AuthSession::connect(uri, ZKP::new()).await.unwrap();
+        let x = BigUint::from(123456789u64);
+        session.register("alice", &x).await.unwrap();
+
+        let mut session_ids = Vec::new();
+        for _ in 0..5 {
+            let session_id = session.login("alice", &x).await.unwrap();
+            let user = session
+                .client()
+                .validate_session(ValidateSessionRequest {
+                    session_id: session_id.clone(),
+                })
+                .await
+                .unwrap()
+                .into_inner()
+                .user;
+            assert_eq!(user, "alice");
+            session_ids.push(session_id);
+        }
+        session_ids.sort();
+        session_ids.dedup();
+        assert_eq!(session_ids.len(), 5);
+
+        // A clone shares the channel, and a wrong secret is still refused over it
+        let err = session.clone().login("alice", &(&x + 1u32)).await.unwrap_err();
+        assert!(
+            matches!(&err, SessionError::Rpc(status) if status.code() == tonic::Code::PermissionDenied),
+            "got {:?}",
+            err
+        );
+
+        server.shutdown().await;
+    }
+}
@@ -0,0 +1,11 @@
+//! The gRPC side of the protocol: the generated `auth` messages and client, an
+//! `AuthSession` that reuses one connection, typed views of the challenge messages, the
+//! `Auth` server the `verifier` binary runs, and a throwaway server for integration tests.
+
+pub mod auth {
+    include!("./auth.rs");
+}
+pub mod client;
+pub mod messages;
+pub mod server;
+pub mod testing;
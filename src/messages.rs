@@ -0,0 +1,177 @@
+//! Typed views of the challenge and answer messages, holding `BigUint`s instead of the
+//! wire bytes.
+//!
+//! Numbers go onto the wire left-padded to `SCALAR_BYTES`, the width a verifier over the
+//! default group sends, so a message converted to its typed form and back keeps its bytes.
+//! Wider values, from larger groups, are sent unpadded.
+
+use std::fmt;
+
+use num_bigint::BigUint;
+
+use chaum_pedersen::encoding::{biguint_from_hex, to_fixed_bytes, SCALAR_BYTES};
+
+use crate::auth::{CreateAuthenticationChallengeResponse, VerifyAuthenticationRequest};
+
+/// A challenge `c` the verifier issued as `auth_id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    pub auth_id: String,
+    pub c: BigUint,
+}
+
+/// The prover's answer `s` to challenge `auth_id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub auth_id: String,
+    pub s: BigUint,
+}
+
+/// Why a proto message has no typed form.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageError {
+    /// The number was sent with no bytes, or as an empty hex string.
+    EmptyField(&'static str),
+    /// The hex encoding of the number does not parse.
+    InvalidHex(&'static str, hex::FromHexError),
+}
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageError::EmptyField(field) => write!(f, "'{}' must not be empty", field),
+            MessageError::InvalidHex(field, e) => write!(f, "'{}' is not valid hex: {}", field, e),
+        }
+    }
+}
+
+impl std::error::Error for MessageError {}
+
+fn decode(field: &'static str, bytes: &[u8], hex: Option<&str>) -> Result<BigUint, MessageError> {
+    match hex {
+        Some("") => Err(MessageError::EmptyField(field)),
+        Some(hex) => biguint_from_hex(hex).map_err(|e| MessageError::InvalidHex(field, e)),
+        None if bytes.is_empty() => Err(MessageError::EmptyField(field)),
+        None => Ok(BigUint::from_bytes_be(bytes)),
+    }
+}
+
+impl From<Challenge> for CreateAuthenticationChallengeResponse {
+    fn from(challenge: Challenge) -> Self {
+        CreateAuthenticationChallengeResponse {
+            auth_id: challenge.auth_id,
+            c: to_fixed_bytes(&challenge.c, SCALAR_BYTES),
+        }
+    }
+}
+
+impl TryFrom<CreateAuthenticationChallengeResponse> for Challenge {
+    type Error = MessageError;
+
+    fn try_from(message: CreateAuthenticationChallengeResponse) -> Result<Self, Self::Error> {
+        Ok(Challenge {
+            c: decode("c", &message.c, None)?,
+            auth_id: message.auth_id,
+        })
+    }
+}
+
+impl From<Response> for VerifyAuthenticationRequest {
+    fn from(response: Response) -> Self {
+        VerifyAuthenticationRequest {
+            auth_id: response.auth_id,
+            s: to_fixed_bytes(&response.s, SCALAR_BYTES),
+            ..Default::default()
+        }
+    }
+}
+
+/// Takes `s` from `s_hex` when it is set, as the verifier does. The other optional fields
+/// are dropped.
+impl TryFrom<VerifyAuthenticationRequest> for Response {
+    type Error = MessageError;
+
+    fn try_from(message: VerifyAuthenticationRequest) -> Result<Self, Self::Error> {
+        Ok(Response {
+            s: decode("s", &message.s, message.s_hex.as_deref())?,
+            auth_id: message.auth_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenge_round_trips_and_keeps_leading_zeros() {
+        let mut c = vec![0u8; SCALAR_BYTES];
+        c[2] = 0x7f;
+        c[SCALAR_BYTES - 1] = 0x01;
+        let message = CreateAuthenticationChallengeResponse {
+            auth_id: "a1".to_string(),
+            c: c.clone(),
+        };
+
+        let challenge = Challenge::try_from(message.clone()).unwrap();
+        assert_eq!(challenge.auth_id, "a1");
+        assert_eq!(challenge.c, BigUint::from_bytes_be(&c));
+        assert_eq!(CreateAuthenticationChallengeResponse::from(challenge.clone()), message);
+
+        // A small challenge is padded out to the full width on the way back
+        let small = Challenge {
+            auth_id: "a2".to_string(),
+            c: BigUint::from(5u32),
+        };
+        let message = CreateAuthenticationChallengeResponse::from(small.clone());
+        assert_eq!(message.c.len(), SCALAR_BYTES);
+        assert_eq!(message.c[SCALAR_BYTES - 1], 5);
+        assert_eq!(Challenge::try_from(message).unwrap(), small);
+    }
+
+    #[test]
+    fn response_round_trips_and_keeps_leading_zeros() {
+        let response = Response {
+            auth_id: "a1".to_string(),
+            s: BigUint::from(0x0102u32),
+        };
+        let message = VerifyAuthenticationRequest::from(response.clone());
+        assert_eq!(message.s[..SCALAR_BYTES - 2], [0u8; SCALAR_BYTES - 2]);
+        assert_eq!(message.s[SCALAR_BYTES - 2..], [1, 2]);
+        assert_eq!(message.s_hex, None);
+        assert_eq!(Response::try_from(message.clone()).unwrap(), response);
+
+        let hex = VerifyAuthenticationRequest {
+            auth_id: "a1".to_string(),
+            s_hex: Some("0102".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(Response::try_from(hex).unwrap(), response);
+    }
+
+    #[test]
+    fn empty_or_malformed_numbers_are_refused() {
+        let message = CreateAuthenticationChallengeResponse {
+            auth_id: "a1".to_string(),
+            c: Vec::new(),
+        };
+        assert_eq!(Challenge::try_from(message), Err(MessageError::EmptyField("c")));
+
+        let message = VerifyAuthenticationRequest {
+            auth_id: "a1".to_string(),
+            s_hex: Some("xyz".to_string()),
+            ..Default::default()
+        };
+        let err = Response::try_from(message).unwrap_err();
+        assert!(matches!(err, MessageError::InvalidHex("s", _)), "{:?}", err);
+
+        // An empty hex string would otherwise parse as 0
+        let message = VerifyAuthenticationRequest {
+            auth_id: "a1".to_string(),
+            s: vec![1],
+            s_hex: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(Response::try_from(message), Err(MessageError::EmptyField("s")));
+    }
+}
@@ -1,42 +1,536 @@
-use std::io::{self, stdin, Write};
+use std::future::Future;
+use std::io::{self, stdin, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::process::ExitCode;
+use std::time::Duration;
+use clap::{Parser, Subcommand, ValueEnum};
 use num_bigint::BigUint;
-use tonic::transport::Channel;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig};
+use tonic::{Code, Status};
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
 
 pub mod auth {
     include!("./auth.rs");
 }
 
 use auth::{
-    auth_client::AuthClient, CreateAuthenticationChallengeRequest, VerifyAuthenticationRequest,
-    RegisterRequest,
+    auth_client::AuthClient, CreateAuthenticationChallengeRequest,
+    CreateAuthenticationChallengeResponse, RegisterRequest, VerifyAuthenticationRequest,
+    VerifyAuthenticationResponse,
 };
 
-use chaum_pedersen::ZKP;
+use zeroize::Zeroizing;
+use zkp::{
+    encoding::biguint_to_hex, secret::Secret, GroupSize, RegistrationBundle, Scalar, ZkpGroup, ZKP,
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "prover", about = "Chaum-Pedersen authentication client")]
+struct Args {
+    /// URI of the verifier to connect to
+    #[arg(long, default_value = "http://127.0.0.1:41337")]
+    server: String,
+
+    /// Connect to a verifier listening on this Unix domain socket instead of --server
+    #[arg(long, conflicts_with_all = ["server", "ca_cert"])]
+    uds: Option<PathBuf>,
+
+    /// Group the proofs are computed in; must match the verifier's
+    #[arg(long, value_enum, default_value_t = Group::Modp1024)]
+    group: Group,
+
+    /// PEM CA certificate to verify the verifier's TLS certificate with; enables TLS
+    #[arg(long)]
+    ca_cert: Option<PathBuf>,
+
+    /// How many times to try reaching the verifier before giving up
+    #[arg(long, default_value_t = 5)]
+    connect_attempts: u32,
+
+    /// Seconds to wait for each call to the verifier before giving up
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: u64,
+
+    /// Refuse passwords shorter than this many characters; empty ones are always refused
+    #[arg(long, default_value_t = DEFAULT_MIN_PASSWORD_LEN)]
+    min_password_len: usize,
+
+    /// What to do; defaults to `register-and-login`
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Subcommand)]
+enum Command {
+    /// Register a new username and password
+    Register {
+        /// Send the user and public keys of a `RegistrationBundle` JSON file instead of
+        /// prompting for them
+        #[arg(long)]
+        from_file: Option<PathBuf>,
+    },
+    /// Log in as an already registered user
+    Login,
+    /// Register, then log in with the new account
+    #[default]
+    RegisterAndLogin,
+}
+
+impl Command {
+    fn registers(&self) -> bool {
+        matches!(self, Command::Register { .. } | Command::RegisterAndLogin)
+    }
+
+    fn logs_in(&self) -> bool {
+        matches!(self, Command::Login | Command::RegisterAndLogin)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Group {
+    /// RFC 5114 1024-bit MODP group with a 160-bit subgroup
+    #[value(name = "1024")]
+    Modp1024,
+    /// RFC 3526 2048-bit MODP group
+    #[value(name = "2048")]
+    Modp2048,
+    /// RFC 3526 3072-bit MODP group
+    #[value(name = "3072")]
+    Modp3072,
+}
+
+impl Group {
+    fn zkp(self) -> ZKP {
+        ZKP::for_group(match self {
+            Group::Modp1024 => GroupSize::Modp1024,
+            Group::Modp2048 => GroupSize::Modp2048,
+            Group::Modp3072 => GroupSize::Modp3072,
+        })
+    }
+}
+
+/// How the prover encodes numbers in its requests.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum WireFormat {
+    /// Raw big-endian bytes in the `bytes` fields.
+    #[default]
+    Bytes,
+    /// Hex strings in the optional `*_hex` fields, for debugging.
+    Hex,
+}
+
+impl WireFormat {
+    /// Reads the format from `PROVER_WIRE_FORMAT` (`bytes` or `hex`), defaulting to bytes.
+    fn from_env() -> Self {
+        match std::env::var("PROVER_WIRE_FORMAT") {
+            Ok(value) if value.eq_ignore_ascii_case("hex") => WireFormat::Hex,
+            _ => WireFormat::Bytes,
+        }
+    }
+}
+
+/// Why the prover's register/login flow stopped.
+#[derive(Debug)]
+enum ProverError {
+    /// Reading user input or the CA certificate failed.
+    Io(io::Error),
+    /// The verifier could not be reached.
+    Connection(String),
+    /// The username is already registered on the verifier.
+    AlreadyRegistered,
+    /// The verifier already issued a challenge for these commitments, e.g. because a
+    /// challenge request was sent twice. Logging in again draws a fresh nonce.
+    CommitmentReused,
+    /// The verifier rejected the proof, which means the password was wrong.
+    AuthRejected,
+    /// Any other error status returned by the verifier, boxed to keep `ProverError` small.
+    Server(Box<Status>),
+    /// The verifier did not answer a call within the deadline.
+    Timeout(Duration),
+    /// The password entered was empty.
+    EmptyPassword,
+    /// The password entered was shorter than `--min-password-len` characters.
+    WeakPassword(usize),
+}
+
+impl std::fmt::Display for ProverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProverError::Io(e) => write!(f, "{}", e),
+            ProverError::Connection(e) => write!(f, "could not connect to the verifier: {}", e),
+            ProverError::AlreadyRegistered => write!(f, "that username is already registered"),
+            ProverError::CommitmentReused => {
+                write!(f, "the verifier has already seen this commitment; try again")
+            }
+            ProverError::AuthRejected => write!(f, "authentication rejected: wrong password"),
+            ProverError::Server(status) => {
+                write!(f, "the verifier returned {:?}: {}", status.code(), status.message())
+            }
+            ProverError::Timeout(deadline) => {
+                write!(f, "the verifier did not answer within {:?}", deadline)
+            }
+            ProverError::EmptyPassword => write!(f, "the password must not be empty"),
+            ProverError::WeakPassword(min_len) => {
+                write!(f, "the password must be at least {} characters long", min_len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProverError {}
+
+impl From<io::Error> for ProverError {
+    fn from(e: io::Error) -> Self {
+        ProverError::Io(e)
+    }
+}
+
+impl From<tonic::transport::Error> for ProverError {
+    fn from(e: tonic::transport::Error) -> Self {
+        ProverError::Connection(e.to_string())
+    }
+}
+
+impl From<Status> for ProverError {
+    fn from(status: Status) -> Self {
+        match status.code() {
+            Code::AlreadyExists => ProverError::AlreadyRegistered,
+            Code::Aborted => ProverError::CommitmentReused,
+            Code::PermissionDenied => ProverError::AuthRejected,
+            Code::Unavailable => ProverError::Connection(status.message().to_string()),
+            _ => ProverError::Server(Box::new(status)),
+        }
+    }
+}
 
 #[tokio::main(flavor = "current_thread")]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let (alpha, beta, p, q) = ZKP::get_constants();
-    let zkp = ZKP { alpha, beta, p, q };
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    match run(Args::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    let mut client = AuthClient::connect("http://127.0.0.1:41337").await?;
-    println!("Connected to the server");
+async fn run(args: Args) -> Result<(), ProverError> {
+    let zkp = args.group.zkp();
+    let format = WireFormat::from_env();
+    let command = args.command.unwrap_or_default();
+
+    let backoff = Backoff {
+        max_attempts: args.connect_attempts,
+        ..Backoff::default()
+    };
+    let mut client = match args.uds {
+        Some(path) => ReconnectingAuthClient::over_uds(path, backoff),
+        None => ReconnectingAuthClient::new(args.server, args.ca_cert, backoff),
+    }
+    .with_timeout(Duration::from_secs(args.timeout));
+    client.client().await?;
+    info!("Connected to the server");
+
+    if let Command::Register {
+        from_file: Some(path),
+    } = &command
+    {
+        let bundle = RegistrationBundle::from_file(path)?;
+        register_bundle(&mut client, &zkp, &bundle, format).await?;
+        info!(user = %bundle.user, "Registration was successful");
+        return Ok(());
+    }
 
     let username = read_line("Please provide username: ")?;
-    let password_registration = read_password("Please provide password: ")?;
-    register_user(&mut client, &zkp, &username, &password_registration).await?;
-    println!("Registration was successful");
+    if command.registers() {
+        let password_registration =
+            read_password("Please provide password: ", args.min_password_len)?;
+        register_user(&mut client, &zkp, &username, &password_registration, format).await?;
+        info!(user = %username, "Registration was successful");
+    }
 
-    let password_auth = read_password("Please provide the password (to login): ")?;
-    let session_id = authenticate_user(&mut client, &zkp, &username, &password_auth, &zkp.q).await?;
-    println!("Login successful! session_id: {}", session_id);
+    if command.logs_in() {
+        let prompt = if command.registers() {
+            "Please provide the password (to login): "
+        } else {
+            "Please provide password: "
+        };
+        let password_auth = read_password(prompt, args.min_password_len)?;
+        let session_id =
+            authenticate_user(&mut client, &zkp, &username, &password_auth, format)
+                .await?;
+        info!(user = %username, "Login successful! session_id: {}", session_id);
+    }
 
     Ok(())
 }
 
+/// The three calls the prover makes to a verifier. The binary talks gRPC through
+/// `AuthClient`; tests can answer in-process instead.
+#[tonic::async_trait]
+trait Transport {
+    async fn register(&mut self, request: RegisterRequest) -> Result<(), ProverError>;
+
+    async fn challenge(
+        &mut self,
+        request: CreateAuthenticationChallengeRequest,
+    ) -> Result<CreateAuthenticationChallengeResponse, ProverError>;
+
+    async fn verify(
+        &mut self,
+        request: VerifyAuthenticationRequest,
+    ) -> Result<VerifyAuthenticationResponse, ProverError>;
+}
+
+#[tonic::async_trait]
+impl Transport for AuthClient<Channel> {
+    async fn register(&mut self, request: RegisterRequest) -> Result<(), ProverError> {
+        // We don't need the response body if it's empty, just check for errors
+        AuthClient::register(self, request).await?;
+        Ok(())
+    }
+
+    async fn challenge(
+        &mut self,
+        request: CreateAuthenticationChallengeRequest,
+    ) -> Result<CreateAuthenticationChallengeResponse, ProverError> {
+        Ok(self.create_authentication_challenge(request).await?.into_inner())
+    }
+
+    async fn verify(
+        &mut self,
+        request: VerifyAuthenticationRequest,
+    ) -> Result<VerifyAuthenticationResponse, ProverError> {
+        Ok(self.verify_authentication(request).await?.into_inner())
+    }
+}
+
+/// How `ReconnectingAuthClient` spaces out its attempts: `initial`, then doubling up to
+/// `max`, for at most `max_attempts` tries in total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Backoff {
+    max_attempts: u32,
+    initial: Duration,
+    max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            max_attempts: 5,
+            initial: Duration::from_millis(200),
+            max: Duration::from_secs(5),
+        }
+    }
+}
+
+/// How long `ReconnectingAuthClient` waits for each call unless told otherwise.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Connector<T> =
+    Box<dyn FnMut() -> Pin<Box<dyn Future<Output = Result<T, ProverError>> + Send>> + Send>;
+
+/// A transport that connects on first use and again after a transport failure, retrying
+/// with exponential backoff while the verifier is unreachable.
+///
+/// A call is not retried once sent: a register, challenge or verify that reached the
+/// verifier before the connection dropped would fail differently on a second try. A
+/// resent challenge request, for one, is refused as reusing its commitments.
+///
+/// A call the verifier does not answer within `timeout` fails with `ProverError::Timeout`
+/// and drops the connection, which may be the part that hung.
+struct ReconnectingAuthClient<T = AuthClient<Channel>> {
+    connect: Connector<T>,
+    backoff: Backoff,
+    timeout: Duration,
+    client: Option<T>,
+}
+
+impl ReconnectingAuthClient {
+    fn new(server: String, ca_cert: Option<PathBuf>, backoff: Backoff) -> Self {
+        let connector = move || {
+            let server = server.clone();
+            let ca_cert = ca_cert.clone();
+            Box::pin(async move { connect(&server, ca_cert.as_deref()).await })
+                as Pin<Box<dyn Future<Output = _> + Send>>
+        };
+        ReconnectingAuthClient::with_connector(Box::new(connector), backoff)
+    }
+
+    /// As `new`, connecting to a verifier on the Unix domain socket at `path`.
+    fn over_uds(path: PathBuf, backoff: Backoff) -> Self {
+        let connector = move || {
+            let path = path.clone();
+            Box::pin(async move { connect_uds(path).await })
+                as Pin<Box<dyn Future<Output = _> + Send>>
+        };
+        ReconnectingAuthClient::with_connector(Box::new(connector), backoff)
+    }
+}
+
+impl<T: Transport + Send> ReconnectingAuthClient<T> {
+    fn with_connector(connect: Connector<T>, backoff: Backoff) -> Self {
+        ReconnectingAuthClient {
+            connect,
+            backoff,
+            timeout: DEFAULT_TIMEOUT,
+            client: None,
+        }
+    }
+
+    /// Sets how long each call may take, in place of `DEFAULT_TIMEOUT`.
+    fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Awaits `call` for at most `timeout`.
+    async fn deadline<R>(
+        timeout: Duration,
+        call: impl Future<Output = Result<R, ProverError>>,
+    ) -> Result<R, ProverError> {
+        tokio::time::timeout(timeout, call)
+            .await
+            .unwrap_or(Err(ProverError::Timeout(timeout)))
+    }
+
+    /// Returns the connected transport, connecting first if there is none.
+    async fn client(&mut self) -> Result<&mut T, ProverError> {
+        if self.client.is_none() {
+            let mut attempts = Attempts::new(self.backoff);
+            let client = loop {
+                match (self.connect)().await {
+                    Ok(client) => break client,
+                    Err(e) if attempts.wait_after(&e).await => {}
+                    Err(e) => return Err(e),
+                }
+            };
+            self.client = Some(client);
+        }
+        Ok(self.client.as_mut().expect("connected above"))
+    }
+
+    /// Forgets the connection after a transport failure or timeout so the next call
+    /// reconnects.
+    fn forget_if_disconnected<R>(&mut self, result: &Result<R, ProverError>) {
+        if let Err(ProverError::Connection(_) | ProverError::Timeout(_)) = result {
+            self.client = None;
+        }
+    }
+}
+
+/// The attempts made so far for one operation under a `Backoff`.
+struct Attempts {
+    backoff: Backoff,
+    made: u32,
+    delay: Duration,
+}
+
+impl Attempts {
+    fn new(backoff: Backoff) -> Self {
+        Attempts {
+            backoff,
+            made: 1,
+            delay: backoff.initial,
+        }
+    }
+
+    /// Sleeps and returns true if `err` is a connection error worth another attempt.
+    async fn wait_after(&mut self, err: &ProverError) -> bool {
+        let ProverError::Connection(e) = err else {
+            return false;
+        };
+        if self.made >= self.backoff.max_attempts {
+            return false;
+        }
+        warn!(attempt = self.made, "Verifier unreachable, retrying in {:?}: {}", self.delay, e);
+        tokio::time::sleep(self.delay).await;
+        self.delay = (self.delay * 2).min(self.backoff.max);
+        self.made += 1;
+        true
+    }
+}
+
+#[tonic::async_trait]
+impl<T: Transport + Send> Transport for ReconnectingAuthClient<T> {
+    async fn register(&mut self, request: RegisterRequest) -> Result<(), ProverError> {
+        let timeout = self.timeout;
+        let result = Self::deadline(timeout, self.client().await?.register(request)).await;
+        self.forget_if_disconnected(&result);
+        result
+    }
+
+    async fn challenge(
+        &mut self,
+        request: CreateAuthenticationChallengeRequest,
+    ) -> Result<CreateAuthenticationChallengeResponse, ProverError> {
+        let timeout = self.timeout;
+        let result = Self::deadline(timeout, self.client().await?.challenge(request)).await;
+        self.forget_if_disconnected(&result);
+        result
+    }
+
+    async fn verify(
+        &mut self,
+        request: VerifyAuthenticationRequest,
+    ) -> Result<VerifyAuthenticationResponse, ProverError> {
+        let timeout = self.timeout;
+        let result = Self::deadline(timeout, self.client().await?.verify(request)).await;
+        self.forget_if_disconnected(&result);
+        result
+    }
+}
+
 // -----------------------------------------------------------
 // HELPER FUNCTIONS
 // -----------------------------------------------------------
 
+/// Connects to the verifier, over TLS when a CA certificate is given.
+async fn connect(
+    server: &str,
+    ca_cert: Option<&std::path::Path>,
+) -> Result<AuthClient<Channel>, ProverError> {
+    let mut endpoint = Channel::from_shared(server.to_string())
+        .map_err(|e| ProverError::Connection(format!("invalid server URI: {}", e)))?;
+    if let Some(ca_cert) = ca_cert {
+        let ca = Certificate::from_pem(std::fs::read(ca_cert)?);
+        endpoint = endpoint.tls_config(ClientTlsConfig::new().ca_certificate(ca))?;
+    }
+    Ok(AuthClient::new(endpoint.connect().await?))
+}
+
+/// Connects to a verifier listening on the Unix domain socket at `path`.
+#[cfg(unix)]
+async fn connect_uds(path: PathBuf) -> Result<AuthClient<Channel>, ProverError> {
+    // The URI is only needed to build the endpoint; every connection goes to `path`
+    let channel = tonic::transport::Endpoint::from_static("http://localhost")
+        .connect_with_connector(tower::service_fn(move |_: tonic::transport::Uri| {
+            let path = path.clone();
+            async move {
+                let stream = tokio::net::UnixStream::connect(path).await?;
+                Ok::<_, io::Error>(hyper_util::rt::TokioIo::new(stream))
+            }
+        }))
+        .await?;
+    Ok(AuthClient::new(channel))
+}
+
+#[cfg(not(unix))]
+async fn connect_uds(_path: PathBuf) -> Result<AuthClient<Channel>, ProverError> {
+    Err(ProverError::Io(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix domain sockets are not available on this platform",
+    )))
+}
+
 /// Reads a single line from stdin after printing a prompt.
 /// Trims trailing whitespace/newlines.
 fn read_line(prompt: &str) -> io::Result<String> {
@@ -44,37 +538,79 @@ fn read_line(prompt: &str) -> io::Result<String> {
     // Ensure we flush stdout so user sees the prompt immediately
     io::stdout().flush()?;
 
+    read_trimmed_line(&mut stdin().lock())
+}
+
+/// Reads one line from `reader` with surrounding whitespace removed.
+fn read_trimmed_line(reader: &mut impl BufRead) -> io::Result<String> {
     let mut buf = String::new();
-    stdin().read_line(&mut buf)?;
+    reader.read_line(&mut buf)?;
     Ok(buf.trim().to_string())
 }
 
-/// Reads a password (or any secret-like input) from stdin after printing a prompt.
-/// In a real CLI application, you might want to mask the input or use a secure method.
-fn read_password(prompt: &str) -> io::Result<BigUint> {
-    let input_str = read_line(prompt)?;
+/// Passwords shorter than this are refused unless `--min-password-len` says otherwise.
+const DEFAULT_MIN_PASSWORD_LEN: usize = 8;
+
+/// Refuses an empty `password`, or one of fewer than `min_len` characters, before it
+/// becomes a secret exponent small enough to find by trying every value.
+fn check_password(password: &str, min_len: usize) -> Result<(), ProverError> {
+    match password.chars().count() {
+        0 => Err(ProverError::EmptyPassword),
+        len if len < min_len => Err(ProverError::WeakPassword(min_len)),
+        _ => Ok(()),
+    }
+}
+
+/// Reads a password (or any secret-like input) after printing a prompt, refusing it if
+/// `check_password` does.
+/// On a terminal the input is not echoed; when stdin is piped it is read as a plain line.
+/// The text and the returned exponent are wiped when dropped.
+fn read_password(prompt: &str, min_len: usize) -> Result<Secret, ProverError> {
+    let input_str = if stdin().is_terminal() {
+        let raw = Zeroizing::new(rpassword::prompt_password(prompt)?);
+        Zeroizing::new(raw.trim().to_string())
+    } else {
+        Zeroizing::new(read_line(prompt)?)
+    };
+    check_password(&input_str, min_len)?;
     // Convert user input to BigUint. In production, you'd handle invalid hex/base cases carefully.
-    Ok(BigUint::from_bytes_be(input_str.as_bytes()))
+    Ok(Secret::from(BigUint::from_bytes_be(input_str.as_bytes())))
 }
 
 /// Registers a user by sending `y1` and `y2` to the server.
 async fn register_user(
-    client: &mut AuthClient<Channel>,
+    client: &mut impl Transport,
     zkp: &ZKP,
     username: &str,
     password: &BigUint,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let (y1, y2) = zkp.compute_pair(password);
+    format: WireFormat,
+) -> Result<(), ProverError> {
+    register_bundle(client, zkp, &zkp.registration_bundle(username, password), format).await
+}
 
-    let request = RegisterRequest {
-        user: username.to_string(),
-        y1: y1.to_bytes_be(),
-        y2: y2.to_bytes_be(),
+/// Registers the user and public keys of `bundle`, which may have been made offline.
+async fn register_bundle(
+    client: &mut impl Transport,
+    zkp: &ZKP,
+    bundle: &RegistrationBundle,
+    format: WireFormat,
+) -> Result<(), ProverError> {
+    let request = match format {
+        WireFormat::Bytes => RegisterRequest {
+            user: bundle.user.clone(),
+            y1: zkp.element_to_bytes(&bundle.y1),
+            y2: zkp.element_to_bytes(&bundle.y2),
+            ..Default::default()
+        },
+        WireFormat::Hex => RegisterRequest {
+            user: bundle.user.clone(),
+            y1_hex: Some(biguint_to_hex(&bundle.y1)),
+            y2_hex: Some(biguint_to_hex(&bundle.y2)),
+            ..Default::default()
+        },
     };
 
-    // We don't need the response body if it's empty, just check for errors
-    client.register(request).await?;
-    Ok(())
+    client.register(request).await
 }
 
 /// Performs the authentication flow:
@@ -83,40 +619,463 @@ async fn register_user(
 ///  3) solve for s = k - c*x mod q
 ///  4) send s back to get session_id
 async fn authenticate_user(
-    client: &mut AuthClient<Channel>,
+    client: &mut impl Transport,
     zkp: &ZKP,
     username: &str,
     password: &BigUint,
-    q: &BigUint,
-) -> Result<String, Box<dyn std::error::Error>> {
-    // Generate ephemeral secret k
-    let k = ZKP::generate_random_number_below(q);
+    format: WireFormat,
+) -> Result<String, ProverError> {
+    // Generate ephemeral secret k; anyone who learns it can recover x from s
+    let k = zkp.generate_nonzero_scalar();
 
     // Commitments
     let (r1, r2) = zkp.compute_pair(&k);
 
-    let challenge_req = CreateAuthenticationChallengeRequest {
-        user: username.to_string(),
-        r1: r1.to_bytes_be(),
-        r2: r2.to_bytes_be(),
+    let challenge_req = match format {
+        WireFormat::Bytes => CreateAuthenticationChallengeRequest {
+            user: username.to_string(),
+            r1: zkp.element_to_bytes(&r1),
+            r2: zkp.element_to_bytes(&r2),
+            ..Default::default()
+        },
+        WireFormat::Hex => CreateAuthenticationChallengeRequest {
+            user: username.to_string(),
+            r1_hex: Some(biguint_to_hex(&r1)),
+            r2_hex: Some(biguint_to_hex(&r2)),
+            ..Default::default()
+        },
     };
 
-    let challenge_resp = client
-        .create_authentication_challenge(challenge_req)
-        .await?
-        .into_inner();
+    let challenge_resp = client.challenge(challenge_req).await?;
 
     let auth_id = challenge_resp.auth_id;
-    let c = BigUint::from_bytes_be(&challenge_resp.c);
+    let c = Scalar::reduce(zkp, &BigUint::from_bytes_be(&challenge_resp.c));
 
     // Solve for s = k - c*x mod q
-    let s = zkp.solve(&k, &c, password);
+    let s = zkp.solve(&k, &c, &Scalar::reduce(zkp, password));
 
-    let verify_req = VerifyAuthenticationRequest {
-        auth_id,
-        s: s.to_bytes_be(),
+    // Echo c back so a server that issued a different challenge says so
+    let verify_req = match format {
+        WireFormat::Bytes => VerifyAuthenticationRequest {
+            auth_id,
+            s: zkp.scalar_to_bytes(&s),
+            c: Some(challenge_resp.c),
+            ..Default::default()
+        },
+        WireFormat::Hex => VerifyAuthenticationRequest {
+            auth_id,
+            s_hex: Some(biguint_to_hex(&s)),
+            c: Some(challenge_resp.c),
+            ..Default::default()
+        },
     };
 
-    let verify_resp = client.verify_authentication(verify_req).await?.into_inner();
+    let verify_resp = client.verify(verify_req).await?;
     Ok(verify_resp.session_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+    use zkp::{
+        encoding::biguint_from_hex,
+        service::{AuthError, AuthService, Verification},
+    };
+
+    /// Answers the prover's calls with an `AuthService` in the same process, so the full
+    /// flow runs without binding a port.
+    #[derive(Default)]
+    struct InProcess {
+        service: Arc<AuthService>,
+    }
+
+    /// The status the gRPC verifier would reply with, so errors still go through
+    /// `ProverError::from(Status)`.
+    fn status(err: AuthError) -> Status {
+        match err {
+            AuthError::UserAlreadyExists(_) => Status::already_exists(err.to_string()),
+            AuthError::CommitmentReused(_) => Status::aborted(err.to_string()),
+            AuthError::IncorrectSolution(_) | AuthError::SwappedPublicKeys(_) => {
+                Status::permission_denied(err.to_string())
+            }
+            AuthError::UserNotFound(_) | AuthError::ChallengeNotFound(_) => {
+                Status::not_found(err.to_string())
+            }
+            _ => Status::invalid_argument(err.to_string()),
+        }
+    }
+
+    fn field(bytes: Vec<u8>, hex: Option<String>) -> Vec<u8> {
+        match hex {
+            Some(hex) => biguint_from_hex(&hex).unwrap().to_bytes_be(),
+            None => bytes,
+        }
+    }
+
+    #[tonic::async_trait]
+    impl Transport for InProcess {
+        async fn register(&mut self, request: RegisterRequest) -> Result<(), ProverError> {
+            let y1 = field(request.y1, request.y1_hex);
+            let y2 = field(request.y2, request.y2_hex);
+            self.service
+                .register(&request.user, &y1, &y2)
+                .map_err(|e| status(e).into())
+        }
+
+        async fn challenge(
+            &mut self,
+            request: CreateAuthenticationChallengeRequest,
+        ) -> Result<CreateAuthenticationChallengeResponse, ProverError> {
+            let r1 = field(request.r1, request.r1_hex);
+            let r2 = field(request.r2, request.r2_hex);
+            let challenge = self
+                .service
+                .create_challenge(&request.user, &r1, &r2)
+                .map_err(status)?;
+            Ok(CreateAuthenticationChallengeResponse {
+                auth_id: challenge.auth_id,
+                c: self.service.group.scalar_to_bytes(&challenge.c),
+            })
+        }
+
+        async fn verify(
+            &mut self,
+            request: VerifyAuthenticationRequest,
+        ) -> Result<VerifyAuthenticationResponse, ProverError> {
+            let s = field(request.s, request.s_hex);
+            if let Some(c) = &request.c {
+                self.service
+                    .check_challenge(&request.auth_id, c)
+                    .map_err(status)?;
+            }
+            match self.service.verify(&request.auth_id, &s).map_err(status)? {
+                Verification::Login(login) => Ok(VerifyAuthenticationResponse {
+                    session_id: login.session_id,
+                    refresh_token: login.refresh_token,
+                }),
+                Verification::ProofOnly { .. } => Ok(VerifyAuthenticationResponse::default()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_flow_over_in_process_transport() {
+        let zkp = ZKP::new();
+        let password = BigUint::from_bytes_be(b"correct horse");
+
+        for format in [WireFormat::Bytes, WireFormat::Hex] {
+            let mut transport = InProcess::default();
+            register_user(&mut transport, &zkp, "alice", &password, format)
+                .await
+                .unwrap();
+            let session_id =
+                authenticate_user(&mut transport, &zkp, "alice", &password, format)
+                    .await
+                    .unwrap();
+            assert_eq!(transport.service.validate_session(&session_id).unwrap(), "alice");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wrong_password_is_auth_rejected() {
+        let mut client = InProcess::default();
+        let zkp = ZKP::new();
+        let password = BigUint::from_bytes_be(b"correct horse");
+        let wrong_password = BigUint::from_bytes_be(b"battery staple");
+
+        register_user(&mut client, &zkp, "alice", &password, WireFormat::Bytes)
+            .await
+            .unwrap();
+        let err = authenticate_user(&mut client, &zkp, "alice", &wrong_password, WireFormat::Bytes)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProverError::AuthRejected), "got {:?}", err);
+        assert_eq!(err.to_string(), "authentication rejected: wrong password");
+
+        let err = register_user(&mut client, &zkp, "alice", &password, WireFormat::Bytes)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProverError::AlreadyRegistered), "got {:?}", err);
+
+        authenticate_user(&mut client, &zkp, "alice", &password, WireFormat::Bytes)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_offline_bundle_registers_for_a_later_login() {
+        let zkp = ZKP::new();
+        let password = BigUint::from_bytes_be(b"correct horse");
+        let path = std::env::temp_dir().join(format!("prover-bundle-{}.json", std::process::id()));
+        zkp.registration_bundle("alice", &password).to_file(&path).unwrap();
+
+        let mut transport = InProcess::default();
+        let bundle = RegistrationBundle::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        register_bundle(&mut transport, &zkp, &bundle, WireFormat::Bytes)
+            .await
+            .unwrap();
+        let session_id =
+            authenticate_user(&mut transport, &zkp, "alice", &password, WireFormat::Bytes)
+                .await
+                .unwrap();
+        assert_eq!(transport.service.validate_session(&session_id).unwrap(), "alice");
+    }
+
+    /// A connector that fails `failures` times with a connection error before handing out
+    /// in-process transports over `service`, counting every attempt in `attempts`.
+    fn flaky_connector(
+        service: Arc<AuthService>,
+        failures: u32,
+        attempts: Arc<AtomicU32>,
+    ) -> Connector<InProcess> {
+        Box::new(move || {
+            let service = service.clone();
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                if attempt < failures {
+                    Err(ProverError::Connection("connection refused".to_string()))
+                } else {
+                    Ok(InProcess { service })
+                }
+            })
+        })
+    }
+
+    const FAST: Backoff = Backoff {
+        max_attempts: 4,
+        initial: Duration::from_millis(1),
+        max: Duration::from_millis(4),
+    };
+
+    #[tokio::test]
+    async fn test_reconnecting_client_retries_transient_connect_failures() {
+        let service = Arc::new(AuthService::default());
+        let attempts = Arc::new(AtomicU32::new(0));
+        let mut client = ReconnectingAuthClient::with_connector(
+            flaky_connector(service.clone(), 2, attempts.clone()),
+            FAST,
+        );
+        let zkp = ZKP::new();
+        let password = BigUint::from_bytes_be(b"correct horse");
+
+        register_user(&mut client, &zkp, "alice", &password, WireFormat::Bytes)
+            .await
+            .unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        let session_id =
+            authenticate_user(&mut client, &zkp, "alice", &password, WireFormat::Bytes)
+                .await
+                .unwrap();
+        assert_eq!(service.validate_session(&session_id).unwrap(), "alice");
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_reconnecting_client_gives_up_after_max_attempts() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let mut client = ReconnectingAuthClient::with_connector(
+            flaky_connector(Arc::default(), u32::MAX, attempts.clone()),
+            FAST,
+        );
+
+        let err = client.client().await.err().unwrap();
+        assert!(matches!(err, ProverError::Connection(_)), "got {:?}", err);
+        assert_eq!(attempts.load(Ordering::SeqCst), FAST.max_attempts);
+    }
+
+    /// A verifier that accepts connections and never answers.
+    struct Unresponsive;
+
+    #[tonic::async_trait]
+    impl Transport for Unresponsive {
+        async fn register(&mut self, _request: RegisterRequest) -> Result<(), ProverError> {
+            std::future::pending().await
+        }
+
+        async fn challenge(
+            &mut self,
+            _request: CreateAuthenticationChallengeRequest,
+        ) -> Result<CreateAuthenticationChallengeResponse, ProverError> {
+            std::future::pending().await
+        }
+
+        async fn verify(
+            &mut self,
+            _request: VerifyAuthenticationRequest,
+        ) -> Result<VerifyAuthenticationResponse, ProverError> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_calls_time_out_and_reconnect() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let connects = attempts.clone();
+        let deadline = Duration::from_millis(20);
+        let mut client = ReconnectingAuthClient::with_connector(
+            Box::new(move || {
+                connects.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(Unresponsive) })
+            }),
+            FAST,
+        )
+        .with_timeout(deadline);
+        let zkp = ZKP::new();
+        let password = BigUint::from_bytes_be(b"correct horse");
+
+        let err = register_user(&mut client, &zkp, "alice", &password, WireFormat::Bytes)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProverError::Timeout(d) if d == deadline), "got {:?}", err);
+        let err = authenticate_user(&mut client, &zkp, "alice", &password, WireFormat::Bytes)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProverError::Timeout(_)), "got {:?}", err);
+        // Each timed-out call dropped the connection it hung on
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(err.to_string(), "the verifier did not answer within 20ms");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_full_flow_over_a_unix_domain_socket() {
+        let path = std::env::temp_dir().join(format!("prover-test-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let auth_impl = chaum_pedersen_protocol::server::AuthImpl::default();
+        let service = auth_impl.service.clone();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(chaum_pedersen_protocol::server::serve_uds(
+            auth_impl,
+            listener,
+            async {
+                let _ = stopped.await;
+            },
+        ));
+
+        let mut client = ReconnectingAuthClient::over_uds(path.clone(), FAST);
+        let zkp = ZKP::new();
+        let password = BigUint::from_bytes_be(b"correct horse");
+        register_user(&mut client, &zkp, "alice", &password, WireFormat::Bytes)
+            .await
+            .unwrap();
+        let session_id =
+            authenticate_user(&mut client, &zkp, "alice", &password, WireFormat::Bytes)
+                .await
+                .unwrap();
+        assert_eq!(service.validate_session(&session_id).unwrap(), "alice");
+
+        drop(client);
+        stop.send(()).unwrap();
+        server.await.unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_is_a_connection_error() {
+        let err = connect("http://127.0.0.1:1", None).await.unwrap_err();
+        assert!(matches!(err, ProverError::Connection(_)), "got {:?}", err);
+    }
+
+    #[test]
+    fn test_piped_input_fallback_reads_a_trimmed_line() {
+        // This is the path `read_password` takes when stdin is not a TTY.
+        let mut piped = io::Cursor::new("hunter2\r\nsecond line\n");
+        assert_eq!(read_trimmed_line(&mut piped).unwrap(), "hunter2");
+        assert_eq!(read_trimmed_line(&mut piped).unwrap(), "second line");
+        assert_eq!(read_trimmed_line(&mut piped).unwrap(), "");
+    }
+
+    #[test]
+    fn test_short_passwords_are_refused() {
+        let err = check_password("", 0).unwrap_err();
+        assert!(matches!(err, ProverError::EmptyPassword), "got {:?}", err);
+        let err = check_password("ab", DEFAULT_MIN_PASSWORD_LEN).unwrap_err();
+        assert!(matches!(err, ProverError::WeakPassword(8)), "got {:?}", err);
+        assert_eq!(err.to_string(), "the password must be at least 8 characters long");
+        // Characters are counted, not bytes
+        assert!(check_password("pässwörd", 9).is_err());
+
+        check_password("correct horse battery staple", DEFAULT_MIN_PASSWORD_LEN).unwrap();
+        check_password("ab", 2).unwrap();
+    }
+
+    #[test]
+    fn test_args_defaults() {
+        let args = Args::try_parse_from(["prover"]).unwrap();
+        assert_eq!(args.server, "http://127.0.0.1:41337");
+        assert_eq!(args.group, Group::Modp1024);
+        assert_eq!(args.ca_cert, None);
+        assert_eq!(args.uds, None);
+        assert_eq!(args.connect_attempts, 5);
+        assert_eq!(args.timeout, 10);
+        assert_eq!(args.min_password_len, DEFAULT_MIN_PASSWORD_LEN);
+        assert_eq!(args.command, None);
+    }
+
+    #[test]
+    fn test_args_subcommands_select_the_flow() {
+        for (name, command, registers, logs_in) in [
+            ("register", Command::Register { from_file: None }, true, false),
+            ("login", Command::Login, false, true),
+            ("register-and-login", Command::RegisterAndLogin, true, true),
+        ] {
+            let args = Args::try_parse_from(["prover", "--group", "2048", name]).unwrap();
+            assert_eq!(args.command, Some(command.clone()));
+            assert_eq!(command.registers(), registers, "{}", name);
+            assert_eq!(command.logs_in(), logs_in, "{}", name);
+        }
+        assert_eq!(Command::default(), Command::RegisterAndLogin);
+        assert!(Args::try_parse_from(["prover", "logout"]).is_err());
+
+        let args = Args::try_parse_from(["prover", "register", "--from-file", "b.json"]).unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::Register {
+                from_file: Some(PathBuf::from("b.json"))
+            })
+        );
+        assert!(Args::try_parse_from(["prover", "login", "--from-file", "b.json"]).is_err());
+    }
+
+    #[test]
+    fn test_args_overrides() {
+        let args =
+            Args::try_parse_from(["prover", "--server", "http://10.0.0.2:50051", "--group", "2048"])
+                .unwrap();
+        assert_eq!(args.server, "http://10.0.0.2:50051");
+        assert_eq!(args.group, Group::Modp2048);
+    }
+
+    #[test]
+    fn test_args_uds_replaces_the_server_uri() {
+        let args = Args::try_parse_from(["prover", "--uds", "/tmp/verifier.sock"]).unwrap();
+        assert_eq!(args.uds, Some(PathBuf::from("/tmp/verifier.sock")));
+        assert!(Args::try_parse_from([
+            "prover",
+            "--uds",
+            "/tmp/verifier.sock",
+            "--server",
+            "http://10.0.0.2:50051",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_args_ca_cert() {
+        let args = Args::try_parse_from([
+            "prover",
+            "--server",
+            "https://localhost:41337",
+            "--ca-cert",
+            "ca.pem",
+        ])
+        .unwrap();
+        assert_eq!(args.ca_cert, Some(PathBuf::from("ca.pem")));
+    }
+}
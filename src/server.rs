@@ -0,0 +1,3706 @@
+//! The gRPC `Auth` service over `AuthService`, and `serve` to run it. The `verifier`
+//! binary wraps this in a command line; `testing` runs it on a throwaway port.
+
+// Helpers return `tonic::Status` directly so handlers can `?` them; clippy flags its size.
+#![allow(clippy::result_large_err)]
+
+use std::{
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use metrics::{counter, Counter};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+use tonic::{
+    transport::{server::Router, Server, ServerTlsConfig},
+    Code, Request, Response, Status, Streaming,
+};
+use subtle::ConstantTimeEq;
+use tracing::{info, warn, Instrument};
+
+use chaum_pedersen::{
+    audit::{transcript_hash, AuditEntry, AuditLog, AuditOutcome},
+    encoding::biguint_from_hex,
+    service::{
+        AuthConfig, AuthError, AuthService, ChallengeStore, PendingChallenge, Verification,
+        DEFAULT_SLOT,
+    },
+    ZkpGroup, ZKP,
+};
+
+use crate::auth::{
+    auth_server::{Auth, AuthServer},
+    authenticate_request::Step as RequestStep,
+    authenticate_response::Step as ResponseStep,
+    rotate_keys_request::Authorization,
+    AuthenticateRequest, AuthenticateResponse, CreateAuthenticationChallengeRequest,
+    CreateAuthenticationChallengeResponse, GetStatsRequest, GetStatsResponse, ListSessionsRequest,
+    ListSessionsResponse, LogoutRequest, LogoutResponse, RefreshSessionRequest,
+    RefreshSessionResponse, RegisterRequest, RegisterResponse, RevokeAllSessionsRequest,
+    RevokeAllSessionsResponse, RevokeSessionRequest, RevokeSessionResponse, RotateKeysRequest,
+    RotateKeysResponse, SessionInfo, StepUpChallengeRequest, StepUpVerifyRequest,
+    StepUpVerifyResponse, ValidateSessionRequest, ValidateSessionResponse,
+    VerifyAuthenticationRequest, VerifyAuthenticationResponse,
+};
+
+/// Counters for the outcomes of the auth RPCs.
+#[derive(Clone, Debug)]
+pub struct Metrics {
+    pub register_total: Counter,
+    pub challenge_total: Counter,
+    pub verify_success_total: Counter,
+    pub verify_failure_total: Counter,
+}
+
+impl Default for Metrics {
+    /// Counters that record nothing.
+    fn default() -> Self {
+        Metrics {
+            register_total: Counter::noop(),
+            challenge_total: Counter::noop(),
+            verify_success_total: Counter::noop(),
+            verify_failure_total: Counter::noop(),
+        }
+    }
+}
+
+impl Metrics {
+    /// Registers the counters with the current recorder: the global one, or the one set by
+    /// `metrics::with_local_recorder`.
+    pub fn register() -> Self {
+        Metrics {
+            register_total: counter!("register_total"),
+            challenge_total: counter!("challenge_total"),
+            verify_success_total: counter!("verify_success_total"),
+            verify_failure_total: counter!("verify_failure_total"),
+        }
+    }
+}
+
+/// How many bytes longer than a group element a numeric field may be, to allow for
+/// encoders that add leading zeros.
+const FIELD_LEN_MARGIN: usize = 16;
+
+/// Largest request message the server decodes. The biggest request holds a few numbers
+/// of at most 3072 bits each, so this is ample; tonic's default is 4 MiB.
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// Request metadata key carrying the admin token.
+pub const ADMIN_TOKEN_METADATA: &str = "x-admin-token";
+
+/// The gRPC `Auth` service: decodes requests, delegates to `AuthService` and maps its
+/// errors onto `Status` codes. Clones share the same `AuthService`.
+#[derive(Debug)]
+pub struct AuthImpl<G: ZkpGroup = ZKP> {
+    pub service: Arc<AuthService<G>>,
+    /// Where issued challenges wait for their answer; by default the service's own
+    /// `auth_id_to_user`. Instances given one shared store can answer each other's
+    /// challenges, though `GetStats` then only counts those in `auth_id_to_user`.
+    pub challenges: Arc<dyn ChallengeStore<G>>,
+    /// Receives an entry for every challenge answered through `VerifyAuthentication`.
+    pub audit_log: Option<Arc<dyn AuditLog>>,
+    /// Shared secret for the admin RPCs; `None` disables them.
+    pub admin_token: Option<String>,
+    /// Let anyone call `Register`. When false only callers sending `admin_token` can,
+    /// so users must be provisioned by an operator.
+    pub allow_self_register: bool,
+    pub metrics: Metrics,
+}
+
+impl Default for AuthImpl {
+    fn default() -> Self {
+        AuthImpl::new(AuthConfig::default())
+    }
+}
+
+impl<G: ZkpGroup> Clone for AuthImpl<G> {
+    fn clone(&self) -> Self {
+        AuthImpl {
+            service: Arc::clone(&self.service),
+            challenges: Arc::clone(&self.challenges),
+            audit_log: self.audit_log.clone(),
+            admin_token: self.admin_token.clone(),
+            allow_self_register: self.allow_self_register,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<G: ZkpGroup + std::fmt::Debug + 'static> AuthImpl<G> {
+    /// Creates a service with no registered users, configured by `config`.
+    pub fn new(config: AuthConfig<G>) -> Self {
+        let service = Arc::new(AuthService::new(config));
+        AuthImpl {
+            challenges: service.auth_id_to_user.clone(),
+            service,
+            audit_log: None,
+            admin_token: None,
+            allow_self_register: true,
+            metrics: Metrics::default(),
+        }
+    }
+
+    pub fn with_group(group: G) -> Self {
+        AuthImpl::new(AuthConfig::with_group(group))
+    }
+}
+
+impl<G: ZkpGroup> AuthImpl<G> {
+    /// `AuthService::check_challenge` and then `verify` or `verify_with_keys`, with the
+    /// challenge looked up in and taken from `challenges`.
+    async fn answer(
+        &self,
+        request: &VerifyAuthenticationRequest,
+        s: &[u8],
+    ) -> Result<Verification, AuthError> {
+        self.take_answer(request, s, |auth_id, pending, s, keys| {
+            self.service.verify_pending(auth_id, pending, s, keys)
+        })
+        .await
+    }
+
+    /// Like `answer`, but opens no session: a correct answer returns the challenge it was
+    /// for, so the proof authorizes only the request carrying it.
+    async fn check_answer(
+        &self,
+        request: &VerifyAuthenticationRequest,
+        s: &[u8],
+    ) -> Result<PendingChallenge<G>, AuthError> {
+        self.take_answer(request, s, |auth_id, pending, s, keys| {
+            self.service.check_answer(auth_id, &pending, s, keys)?;
+            Ok(pending)
+        })
+        .await
+    }
+
+    /// Takes the challenge `request` answers out of `challenges` and hands it to `check`,
+    /// recording the outcome in the audit log.
+    async fn take_answer<T>(
+        &self,
+        request: &VerifyAuthenticationRequest,
+        s: &[u8],
+        check: impl FnOnce(
+            &str,
+            PendingChallenge<G>,
+            &G::Scalar,
+            Option<(&[u8], &[u8])>,
+        ) -> Result<T, AuthError>,
+    ) -> Result<T, AuthError> {
+        let auth_id = &request.auth_id;
+        let not_found = || AuthError::ChallengeNotFound(auth_id.clone());
+        // A step-up challenge is not answered here, and must survive the attempt
+        let pending = self.challenges.get(auth_id).await.ok_or_else(not_found)?;
+        if pending.step_up.is_some() {
+            return Err(not_found());
+        }
+        if let Some(c) = &request.c {
+            let c = self.service.decode_scalar("c", c)?;
+            self.service.check_pending(auth_id, &pending, &c)?;
+        }
+
+        let s = self.service.decode_scalar("s", s)?;
+        let pending = self.challenges.remove(auth_id).await.ok_or_else(not_found)?;
+        let keys = match (&request.y1, &request.y2) {
+            (None, None) => None,
+            (y1, y2) => Some((
+                y1.as_deref().unwrap_or_default(),
+                y2.as_deref().unwrap_or_default(),
+            )),
+        };
+        let audit = self.audit_log.as_ref().map(|log| {
+            let hash = transcript_hash(&self.service.group, &pending, &s);
+            (log, pending.user_name.clone(), hash)
+        });
+
+        let result = check(auth_id, pending, &s, keys);
+        if let Some((log, user, transcript_hash)) = audit {
+            let entry = AuditEntry {
+                user,
+                auth_id: auth_id.clone(),
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_secs()),
+                outcome: match &result {
+                    Ok(_) => AuditOutcome::Success,
+                    Err(e) => AuditOutcome::Failure(e.to_string()),
+                },
+                transcript_hash,
+            };
+            if let Err(e) = log.record(entry) {
+                warn!("Failed to write the audit log: {}", e);
+            }
+        }
+        result
+    }
+
+    /// Rejects the request unless its `x-admin-token` metadata matches `admin_token`. An
+    /// empty token, configured or sent, never matches.
+    fn check_admin_token<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let expected = self
+            .admin_token
+            .as_deref()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| Status::new(Code::Unauthenticated, "Admin RPCs are disabled"))?;
+        let given = request
+            .metadata()
+            .get(ADMIN_TOKEN_METADATA)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+
+        if given.is_empty() || !bool::from(given.as_bytes().ct_eq(expected.as_bytes())) {
+            return Err(Status::new(Code::Unauthenticated, "Invalid admin token"));
+        }
+        Ok(())
+    }
+
+    /// Rejects a request acting for `user`, such as one on its sessions or key slots, unless
+    /// it carries the admin token or `session_id` is a live session of `user`.
+    fn check_session_owner<T>(
+        &self,
+        request: &Request<T>,
+        user: &str,
+        session_id: &str,
+    ) -> Result<(), Status> {
+        if self.check_admin_token(request).is_ok() {
+            return Ok(());
+        }
+        let owner = self
+            .service
+            .validate_session(session_id)
+            .map_err(session_status)?;
+        if owner != user {
+            warn!("Request authorized for a different user");
+            return Err(Status::permission_denied(format!(
+                "Not authorized to act for user '{}'",
+                user
+            )));
+        }
+        Ok(())
+    }
+
+    /// The longest numeric field accepted: an element of the group plus
+    /// `FIELD_LEN_MARGIN`.
+    fn max_field_len(&self) -> usize {
+        let element = self.service.group.element_to_bytes(&G::Element::default());
+        element.len() + FIELD_LEN_MARGIN
+    }
+
+    /// Rejects a numeric field longer than `max_field_len` before anything decodes it.
+    fn check_field_len(&self, field: &str, bytes: &[u8]) -> Result<(), Status> {
+        let max = self.max_field_len();
+        if bytes.len() > max {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!("'{}' is longer than {} bytes", field, max),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the bytes of a numeric field, taken from its optional `*_hex` counterpart
+    /// when the client sent one.
+    fn wire_bytes(&self, field: &str, bytes: &[u8], hex: Option<&str>) -> Result<Vec<u8>, Status> {
+        match hex {
+            Some(hex) => {
+                // An empty string parses as 0, which would slip past the empty-field check
+                if hex.is_empty() {
+                    return Err(Status::new(
+                        Code::InvalidArgument,
+                        format!("'{}_hex' must not be empty", field),
+                    ));
+                }
+                let max_digits = 2 * self.max_field_len();
+                if hex.len() > max_digits {
+                    return Err(Status::new(
+                        Code::InvalidArgument,
+                        format!("'{}_hex' is longer than {} hex digits", field, max_digits),
+                    ));
+                }
+                biguint_from_hex(hex)
+                    .map(|value| value.to_bytes_be())
+                    .map_err(|e| {
+                        Status::new(
+                            Code::InvalidArgument,
+                            format!("'{}_hex' is not valid hex: {}", field, e),
+                        )
+                    })
+            }
+            None => {
+                self.check_field_len(field, bytes)?;
+                Ok(bytes.to_vec())
+            }
+        }
+    }
+}
+
+type OutboundSender = mpsc::Sender<Result<AuthenticateResponse, Status>>;
+
+impl<G: ZkpGroup + Send + Sync + 'static> AuthImpl<G> {
+    /// Drives one `Authenticate` stream through the unary handlers: commitments in,
+    /// challenge out, answer in, session out. A challenge the stream stops short of
+    /// answering is cancelled.
+    async fn run_authenticate(
+        &self,
+        inbound: &mut Streaming<AuthenticateRequest>,
+        outbound: &OutboundSender,
+    ) -> Result<(), Status> {
+        let commitments = match inbound.message().await? {
+            Some(AuthenticateRequest {
+                step: Some(RequestStep::Commitments(commitments)),
+            }) => commitments,
+            Some(_) => {
+                return Err(Status::invalid_argument(
+                    "the first message must carry the commitments",
+                ))
+            }
+            None => return Ok(()),
+        };
+        let challenge = self
+            .create_authentication_challenge(Request::new(commitments))
+            .await?
+            .into_inner();
+        let auth_id = challenge.auth_id.clone();
+        let sent = outbound
+            .send(Ok(AuthenticateResponse {
+                step: Some(ResponseStep::Challenge(challenge)),
+            }))
+            .await;
+
+        let answer = match (sent, inbound.message().await) {
+            (
+                Ok(()),
+                Ok(Some(AuthenticateRequest {
+                    step: Some(RequestStep::Answer(answer)),
+                })),
+            ) => answer,
+            (_, next) => {
+                self.challenges.remove(&auth_id).await;
+                info!(auth_id = %auth_id, "Stream ended before the challenge was answered");
+                return match next {
+                    Ok(Some(_)) => Err(Status::invalid_argument(
+                        "the second message must carry the answer",
+                    )),
+                    Ok(None) => Ok(()),
+                    Err(status) => Err(status),
+                };
+            }
+        };
+
+        let session = self
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id,
+                s: answer.s,
+                s_hex: answer.s_hex,
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+        // A client that hangs up now simply never reads its session
+        let _ = outbound
+            .send(Ok(AuthenticateResponse {
+                step: Some(ResponseStep::Session(session)),
+            }))
+            .await;
+        Ok(())
+    }
+}
+
+fn status_from(err: AuthError) -> Status {
+    let code = match err {
+        AuthError::EmptyField(_)
+        | AuthError::InvalidElement(_)
+        | AuthError::InvalidCommitment(_)
+        | AuthError::InvalidScalar(_)
+        | AuthError::TrivialPublicKey
+        | AuthError::NotInSubgroup(_) => Code::InvalidArgument,
+        AuthError::UserAlreadyExists(_) | AuthError::SlotAlreadyExists(_) => Code::AlreadyExists,
+        // Not AlreadyExists, which clients read as a taken username; a fresh nonce succeeds
+        AuthError::CommitmentReused(_) => Code::Aborted,
+        AuthError::UserNotFound(_)
+        | AuthError::SlotNotFound(_)
+        | AuthError::ChallengeNotFound(_)
+        | AuthError::SessionNotFound => Code::NotFound,
+        AuthError::RateLimited(_) => Code::ResourceExhausted,
+        AuthError::ChallengeExpired(_) => Code::DeadlineExceeded,
+        AuthError::ChallengeMismatch(_) => Code::FailedPrecondition,
+        AuthError::IncorrectSolution(_) | AuthError::SwappedPublicKeys(_) => {
+            Code::PermissionDenied
+        }
+        AuthError::SessionExpired
+        | AuthError::RefreshTokenNotFound
+        | AuthError::RefreshTokenExpired => Code::Unauthenticated,
+    };
+    Status::new(code, err.to_string())
+}
+
+/// `status_from`, except that an unknown or expired session means the caller is not
+/// logged in, as in `ValidateSession`.
+fn session_status(err: AuthError) -> Status {
+    match err {
+        AuthError::SessionNotFound | AuthError::SessionExpired => {
+            Status::new(Code::Unauthenticated, err.to_string())
+        }
+        err => status_from(err),
+    }
+}
+
+/// Serves the `Auth` service on `addr`, alongside the standard gRPC health service
+/// reporting it as `SERVING`. Plaintext HTTP/2 is used unless `tls` is given. Once
+/// `shutdown` resolves the server stops accepting connections, lets in-flight requests
+/// finish and returns.
+pub async fn serve(
+    auth_impl: AuthImpl,
+    addr: SocketAddr,
+    tls: Option<ServerTlsConfig>,
+    shutdown: impl Future<Output = ()> + Send,
+) -> Result<(), tonic::transport::Error> {
+    router(auth_impl, tls)
+        .await?
+        .serve_with_shutdown(addr, shutdown)
+        .await
+}
+
+/// Like `serve`, but accepting connections on the Unix domain socket `listener` instead
+/// of TCP. There is no TLS; the socket file's permissions decide who can connect.
+#[cfg(unix)]
+pub async fn serve_uds(
+    auth_impl: AuthImpl,
+    listener: tokio::net::UnixListener,
+    shutdown: impl Future<Output = ()> + Send,
+) -> Result<(), tonic::transport::Error> {
+    router(auth_impl, None)
+        .await?
+        .serve_with_incoming_shutdown(
+            tokio_stream::wrappers::UnixListenerStream::new(listener),
+            shutdown,
+        )
+        .await
+}
+
+/// The services `serve` runs, ready to be bound to an address or a listener.
+pub(crate) async fn router(
+    auth_impl: AuthImpl,
+    tls: Option<ServerTlsConfig>,
+) -> Result<Router, tonic::transport::Error> {
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    health_reporter.set_serving::<AuthServer<AuthImpl>>().await;
+
+    let mut builder = Server::builder();
+    if let Some(tls) = tls {
+        builder = builder.tls_config(tls)?;
+    }
+
+    Ok(builder
+        .add_service(health_service)
+        .add_service(AuthServer::new(auth_impl).max_decoding_message_size(MAX_MESSAGE_SIZE)))
+}
+
+#[tonic::async_trait]
+impl<G: ZkpGroup + Send + Sync + 'static> Auth for AuthImpl<G> {
+    #[tracing::instrument(skip_all, fields(user = %request.get_ref().user))]
+    async fn register(
+        &self,
+        request: Request<RegisterRequest>,
+    ) -> Result<Response<RegisterResponse>, Status> {
+        // Adding a slot to an existing user is not self-registration, but needs the user
+        if let Some(session_id) = &request.get_ref().session_id {
+            self.check_session_owner(&request, &request.get_ref().user, session_id)?;
+        } else if !self.allow_self_register && self.check_admin_token(&request).is_err() {
+            warn!("Self-registration rejected");
+            return Err(Status::permission_denied(
+                "Registration requires the admin token",
+            ));
+        }
+        let request = request.into_inner();
+        self.metrics.register_total.increment(1);
+
+        info!("Registration of user");
+
+        let y1 = self.wire_bytes("y1", &request.y1, request.y1_hex.as_deref())?;
+        let y2 = self.wire_bytes("y2", &request.y2, request.y2_hex.as_deref())?;
+        let slot = request.slot.as_deref().unwrap_or(DEFAULT_SLOT);
+        let registered = match request.session_id {
+            Some(_) => self.service.add_slot(&request.user, slot, &y1, &y2),
+            None => self.service.register_slot(&request.user, slot, &y1, &y2),
+        };
+        registered.map_err(|e| {
+            if let AuthError::UserAlreadyExists(_) = e {
+                warn!("User already registered");
+            }
+            status_from(e)
+        })?;
+
+        info!(slot, "Registration successful");
+
+        Ok(Response::new(RegisterResponse {}))
+    }
+
+    #[tracing::instrument(skip_all, fields(user = %request.get_ref().user))]
+    async fn create_authentication_challenge(
+        &self,
+        request: Request<CreateAuthenticationChallengeRequest>,
+    ) -> Result<Response<CreateAuthenticationChallengeResponse>, Status> {
+        let request = request.into_inner();
+        self.metrics.challenge_total.increment(1);
+        info!("Processing Challenge Request");
+
+        let r1 = self.wire_bytes("r1", &request.r1, request.r1_hex.as_deref())?;
+        let r2 = self.wire_bytes("r2", &request.r2, request.r2_hex.as_deref())?;
+        let slot = request.slot.as_deref().unwrap_or(DEFAULT_SLOT);
+        let (challenge, pending) = self
+            .service
+            .issue_slot_challenge(&request.user, slot, &r1, &r2)
+            .map_err(status_from)?;
+        self.challenges
+            .put(challenge.auth_id.clone(), pending, self.service.challenge_ttl)
+            .await;
+
+        info!(auth_id = %challenge.auth_id, "Challenge created");
+
+        Ok(Response::new(CreateAuthenticationChallengeResponse {
+            auth_id: challenge.auth_id,
+            c: self.service.group.scalar_to_bytes(&challenge.c),
+        }))
+    }
+
+    #[tracing::instrument(skip_all, fields(auth_id = %request.get_ref().auth_id))]
+    async fn verify_authentication(
+        &self,
+        request: Request<VerifyAuthenticationRequest>,
+    ) -> Result<Response<VerifyAuthenticationResponse>, Status> {
+        let request = request.into_inner();
+        info!("Processing Challenge Solution");
+
+        let s = self.wire_bytes("s", &request.s, request.s_hex.as_deref())?;
+        for (field, bytes) in [("c", &request.c), ("y1", &request.y1), ("y2", &request.y2)] {
+            if let Some(bytes) = bytes {
+                self.check_field_len(field, bytes)?;
+            }
+        }
+        match self.answer(&request, &s).await {
+            Ok(verification) => {
+                self.metrics.verify_success_total.increment(1);
+                info!(user = %verification.user_name(), "Solution correct");
+                Ok(Response::new(match verification {
+                    Verification::Login(login) => VerifyAuthenticationResponse {
+                        session_id: login.session_id,
+                        refresh_token: login.refresh_token,
+                    },
+                    Verification::ProofOnly { .. } => VerifyAuthenticationResponse::default(),
+                }))
+            }
+            Err(e) => {
+                self.metrics.verify_failure_total.increment(1);
+                match e {
+                    AuthError::ChallengeExpired(_) => warn!("Challenge expired"),
+                    AuthError::ChallengeMismatch(_) => {
+                        warn!("Client answered a different challenge than it was issued")
+                    }
+                    AuthError::IncorrectSolution(_) => warn!("Solution incorrect"),
+                    AuthError::SwappedPublicKeys(_) => warn!(
+                        "Solution incorrect, but it verifies with y1 and y2 swapped; \
+                         the client likely registered its keys in the wrong order"
+                    ),
+                    _ => {}
+                }
+                Err(status_from(e))
+            }
+        }
+    }
+
+    type AuthenticateStream =
+        Pin<Box<dyn Stream<Item = Result<AuthenticateResponse, Status>> + Send>>;
+
+    #[tracing::instrument(skip_all)]
+    async fn authenticate(
+        &self,
+        request: Request<Streaming<AuthenticateRequest>>,
+    ) -> Result<Response<Self::AuthenticateStream>, Status> {
+        let mut inbound = request.into_inner();
+        let auth_impl = self.clone();
+        let (outbound, responses) = mpsc::channel(2);
+        info!("Opening authentication stream");
+
+        tokio::spawn(
+            async move {
+                if let Err(status) = auth_impl.run_authenticate(&mut inbound, &outbound).await {
+                    let _ = outbound.send(Err(status)).await;
+                }
+            }
+            .in_current_span(),
+        );
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(responses))))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn validate_session(
+        &self,
+        request: Request<ValidateSessionRequest>,
+    ) -> Result<Response<ValidateSessionResponse>, Status> {
+        let request = request.into_inner();
+        info!("Validating session");
+
+        match self.service.validate_session(&request.session_id) {
+            Ok(user) => Ok(Response::new(ValidateSessionResponse {
+                user,
+                elevated: self.service.is_elevated(&request.session_id),
+            })),
+            Err(e) => {
+                if let AuthError::SessionExpired = e {
+                    warn!("Session expired");
+                }
+                // Unknown and expired sessions alike mean the caller is not logged in
+                Err(Status::new(Code::Unauthenticated, e.to_string()))
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn refresh_session(
+        &self,
+        request: Request<RefreshSessionRequest>,
+    ) -> Result<Response<RefreshSessionResponse>, Status> {
+        let request = request.into_inner();
+        info!("Refreshing session");
+
+        let login = self
+            .service
+            .refresh_session(&request.refresh_token)
+            .map_err(|e| {
+                if let AuthError::RefreshTokenExpired = e {
+                    warn!("Refresh token expired");
+                }
+                status_from(e)
+            })?;
+        info!(user = %login.user_name, "Session refreshed");
+        Ok(Response::new(RefreshSessionResponse {
+            session_id: login.session_id,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn logout(
+        &self,
+        request: Request<LogoutRequest>,
+    ) -> Result<Response<LogoutResponse>, Status> {
+        let request = request.into_inner();
+        info!("Logging out session");
+
+        self.service
+            .logout(&request.session_id)
+            .map_err(status_from)?;
+        Ok(Response::new(LogoutResponse {}))
+    }
+
+    #[tracing::instrument(skip_all, fields(user = %request.get_ref().user))]
+    async fn list_sessions(
+        &self,
+        request: Request<ListSessionsRequest>,
+    ) -> Result<Response<ListSessionsResponse>, Status> {
+        let user = &request.get_ref().user;
+        info!("Listing sessions");
+        self.check_session_owner(&request, user, &request.get_ref().session_id)?;
+
+        let sessions = self
+            .service
+            .list_sessions(user)
+            .into_iter()
+            .map(|session| SessionInfo {
+                session_ref: session.session_ref,
+                age_secs: session.age.as_secs(),
+                elevated: session.elevated,
+            })
+            .collect();
+        Ok(Response::new(ListSessionsResponse { sessions }))
+    }
+
+    #[tracing::instrument(skip_all, fields(user = %request.get_ref().user))]
+    async fn revoke_session(
+        &self,
+        request: Request<RevokeSessionRequest>,
+    ) -> Result<Response<RevokeSessionResponse>, Status> {
+        info!("Revoking session");
+        let RevokeSessionRequest {
+            user,
+            session_id,
+            session_ref,
+        } = request.get_ref();
+        self.check_session_owner(&request, user, session_id)?;
+
+        self.service
+            .revoke_session(user, session_ref)
+            .map_err(status_from)?;
+        info!("Session revoked");
+        Ok(Response::new(RevokeSessionResponse {}))
+    }
+
+    #[tracing::instrument(skip_all, fields(user = %request.get_ref().user))]
+    async fn revoke_all_sessions(
+        &self,
+        request: Request<RevokeAllSessionsRequest>,
+    ) -> Result<Response<RevokeAllSessionsResponse>, Status> {
+        info!("Revoking all sessions");
+        let user = &request.get_ref().user;
+        self.check_session_owner(&request, user, &request.get_ref().session_id)?;
+
+        let revoked = self.service.revoke_all_sessions(user);
+        info!(revoked, "Sessions revoked");
+        Ok(Response::new(RevokeAllSessionsResponse {
+            revoked: revoked as u64,
+        }))
+    }
+
+    #[tracing::instrument(skip_all, fields(user = %request.get_ref().user))]
+    async fn rotate_keys(
+        &self,
+        request: Request<RotateKeysRequest>,
+    ) -> Result<Response<RotateKeysResponse>, Status> {
+        let request = request.into_inner();
+        info!("Rotating public keys");
+
+        let y1 = self.wire_bytes("y1", &request.y1, request.y1_hex.as_deref())?;
+        let y2 = self.wire_bytes("y2", &request.y2, request.y2_hex.as_deref())?;
+        let owner = match &request.authorization {
+            Some(Authorization::SessionId(session_id)) => self
+                .service
+                .validate_session(session_id)
+                .map_err(|e| Status::new(Code::Unauthenticated, e.to_string()))?,
+            Some(Authorization::Proof(proof)) => {
+                let s = self.wire_bytes("s", &proof.s, proof.s_hex.as_deref())?;
+                if let Some(c) = &proof.c {
+                    self.check_field_len("c", c)?;
+                }
+                let pending = self.check_answer(proof, &s).await.map_err(status_from)?;
+                if pending.unregistered {
+                    return Err(Status::permission_denied(
+                        "A proof for an unregistered name cannot rotate keys",
+                    ));
+                }
+                pending.user_name
+            }
+            None => {
+                return Err(Status::unauthenticated(
+                    "A session_id or proof is required to rotate keys",
+                ))
+            }
+        };
+        if owner != request.user {
+            warn!("Key rotation authorized for a different user");
+            return Err(Status::permission_denied(format!(
+                "Not authorized to rotate the keys of user '{}'",
+                request.user
+            )));
+        }
+
+        let slot = request.slot.as_deref().unwrap_or(DEFAULT_SLOT);
+        self.service
+            .rotate_slot_keys(&request.user, slot, &y1, &y2)
+            .map_err(status_from)?;
+        info!("Public keys rotated");
+        Ok(Response::new(RotateKeysResponse {}))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn step_up_challenge(
+        &self,
+        request: Request<StepUpChallengeRequest>,
+    ) -> Result<Response<CreateAuthenticationChallengeResponse>, Status> {
+        let request = request.into_inner();
+        self.metrics.challenge_total.increment(1);
+        info!("Processing step-up challenge request");
+
+        let r1 = self.wire_bytes("r1", &request.r1, request.r1_hex.as_deref())?;
+        let r2 = self.wire_bytes("r2", &request.r2, request.r2_hex.as_deref())?;
+        let slot = request.slot.as_deref().unwrap_or(DEFAULT_SLOT);
+        let (challenge, pending) = self
+            .service
+            .issue_step_up_challenge(&request.session_id, slot, &r1, &r2)
+            .map_err(session_status)?;
+        self.challenges
+            .put(challenge.auth_id.clone(), pending, self.service.challenge_ttl)
+            .await;
+
+        info!(auth_id = %challenge.auth_id, "Step-up challenge created");
+        Ok(Response::new(CreateAuthenticationChallengeResponse {
+            auth_id: challenge.auth_id,
+            c: self.service.group.scalar_to_bytes(&challenge.c),
+        }))
+    }
+
+    #[tracing::instrument(skip_all, fields(auth_id = %request.get_ref().auth_id))]
+    async fn step_up_verify(
+        &self,
+        request: Request<StepUpVerifyRequest>,
+    ) -> Result<Response<StepUpVerifyResponse>, Status> {
+        let request = request.into_inner();
+        info!("Processing step-up solution");
+
+        let s = self.wire_bytes("s", &request.s, request.s_hex.as_deref())?;
+        let s = self.service.decode_scalar("s", &s).map_err(status_from)?;
+        let not_found = || status_from(AuthError::ChallengeNotFound(request.auth_id.clone()));
+        // Another session's attempt must not use up the challenge, so look before taking it
+        let pending = self.challenges.get(&request.auth_id).await;
+        if pending.and_then(|pending| pending.step_up).as_ref() != Some(&request.session_id) {
+            return Err(not_found());
+        }
+        let pending = self.challenges.remove(&request.auth_id).await.ok_or_else(not_found)?;
+        self.service
+            .verify_step_up(&request.session_id, &request.auth_id, pending, &s)
+            .map_err(|e| {
+                if let AuthError::IncorrectSolution(_) = e {
+                    warn!("Step-up solution incorrect");
+                }
+                session_status(e)
+            })?;
+
+        info!("Session elevated");
+        Ok(Response::new(StepUpVerifyResponse {
+            elevated_for_secs: self.service.step_up_ttl.as_secs(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_stats(
+        &self,
+        request: Request<GetStatsRequest>,
+    ) -> Result<Response<GetStatsResponse>, Status> {
+        if let Err(status) = self.check_admin_token(&request) {
+            warn!("Stats request rejected");
+            return Err(status);
+        }
+
+        let stats = self.service.stats();
+        Ok(Response::new(GetStatsResponse {
+            user_count: stats.user_count as u64,
+            pending_challenges: stats.pending_challenges as u64,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{
+        auth_client::AuthClient, AuthenticateAnswer,
+        CreateAuthenticationChallengeRequest, RegisterRequest, VerifyAuthenticationRequest,
+    };
+    use chaum_pedersen::{
+        encoding::biguint_to_hex,
+        service::{Clock, InMemoryChallengeStore, DEFAULT_CHALLENGE_TTL},
+        Scalar, ZKP,
+    };
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use num_bigint::BigUint;
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+    use tokio::time::{sleep, Duration};
+    use tonic::{transport::Identity, Request};
+    use tonic_health::pb::{
+        health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+    };
+    use tracing_test::traced_test;
+
+    /// Spawn the gRPC server on a random free port and return (full_uri, JoinHandle).
+    async fn spawn_server() -> (String, tokio::task::JoinHandle<()>) {
+        spawn_server_with(AuthImpl::default()).await
+    }
+
+    /// Same as `spawn_server`, but serving a caller-configured `AuthImpl`.
+    async fn spawn_server_with(auth_impl: AuthImpl) -> (String, tokio::task::JoinHandle<()>) {
+        // 1) Bind a standard TcpListener to an ephemeral port
+        let std_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let local_addr = std_listener.local_addr().unwrap(); // e.g. 127.0.0.1:54321
+        let full_uri = format!("http://{}", local_addr);
+        // Release the port before `serve` binds it; on a multi-threaded runtime the task
+        // below can start before this function returns
+        drop(std_listener);
+
+        // 2) Spawn the server in the background
+        let handle = tokio::spawn(async move {
+            // We don’t need serve_with_incoming — just serve the `local_addr`
+            // The server will keep running until the test finishes and drops the JoinHandle
+            serve(auth_impl, local_addr, None, std::future::pending())
+                .await
+                .expect("server failed");
+        });
+
+        // 3) Return the address + the join handle
+        (full_uri, handle)
+    }
+
+    /// A clock that only moves when the test advances it.
+    #[derive(Debug)]
+    struct MockClock {
+        now: Mutex<Instant>,
+    }
+
+    impl MockClock {
+        fn new() -> Self {
+            MockClock {
+                now: Mutex::new(Instant::now()),
+            }
+        }
+
+        fn advance(&self, by: Duration) {
+            *self.now.lock().unwrap() += by;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            *self.now.lock().unwrap()
+        }
+    }
+
+    /// Register `user_name` with a fresh secret and log in, returning the session_id.
+    async fn register_and_login(
+        client: &mut AuthClient<tonic::transport::Channel>,
+        user_name: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(register_and_login_with_tokens(client, user_name).await?.session_id)
+    }
+
+    /// Like `register_and_login`, but returning the whole response with the refresh token.
+    async fn register_and_login_with_tokens(
+        client: &mut AuthClient<tonic::transport::Channel>,
+        user_name: &str,
+    ) -> Result<VerifyAuthenticationResponse, Box<dyn std::error::Error>> {
+        let zkp = ZKP::new();
+
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: user_name.to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+
+        Ok(login_with(client, &zkp, user_name, &x).await?)
+    }
+
+    /// Requests a challenge for `user_name` and returns the answer to it for secret `x`,
+    /// without sending it.
+    async fn answer_challenge(
+        client: &mut AuthClient<tonic::transport::Channel>,
+        zkp: &ZKP,
+        user_name: &str,
+        x: &Scalar,
+    ) -> Result<VerifyAuthenticationRequest, Status> {
+        answer_slot_challenge(client, zkp, user_name, None, x).await
+    }
+
+    /// Like `answer_challenge`, for the keys in `slot`.
+    async fn answer_slot_challenge(
+        client: &mut AuthClient<tonic::transport::Channel>,
+        zkp: &ZKP,
+        user_name: &str,
+        slot: Option<&str>,
+        x: &Scalar,
+    ) -> Result<VerifyAuthenticationRequest, Status> {
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = client
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: user_name.to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                slot: slot.map(str::to_string),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+
+        let c = Scalar::new(zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let s = zkp.solve(&k, &c, x);
+        Ok(VerifyAuthenticationRequest {
+            auth_id: challenge_resp.auth_id,
+            s: s.to_bytes_be(),
+            ..Default::default()
+        })
+    }
+
+    /// Logs in as the already registered `user_name` with secret `x`.
+    async fn login_with(
+        client: &mut AuthClient<tonic::transport::Channel>,
+        zkp: &ZKP,
+        user_name: &str,
+        x: &Scalar,
+    ) -> Result<VerifyAuthenticationResponse, Status> {
+        let request = answer_challenge(client, zkp, user_name, x).await?;
+        Ok(client
+            .verify_authentication(Request::new(request))
+            .await?
+            .into_inner())
+    }
+
+    /// Requests a step-up challenge on `session_id` and answers it with secret `x`.
+    async fn step_up(
+        client: &mut AuthClient<tonic::transport::Channel>,
+        zkp: &ZKP,
+        session_id: &str,
+        x: &Scalar,
+    ) -> Result<StepUpVerifyResponse, Status> {
+        step_up_slot(client, zkp, session_id, None, x).await
+    }
+
+    /// Like `step_up`, for the keys in `slot`.
+    async fn step_up_slot(
+        client: &mut AuthClient<tonic::transport::Channel>,
+        zkp: &ZKP,
+        session_id: &str,
+        slot: Option<&str>,
+        x: &Scalar,
+    ) -> Result<StepUpVerifyResponse, Status> {
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = client
+            .step_up_challenge(Request::new(StepUpChallengeRequest {
+                session_id: session_id.to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                slot: slot.map(str::to_string),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+
+        let c = Scalar::new(zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let s = zkp.solve(&k, &c, x);
+        Ok(client
+            .step_up_verify(Request::new(StepUpVerifyRequest {
+                session_id: session_id.to_string(),
+                auth_id: challenge_resp.auth_id,
+                s: s.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner())
+    }
+
+    /// Whether `ValidateSession` reports `session_id` as elevated.
+    async fn is_elevated(
+        client: &mut AuthClient<tonic::transport::Channel>,
+        session_id: &str,
+    ) -> Result<bool, Status> {
+        Ok(client
+            .validate_session(Request::new(ValidateSessionRequest {
+                session_id: session_id.to_string(),
+            }))
+            .await?
+            .into_inner()
+            .elevated)
+    }
+
+    /// A `RotateKeysRequest` of `user_name` to the keys of `x`.
+    fn rotate_to(
+        zkp: &ZKP,
+        user_name: &str,
+        x: &Scalar,
+        authorization: Option<Authorization>,
+    ) -> RotateKeysRequest {
+        let (y1, y2) = zkp.compute_pair(x);
+        RotateKeysRequest {
+            user: user_name.to_string(),
+            y1: y1.to_bytes_be(),
+            y2: y2.to_bytes_be(),
+            authorization,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_end_to_end_auth_flow() -> Result<(), Box<dyn std::error::Error>> {
+        // --------------------------------------------------
+        // 1) Spawn our gRPC server on a random port
+        // --------------------------------------------------
+        let (server_addr, _join_handle) = spawn_server().await;
+
+        // Give the server a moment to actually start listening
+        sleep(Duration::from_millis(100)).await;
+
+        // --------------------------------------------------
+        // 2) Create a client for our Auth service
+        // --------------------------------------------------
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        // For convenience, create a ZKP struct
+        let zkp = ZKP::new();
+
+        // --------------------------------------------------
+        // 3) REGISTER the user
+        // --------------------------------------------------
+        let x = zkp.generate_random_scalar(); // user’s secret
+        let y1 = zkp.alpha.modpow(&x, &zkp.p);
+        let y2 = zkp.beta.modpow(&x, &zkp.p);
+
+        let user_name = "alice".to_string();
+        let register_request = RegisterRequest {
+            user: user_name.clone(),
+            y1: y1.to_bytes_be(),
+            y2: y2.to_bytes_be(),
+            ..Default::default()
+        };
+        client.register(Request::new(register_request)).await?;
+        println!("--- Registered user: {} ---", user_name);
+
+        // --------------------------------------------------
+        // 4) CREATE AUTHENTICATION CHALLENGE
+        // --------------------------------------------------
+        let k = zkp.generate_random_scalar(); // ephemeral
+        let (r1, r2) = zkp.compute_pair(&k);
+
+        let challenge_req = CreateAuthenticationChallengeRequest {
+            user: user_name.clone(),
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+            ..Default::default()
+        };
+        let challenge_resp = client
+            .create_authentication_challenge(Request::new(challenge_req))
+            .await?
+            .into_inner();
+
+        let auth_id = challenge_resp.auth_id;
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        println!("--- Created challenge for user: {} / auth_id: {} ---", user_name, auth_id);
+
+        // --------------------------------------------------
+        // 5) SOLVE AND VERIFY AUTHENTICATION
+        // --------------------------------------------------
+        // Solve: s = k - c*x (mod q)
+        let s = zkp.solve(&k, &c, &x);
+
+        let verify_req = VerifyAuthenticationRequest {
+            auth_id: auth_id.clone(),
+            s: s.to_bytes_be(),
+            ..Default::default()
+        };
+        let verify_resp = client.verify_authentication(Request::new(verify_req)).await;
+
+        match verify_resp {
+            Ok(resp) => {
+                let session_id = resp.into_inner().session_id;
+                assert!(!session_id.is_empty(), "Session ID should not be empty");
+                println!("--- Auth succeeded! Session ID: {} ---", session_id);
+            }
+            Err(e) => panic!("Auth verification failed with error: {:?}", e),
+        }
+
+        // Test completes and drops the JoinHandle — the server stops
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_end_to_end_auth_flow_rejects_wrong_password(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        // Derive the secrets the way the prover does: the password's bytes as a number mod q
+        let x_a = Scalar::reduce(&zkp, &BigUint::from_bytes_be(b"password A"));
+        let x_b = Scalar::reduce(&zkp, &BigUint::from_bytes_be(b"password B"));
+        assert_ne!(x_a, x_b);
+
+        let (y1, y2) = zkp.compute_pair(&x_a);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = client
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let s = zkp.solve(&k, &c, &x_b);
+        let err = client
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id,
+                s: s.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_end_to_end_auth_flow_with_explicit_modp_group() -> Result<(), Box<dyn std::error::Error>> {
+        let auth_impl: AuthImpl<ZKP> = AuthImpl::with_group(ZKP::new());
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let session_id = register_and_login(&mut client, "alice").await?;
+        assert!(!session_id.is_empty(), "Session ID should not be empty");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verification_uses_the_configured_group() {
+        // alpha = 4 and beta = 4^8 = 9 both have order 11 mod 23
+        let toy = ZKP::from_hex("17", "0b", "04", "08").unwrap();
+        let auth_impl = AuthImpl::with_group(toy.clone());
+        let (x, k) = (BigUint::from(6u32), BigUint::from(7u32));
+
+        let (y1, y2) = ZkpGroup::compute_pair(&toy, &x);
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+        // Keys of the built-in 1024-bit group are not elements of the configured one
+        let (default_y1, default_y2) = ZKP::new().register_values(&x);
+        let err = auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "bob".to_string(),
+                y1: default_y1,
+                y2: default_y2,
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+
+        let (r1, r2) = ZkpGroup::compute_pair(&toy, &k);
+        let challenge_resp = auth_impl
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let c = BigUint::from_bytes_be(&challenge_resp.c);
+        assert!(c < toy.q);
+        let s = ZkpGroup::solve(&toy, &k, &c, &x);
+        // The transcript only holds in the toy group
+        let default = ZKP::new();
+        assert!(ZkpGroup::verify(&toy, &r1, &r2, &y1, &y2, &c, &s));
+        assert!(!ZkpGroup::verify(&default, &r1, &r2, &y1, &y2, &c, &s));
+
+        let login = auth_impl
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id,
+                s: s.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!login.session_id.is_empty());
+    }
+
+    /// Registers `user_name` over unary RPCs and opens an `Authenticate` stream, returning
+    /// its secret, the sender feeding the stream and the server's replies.
+    async fn open_authenticate_stream(
+        client: &mut AuthClient<tonic::transport::Channel>,
+        user_name: &str,
+    ) -> Result<
+        (
+            Scalar,
+            mpsc::Sender<AuthenticateRequest>,
+            Streaming<AuthenticateResponse>,
+        ),
+        Box<dyn std::error::Error>,
+    > {
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: user_name.to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+
+        let (requests, outbound) = mpsc::channel(2);
+        let responses = client
+            .authenticate(ReceiverStream::new(outbound))
+            .await?
+            .into_inner();
+        Ok((x, requests, responses))
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_stream_yields_a_session() -> Result<(), Box<dyn std::error::Error>> {
+        let auth_impl = AuthImpl::default();
+        let service = auth_impl.service.clone();
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        let (x, requests, mut responses) = open_authenticate_stream(&mut client, "alice").await?;
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        requests
+            .send(AuthenticateRequest {
+                step: Some(RequestStep::Commitments(CreateAuthenticationChallengeRequest {
+                    user: "alice".to_string(),
+                    r1: r1.to_bytes_be(),
+                    r2: r2.to_bytes_be(),
+                    ..Default::default()
+                })),
+            })
+            .await?;
+        let Some(ResponseStep::Challenge(challenge)) = responses.message().await?.unwrap().step
+        else {
+            panic!("expected the challenge first");
+        };
+
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge.c)).unwrap();
+        requests
+            .send(AuthenticateRequest {
+                step: Some(RequestStep::Answer(AuthenticateAnswer {
+                    s: zkp.solve(&k, &c, &x).to_bytes_be(),
+                    ..Default::default()
+                })),
+            })
+            .await?;
+        let Some(ResponseStep::Session(session)) = responses.message().await?.unwrap().step
+        else {
+            panic!("expected the session second");
+        };
+        assert_eq!(service.validate_session(&session.session_id)?, "alice");
+        assert!(responses.message().await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_stream_cancels_challenge_on_disconnect(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let auth_impl = AuthImpl::default();
+        let service = auth_impl.service.clone();
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        let (_, requests, mut responses) = open_authenticate_stream(&mut client, "alice").await?;
+        let (r1, r2) = zkp.compute_pair(&zkp.generate_random_scalar());
+        requests
+            .send(AuthenticateRequest {
+                step: Some(RequestStep::Commitments(CreateAuthenticationChallengeRequest {
+                    user: "alice".to_string(),
+                    r1: r1.to_bytes_be(),
+                    r2: r2.to_bytes_be(),
+                    ..Default::default()
+                })),
+            })
+            .await?;
+        responses.message().await?.unwrap();
+        assert_eq!(service.stats().pending_challenges, 1);
+
+        drop(requests);
+        drop(responses);
+        for _ in 0..50 {
+            if service.stats().pending_challenges == 0 {
+                return Ok(());
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        panic!("the unanswered challenge outlived its stream");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_flows_for_distinct_users_all_succeed(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let client = AuthClient::connect(server_addr).await?;
+
+        let flows: Vec<_> = (0..32)
+            .map(|i| {
+                let mut client = client.clone();
+                tokio::spawn(async move {
+                    register_and_login(&mut client, &format!("user-{}", i))
+                        .await
+                        .map_err(|e| e.to_string())
+                })
+            })
+            .collect();
+
+        let results = tokio::time::timeout(Duration::from_secs(60), join_all(flows))
+            .await
+            .expect("concurrent flows deadlocked");
+        for result in results {
+            assert!(!result??.is_empty());
+        }
+
+        Ok(())
+    }
+
+    /// Awaits every handle in order; the flows still run concurrently on the runtime.
+    async fn join_all<T>(
+        handles: Vec<tokio::task::JoinHandle<T>>,
+    ) -> Vec<Result<T, tokio::task::JoinError>> {
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            results.push(handle.await);
+        }
+        results
+    }
+
+    #[tokio::test]
+    async fn test_validate_session_accepts_live_session() -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let session_id = register_and_login(&mut client, "alice").await?;
+
+        let resp = client
+            .validate_session(Request::new(ValidateSessionRequest { session_id }))
+            .await?
+            .into_inner();
+        assert_eq!(resp.user, "alice");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate_session_rejects_expired_session() -> Result<(), Box<dyn std::error::Error>> {
+        let clock = Arc::new(MockClock::new());
+        let auth_impl = AuthImpl::new(AuthConfig {
+            session_ttl: Duration::from_secs(60),
+            clock: clock.clone(),
+            ..Default::default()
+        });
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let session_id = register_and_login(&mut client, "alice").await?;
+        clock.advance(Duration::from_secs(61));
+
+        let err = client
+            .validate_session(Request::new(ValidateSessionRequest { session_id }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_respects_configured_session_ttl() -> Result<(), Box<dyn std::error::Error>> {
+        let clock = Arc::new(MockClock::new());
+        let auth_impl = AuthImpl::new(AuthConfig {
+            session_ttl: Duration::from_secs(5),
+            clock: clock.clone(),
+            ..Default::default()
+        });
+        assert_eq!(auth_impl.service.challenge_ttl, DEFAULT_CHALLENGE_TTL);
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let session_id = register_and_login(&mut client, "alice").await?;
+        clock.advance(Duration::from_secs(4));
+        client
+            .validate_session(Request::new(ValidateSessionRequest {
+                session_id: session_id.clone(),
+            }))
+            .await?;
+
+        clock.advance(Duration::from_secs(2));
+        let err = client
+            .validate_session(Request::new(ValidateSessionRequest { session_id }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate_session_rejects_logged_out_session() -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let session_id = register_and_login(&mut client, "alice").await?;
+        client
+            .logout(Request::new(LogoutRequest {
+                session_id: session_id.clone(),
+            }))
+            .await?;
+
+        let err = client
+            .validate_session(Request::new(ValidateSessionRequest { session_id }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rotate_keys_with_a_session_switches_logins_to_the_new_keys(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+        let session_id = login_with(&mut client, &zkp, "alice", &x).await?.session_id;
+
+        let new_x = zkp.generate_random_scalar();
+        client
+            .rotate_keys(Request::new(rotate_to(
+                &zkp,
+                "alice",
+                &new_x,
+                Some(Authorization::SessionId(session_id.clone())),
+            )))
+            .await?;
+
+        // The session opened under the old keys has ended
+        let err = client
+            .validate_session(Request::new(ValidateSessionRequest { session_id }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        let err = login_with(&mut client, &zkp, "alice", &x).await.unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+        let session_id = login_with(&mut client, &zkp, "alice", &new_x).await?.session_id;
+        let user = client
+            .validate_session(Request::new(ValidateSessionRequest { session_id }))
+            .await?
+            .into_inner()
+            .user;
+        assert_eq!(user, "alice");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_step_up_elevates_the_session_for_a_short_window(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let clock = Arc::new(MockClock::new());
+        let auth_impl = AuthImpl::new(AuthConfig {
+            step_up_ttl: Duration::from_secs(60),
+            clock: clock.clone(),
+            ..Default::default()
+        });
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+        let session_id = login_with(&mut client, &zkp, "alice", &x).await?.session_id;
+        assert!(!is_elevated(&mut client, &session_id).await?);
+
+        let resp = step_up(&mut client, &zkp, &session_id, &x).await?;
+        assert_eq!(resp.elevated_for_secs, 60);
+        assert!(is_elevated(&mut client, &session_id).await?);
+
+        clock.advance(Duration::from_secs(61));
+        assert!(!is_elevated(&mut client, &session_id).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_step_up_checks_the_requested_slot() -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        // Alice never registered keys in the default slot
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                slot: Some("phone".to_string()),
+                ..Default::default()
+            }))
+            .await?;
+        let answer = answer_slot_challenge(&mut client, &zkp, "alice", Some("phone"), &x).await?;
+        let session_id = client.verify_authentication(answer).await?.into_inner().session_id;
+
+        let err = step_up(&mut client, &zkp, &session_id, &x).await.unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+        step_up_slot(&mut client, &zkp, &session_id, Some("phone"), &x).await?;
+        assert!(is_elevated(&mut client, &session_id).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_step_up_challenge_survives_another_sessions_answer(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+        let session_id = login_with(&mut client, &zkp, "alice", &x).await?.session_id;
+        let bob_session = register_and_login(&mut client, "bob").await?;
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = client
+            .step_up_challenge(Request::new(StepUpChallengeRequest {
+                session_id: session_id.clone(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let answer = |session_id: &str| StepUpVerifyRequest {
+            session_id: session_id.to_string(),
+            auth_id: challenge_resp.auth_id.clone(),
+            s: zkp.solve(&k, &c, &x).to_bytes_be(),
+            ..Default::default()
+        };
+
+        let err = client.step_up_verify(answer(&bob_session)).await.unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+        client.step_up_verify(answer(&session_id)).await?;
+        assert!(is_elevated(&mut client, &session_id).await?);
+        assert!(!is_elevated(&mut client, &bob_session).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_step_up_challenge_survives_a_login_attempt_with_its_auth_id(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+        let session_id = login_with(&mut client, &zkp, "alice", &x).await?.session_id;
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = client
+            .step_up_challenge(Request::new(StepUpChallengeRequest {
+                session_id: session_id.clone(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let s = zkp.solve(&k, &c, &x).to_bytes_be();
+
+        let proof = VerifyAuthenticationRequest {
+            auth_id: challenge_resp.auth_id.clone(),
+            s: s.clone(),
+            ..Default::default()
+        };
+        let err = client.verify_authentication(proof.clone()).await.unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+        let err = client
+            .rotate_keys(Request::new(rotate_to(
+                &zkp,
+                "alice",
+                &x,
+                Some(Authorization::Proof(proof)),
+            )))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+
+        client
+            .step_up_verify(Request::new(StepUpVerifyRequest {
+                session_id: session_id.clone(),
+                auth_id: challenge_resp.auth_id,
+                s,
+                ..Default::default()
+            }))
+            .await?;
+        assert!(is_elevated(&mut client, &session_id).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_step_up_with_a_wrong_proof_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+        let session_id = login_with(&mut client, &zkp, "alice", &x).await?.session_id;
+
+        let wrong_x = zkp.generate_random_scalar();
+        let err = step_up(&mut client, &zkp, &session_id, &wrong_x)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+        assert!(!is_elevated(&mut client, &session_id).await?);
+
+        let err = step_up(&mut client, &zkp, "forged", &x).await.unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        // A login challenge does not step up a session, nor a step-up challenge log in
+        let login_answer = answer_challenge(&mut client, &zkp, "alice", &x).await?;
+        let err = client
+            .step_up_verify(Request::new(StepUpVerifyRequest {
+                session_id: session_id.clone(),
+                auth_id: login_answer.auth_id,
+                s: login_answer.s,
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = client
+            .step_up_challenge(Request::new(StepUpChallengeRequest {
+                session_id: session_id.clone(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let err = client
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id,
+                s: zkp.solve(&k, &c, &x).to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+        assert!(!is_elevated(&mut client, &session_id).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rotate_keys_with_an_inline_proof() -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+
+        let new_x = zkp.generate_random_scalar();
+        // An answer computed with the new secret does not prove knowledge of the old one
+        let wrong = answer_challenge(&mut client, &zkp, "alice", &new_x).await?;
+        let err = client
+            .rotate_keys(Request::new(rotate_to(
+                &zkp,
+                "alice",
+                &new_x,
+                Some(Authorization::Proof(wrong)),
+            )))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+
+        let proof = answer_challenge(&mut client, &zkp, "alice", &x).await?;
+        client
+            .rotate_keys(Request::new(rotate_to(
+                &zkp,
+                "alice",
+                &new_x,
+                Some(Authorization::Proof(proof)),
+            )))
+            .await?;
+        assert!(login_with(&mut client, &zkp, "alice", &new_x).await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rotate_keys_with_a_proof_opens_no_session(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let auth_impl = AuthImpl::default();
+        let service = Arc::clone(&auth_impl.service);
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+
+        // A refused rotation leaves nothing behind either
+        let new_x = zkp.generate_random_scalar();
+        let proof = answer_challenge(&mut client, &zkp, "alice", &x).await?;
+        let err = client
+            .rotate_keys(Request::new(rotate_to(
+                &zkp,
+                "bob",
+                &new_x,
+                Some(Authorization::Proof(proof)),
+            )))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+        assert!(service.sessions.read().unwrap().is_empty());
+        assert!(service.refresh_tokens.read().unwrap().is_empty());
+
+        let proof = answer_challenge(&mut client, &zkp, "alice", &x).await?;
+        client
+            .rotate_keys(Request::new(rotate_to(
+                &zkp,
+                "alice",
+                &new_x,
+                Some(Authorization::Proof(proof)),
+            )))
+            .await?;
+        assert!(service.sessions.read().unwrap().is_empty());
+        assert!(service.refresh_tokens.read().unwrap().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rotate_keys_without_authentication_is_rejected(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+        let bob_session = register_and_login(&mut client, "bob").await?;
+
+        let attacker_x = zkp.generate_random_scalar();
+        for (authorization, code) in [
+            (None, Code::Unauthenticated),
+            (
+                Some(Authorization::SessionId("forged".to_string())),
+                Code::Unauthenticated,
+            ),
+            (
+                Some(Authorization::SessionId(bob_session)),
+                Code::PermissionDenied,
+            ),
+        ] {
+            let err = client
+                .rotate_keys(Request::new(rotate_to(
+                    &zkp,
+                    "alice",
+                    &attacker_x,
+                    authorization,
+                )))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), code);
+        }
+
+        // Alice's keys are untouched
+        assert!(login_with(&mut client, &zkp, "alice", &x).await.is_ok());
+        let err = login_with(&mut client, &zkp, "alice", &attacker_x)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sessions_can_be_listed_and_revoked() -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+        let first = login_with(&mut client, &zkp, "alice", &x).await?.session_id;
+        let second_login = login_with(&mut client, &zkp, "alice", &x).await?;
+        let second = second_login.session_id.clone();
+        let bob_session = register_and_login(&mut client, "bob").await?;
+
+        let list = |session_id: &str| ListSessionsRequest {
+            user: "alice".to_string(),
+            session_id: session_id.to_string(),
+        };
+        let sessions = client.list_sessions(list(&first)).await?.into_inner().sessions;
+        let mut refs: Vec<_> = sessions.into_iter().map(|s| s.session_ref).collect();
+        let mut expected = [&first, &second].map(|id| AuthService::<ZKP>::session_ref(id));
+        refs.sort();
+        expected.sort();
+        // The listing names sessions without handing out their ids
+        assert_eq!(refs, expected);
+
+        client
+            .revoke_session(RevokeSessionRequest {
+                user: "alice".to_string(),
+                session_id: first.clone(),
+                session_ref: AuthService::<ZKP>::session_ref(&second),
+            })
+            .await?;
+        let err = client
+            .validate_session(ValidateSessionRequest {
+                session_id: second.clone(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        let still_live = client
+            .validate_session(ValidateSessionRequest {
+                session_id: first.clone(),
+            })
+            .await?;
+        assert_eq!(still_live.into_inner().user, "alice");
+        let sessions = client.list_sessions(list(&first)).await?.into_inner().sessions;
+        assert_eq!(sessions.len(), 1);
+        // The refresh token of that login is gone too, so it cannot reopen the session
+        let err = client
+            .refresh_session(RefreshSessionRequest {
+                refresh_token: second_login.refresh_token,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        // Revoking everything includes the session that asked, and no one else's
+        let revoked = client
+            .revoke_all_sessions(RevokeAllSessionsRequest {
+                user: "alice".to_string(),
+                session_id: first.clone(),
+            })
+            .await?;
+        assert_eq!(revoked.into_inner().revoked, 1);
+        let err = client.list_sessions(list(&first)).await.unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        let bob = client
+            .validate_session(ValidateSessionRequest {
+                session_id: bob_session,
+            })
+            .await?;
+        assert_eq!(bob.into_inner().user, "bob");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_each_key_slot_logs_in_independently() -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        let zkp = ZKP::new();
+
+        // The first pair goes into the default slot, as before slots existed
+        let laptop_x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.register_values(&laptop_x);
+        client
+            .register(RegisterRequest {
+                user: "alice".to_string(),
+                y1,
+                y2,
+                ..Default::default()
+            })
+            .await?;
+        let laptop_session = login_with(&mut client, &zkp, "alice", &laptop_x).await?.session_id;
+        let bob_session = register_and_login(&mut client, "bob").await?;
+
+        let phone_x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.register_values(&phone_x);
+        let add_phone = |session_id: Option<&str>| RegisterRequest {
+            user: "alice".to_string(),
+            y1: y1.clone(),
+            y2: y2.clone(),
+            slot: Some("phone".to_string()),
+            session_id: session_id.map(str::to_string),
+            ..Default::default()
+        };
+        // Without a session this would be a second registration of alice
+        for (session_id, code) in [
+            (None, Code::AlreadyExists),
+            (Some("forged"), Code::Unauthenticated),
+            (Some(bob_session.as_str()), Code::PermissionDenied),
+        ] {
+            let err = client.register(add_phone(session_id)).await.unwrap_err();
+            assert_eq!(err.code(), code, "{:?}", session_id);
+        }
+        client.register(add_phone(Some(&laptop_session))).await?;
+        let err = client.register(add_phone(Some(&laptop_session))).await.unwrap_err();
+        assert_eq!(err.code(), Code::AlreadyExists);
+
+        for (slot, x) in [
+            (Some("phone"), &phone_x),
+            (None, &laptop_x),
+            (Some("default"), &laptop_x),
+        ] {
+            let answer = answer_slot_challenge(&mut client, &zkp, "alice", slot, x).await?;
+            let session_id = client.verify_authentication(answer).await?.into_inner().session_id;
+            let user = client
+                .validate_session(ValidateSessionRequest { session_id })
+                .await?
+                .into_inner()
+                .user;
+            assert_eq!(user, "alice");
+        }
+
+        // Each slot's challenge is checked against that slot's keys only
+        let answer =
+            answer_slot_challenge(&mut client, &zkp, "alice", Some("phone"), &laptop_x).await?;
+        let err = client.verify_authentication(answer).await.unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+        let err = answer_slot_challenge(&mut client, &zkp, "alice", Some("tablet"), &phone_x)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_managed_only_by_their_owner_or_an_operator(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let auth_impl = AuthImpl {
+            admin_token: Some("let-me-in".to_string()),
+            ..Default::default()
+        };
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let alice_session = register_and_login(&mut client, "alice").await?;
+        let bob_session = register_and_login(&mut client, "bob").await?;
+        let list = |session_id: &str| ListSessionsRequest {
+            user: "alice".to_string(),
+            session_id: session_id.to_string(),
+        };
+        for (session_id, code) in [
+            ("", Code::Unauthenticated),
+            ("forged", Code::Unauthenticated),
+            (bob_session.as_str(), Code::PermissionDenied),
+        ] {
+            let err = client.list_sessions(list(session_id)).await.unwrap_err();
+            assert_eq!(err.code(), code, "{:?}", session_id);
+        }
+        let err = client
+            .revoke_all_sessions(RevokeAllSessionsRequest {
+                user: "alice".to_string(),
+                session_id: bob_session.clone(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+
+        // The admin token stands in for a session of the user
+        let mut request = Request::new(list(""));
+        request
+            .metadata_mut()
+            .insert(ADMIN_TOKEN_METADATA, "let-me-in".parse().unwrap());
+        let sessions = client.list_sessions(request).await?.into_inner().sessions;
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_ref, AuthService::<ZKP>::session_ref(&alice_session));
+        assert!(!sessions[0].elevated);
+
+        let mut request = Request::new(RevokeAllSessionsRequest {
+            user: "alice".to_string(),
+            session_id: String::new(),
+        });
+        request
+            .metadata_mut()
+            .insert(ADMIN_TOKEN_METADATA, "let-me-in".parse().unwrap());
+        assert_eq!(client.revoke_all_sessions(request).await?.into_inner().revoked, 1);
+        let err = client
+            .validate_session(ValidateSessionRequest {
+                session_id: alice_session,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_opens_a_new_session() -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let login = register_and_login_with_tokens(&mut client, "alice").await?;
+        assert!(!login.refresh_token.is_empty());
+        let refreshed = client
+            .refresh_session(Request::new(RefreshSessionRequest {
+                refresh_token: login.refresh_token,
+            }))
+            .await?
+            .into_inner();
+        assert_ne!(refreshed.session_id, login.session_id);
+
+        let resp = client
+            .validate_session(Request::new(ValidateSessionRequest {
+                session_id: refreshed.session_id,
+            }))
+            .await?
+            .into_inner();
+        assert_eq!(resp.user, "alice");
+
+        let err = client
+            .refresh_session(Request::new(RefreshSessionRequest {
+                refresh_token: "not-a-token".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired_refresh_token_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+        let clock = Arc::new(MockClock::new());
+        let auth_impl = AuthImpl::new(AuthConfig {
+            refresh_ttl: Duration::from_secs(600),
+            clock: clock.clone(),
+            ..Default::default()
+        });
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let login = register_and_login_with_tokens(&mut client, "alice").await?;
+        clock.advance(Duration::from_secs(601));
+
+        let err = client
+            .refresh_session(Request::new(RefreshSessionRequest {
+                refresh_token: login.refresh_token,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        assert_eq!(err.message(), "Refresh token has expired");
+
+        Ok(())
+    }
+
+    /// Register a user and send a challenge request with the given raw commitments.
+    async fn request_challenge_with(
+        r1: Vec<u8>,
+        r2: Vec<u8>,
+    ) -> Result<Result<(), tonic::Status>, Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+
+        let result = client
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1,
+                r2,
+                ..Default::default()
+            }))
+            .await;
+        Ok(result.map(|_| ()))
+    }
+
+    #[tokio::test]
+    async fn test_challenge_rejects_zero_commitment() -> Result<(), Box<dyn std::error::Error>> {
+        let zkp = ZKP::new();
+        let (_, r2) = zkp.compute_pair(&Scalar::new(&zkp, BigUint::from(7u32)).unwrap());
+
+        let err = request_challenge_with(vec![0], r2.to_bytes_be()).await?.unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_challenge_rejects_commitment_equal_to_p() -> Result<(), Box<dyn std::error::Error>> {
+        let zkp = ZKP::new();
+        let (_, r2) = zkp.compute_pair(&Scalar::new(&zkp, BigUint::from(7u32)).unwrap());
+
+        let err = request_challenge_with(zkp.p.to_bytes_be(), r2.to_bytes_be())
+            .await?
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_challenge_requests_are_rate_limited_per_user() -> Result<(), Box<dyn std::error::Error>> {
+        let clock = Arc::new(MockClock::new());
+        let auth_impl = AuthImpl::new(AuthConfig {
+            challenge_limit: 3,
+            challenge_window: Duration::from_secs(10),
+            clock: clock.clone(),
+            ..Default::default()
+        });
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+
+        // Each request needs fresh commitments, as reused ones are refused
+        let challenge_req = || {
+            let (r1, r2) = zkp.compute_pair(&zkp.generate_random_scalar());
+            CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }
+        };
+
+        for _ in 0..3 {
+            client
+                .create_authentication_challenge(Request::new(challenge_req()))
+                .await?;
+        }
+        let err = client
+            .create_authentication_challenge(Request::new(challenge_req()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::ResourceExhausted);
+
+        clock.advance(Duration::from_secs(10));
+        client
+            .create_authentication_challenge(Request::new(challenge_req()))
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired_challenge_cannot_be_verified() -> Result<(), Box<dyn std::error::Error>> {
+        let clock = Arc::new(MockClock::new());
+        let auth_impl = AuthImpl::new(AuthConfig {
+            challenge_ttl: Duration::from_secs(30),
+            clock: clock.clone(),
+            ..Default::default()
+        });
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?;
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = client
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+
+        clock.advance(Duration::from_secs(31));
+
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let s = zkp.solve(&k, &c, &x);
+        let err = client
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id,
+                s: s.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::DeadlineExceeded);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_instances_sharing_a_challenge_store_hand_off_challenges() {
+        let store: Arc<dyn ChallengeStore> = Arc::new(InMemoryChallengeStore::<ZKP>::default());
+        let [issuer, checker] = [AuthImpl::default(), AuthImpl::default()].map(|mut auth_impl| {
+            auth_impl.challenges = store.clone();
+            auth_impl
+        });
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        // Users are not shared, so both instances hold the registration
+        for auth_impl in [&issuer, &checker] {
+            auth_impl
+                .register(Request::new(RegisterRequest {
+                    user: "alice".to_string(),
+                    y1: y1.to_bytes_be(),
+                    y2: y2.to_bytes_be(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+        }
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = issuer
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(issuer.service.auth_id_to_user.lock().unwrap().is_empty());
+
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let session_id = checker
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id.clone(),
+                s: zkp.solve(&k, &c, &x).to_bytes_be(),
+                c: Some(challenge_resp.c),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .session_id;
+        assert_eq!(checker.service.validate_session(&session_id).unwrap(), "alice");
+        assert!(store.get(&challenge_resp.auth_id).await.is_none());
+    }
+
+    /// An `AuditLog` keeping its entries in memory.
+    #[derive(Debug, Default)]
+    struct RecordingLog(Mutex<Vec<AuditEntry>>);
+
+    impl AuditLog for RecordingLog {
+        fn record(&self, entry: AuditEntry) -> std::io::Result<()> {
+            self.0.lock().unwrap().push(entry);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verifications_are_recorded_in_the_audit_log() {
+        let log = Arc::new(RecordingLog::default());
+        let auth_impl = AuthImpl {
+            audit_log: Some(log.clone()),
+            ..Default::default()
+        };
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let mut hashes = Vec::new();
+        for correct in [true, false] {
+            let k = zkp.generate_random_scalar();
+            let (r1, r2) = zkp.compute_pair(&k);
+            let challenge_resp = auth_impl
+                .create_authentication_challenge(Request::new(
+                    CreateAuthenticationChallengeRequest {
+                        user: "alice".to_string(),
+                        r1: r1.to_bytes_be(),
+                        r2: r2.to_bytes_be(),
+                        ..Default::default()
+                    },
+                ))
+                .await
+                .unwrap()
+                .into_inner();
+            let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+            let mut s: BigUint = zkp.solve(&k, &c, &x).into_inner();
+            if !correct {
+                s = (s + 1u32) % &zkp.q;
+            }
+            let result = auth_impl
+                .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                    auth_id: challenge_resp.auth_id.clone(),
+                    s: s.to_bytes_be(),
+                    ..Default::default()
+                }))
+                .await;
+            assert_eq!(result.is_ok(), correct);
+
+            let entries = log.0.lock().unwrap();
+            let entry = entries.last().unwrap();
+            assert_eq!(entry.user, "alice");
+            assert_eq!(entry.auth_id, challenge_resp.auth_id);
+            assert!(entry.timestamp > 0);
+            assert_eq!(entry.transcript_hash.len(), 64);
+            assert!(!entry.transcript_hash.contains(&format!("{:x}", s)));
+            hashes.push(entry.transcript_hash.clone());
+        }
+
+        let entries = log.0.lock().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].outcome, AuditOutcome::Success);
+        assert!(matches!(
+            &entries[1].outcome,
+            AuditOutcome::Failure(reason) if reason.contains("incorrect challenge solution")
+        ));
+        assert_ne!(hashes[0], hashes[1]);
+    }
+
+    #[tokio::test]
+    async fn test_solved_challenge_cannot_be_replayed() {
+        let auth_impl = AuthImpl::default();
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = auth_impl
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let verify_req = VerifyAuthenticationRequest {
+            auth_id: challenge_resp.auth_id,
+            s: zkp.solve(&k, &c, &x).to_bytes_be(),
+            ..Default::default()
+        };
+        auth_impl
+            .verify_authentication(Request::new(verify_req.clone()))
+            .await
+            .unwrap();
+
+        // The commitments and challenge went with the pending entry
+        assert!(auth_impl.service.auth_id_to_user.lock().unwrap().is_empty());
+
+        let err = auth_impl
+            .verify_authentication(Request::new(verify_req))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_expired_challenges_are_swept_on_next_challenge() {
+        let clock = Arc::new(MockClock::new());
+        let auth_impl = AuthImpl::new(AuthConfig {
+            challenge_ttl: Duration::from_secs(30),
+            clock: clock.clone(),
+            ..Default::default()
+        });
+
+        let zkp = ZKP::new();
+        let (y1, y2) = zkp.compute_pair(&zkp.generate_random_scalar());
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        // Each request needs fresh commitments, as reused ones are refused
+        let challenge_req = || {
+            let (r1, r2) = zkp.compute_pair(&zkp.generate_random_scalar());
+            CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }
+        };
+        let first_auth_id = auth_impl
+            .create_authentication_challenge(Request::new(challenge_req()))
+            .await
+            .unwrap()
+            .into_inner()
+            .auth_id;
+
+        clock.advance(Duration::from_secs(31));
+        auth_impl
+            .create_authentication_challenge(Request::new(challenge_req()))
+            .await
+            .unwrap();
+
+        let auth_map = auth_impl.service.auth_id_to_user.lock().unwrap();
+        assert!(!auth_map.contains_key(&first_auth_id));
+        assert_eq!(auth_map.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_end_to_end_auth_flow_with_hex_fields() -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1_hex: Some(biguint_to_hex(&y1)),
+                y2_hex: Some(biguint_to_hex(&y2)),
+                ..Default::default()
+            }))
+            .await?;
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = client
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1_hex: Some(biguint_to_hex(&r1)),
+                r2_hex: Some(biguint_to_hex(&r2)),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let s = zkp.solve(&k, &c, &x);
+        let verify_resp = client
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id,
+                s_hex: Some(biguint_to_hex(&s)),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+        assert!(!verify_resp.session_id.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_invalid_hex() {
+        let auth_impl = AuthImpl::default();
+        let err = auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1_hex: Some("not hex".to_string()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_empty_y1() {
+        let auth_impl = AuthImpl::default();
+        let err = auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: vec![],
+                y2: vec![4],
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert_eq!(err.message(), "'y1' must not be empty");
+    }
+
+    #[tokio::test]
+    async fn test_empty_hex_fields_are_rejected() {
+        let auth_impl = AuthImpl::default();
+        let err = auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1_hex: Some(String::new()),
+                y2: vec![4],
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert_eq!(err.message(), "'y1_hex' must not be empty");
+
+        let err = auth_impl
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1_hex: Some(String::new()),
+                r2: vec![4],
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.message(), "'r1_hex' must not be empty");
+
+        let err = auth_impl
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: "unknown".to_string(),
+                s_hex: Some(String::new()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.message(), "'s_hex' must not be empty");
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_an_oversized_y1() {
+        let auth_impl = AuthImpl::default();
+        let err = auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: vec![1; 1024],
+                y2: vec![4],
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert_eq!(err.message(), "'y1' is longer than 144 bytes");
+
+        let err = auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1_hex: Some("1".repeat(289)),
+                y2: vec![4],
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert!(auth_impl.service.user_info.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_server_refuses_requests_over_the_size_limit() {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await.unwrap();
+
+        let err = client
+            .register(Request::new(RegisterRequest {
+                user: "a".repeat(MAX_MESSAGE_SIZE),
+                y1: vec![4],
+                y2: vec![4],
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::OutOfRange);
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_public_keys_of_zero_secret() {
+        let auth_impl = AuthImpl::default();
+        let zkp = ZKP::new();
+        let (y1, y2) = zkp.compute_pair(&Scalar::new(&zkp, BigUint::from(0u32)).unwrap());
+        let err = auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "mallory".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert!(auth_impl.service.user_info.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_public_keys_outside_the_subgroup() {
+        let auth_impl = AuthImpl::default();
+        let zkp = ZKP::new();
+        let register = |user: &str, y1: &BigUint, y2: &BigUint| {
+            auth_impl.register(Request::new(RegisterRequest {
+                user: user.to_string(),
+                y1: zkp.element_to_bytes(y1),
+                y2: zkp.element_to_bytes(y2),
+                ..Default::default()
+            }))
+        };
+
+        let (y1, y2) = zkp.public_keys(&BigUint::from(5u32));
+        register("alice", &y1, &y2).await.unwrap();
+
+        // p - 1 is in [1, p) but has order 2
+        let err = register("mallory", &y1, &(&zkp.p - 1u32)).await.unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert_eq!(err.message(), "'y2' is not in the order-q subgroup");
+        assert!(!auth_impl.service.user_info.read().unwrap().contains_key("mallory"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_empty_s() {
+        let auth_impl = AuthImpl::default();
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = auth_impl
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let err = auth_impl
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id,
+                s: vec![],
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert_eq!(err.message(), "'s' must not be empty");
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_an_echoed_challenge_that_was_not_issued() {
+        let auth_impl = AuthImpl::default();
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = auth_impl
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let s = zkp.solve(&k, &c, &x).to_bytes_be();
+
+        let other_c = zkp.scalar_to_bytes(&((&*c + 1u32) % &zkp.q));
+        let err = auth_impl
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id.clone(),
+                s: s.clone(),
+                c: Some(other_c),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::FailedPrecondition);
+
+        // The mismatch is caught before the challenge is consumed
+        auth_impl
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id,
+                s,
+                c: Some(challenge_resp.c),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reused_commitments_are_refused() {
+        let auth_impl = AuthImpl::default();
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_req = CreateAuthenticationChallengeRequest {
+            user: "alice".to_string(),
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+            ..Default::default()
+        };
+        let challenge_resp = auth_impl
+            .create_authentication_challenge(Request::new(challenge_req.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        auth_impl
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id,
+                s: zkp.solve(&k, &c, &x).to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        // A second challenge for the same nonce would let anyone who sees both answers
+        // solve for x, so it is never issued; padding r1 with a zero byte does not help
+        let mut padded_r1 = vec![0];
+        padded_r1.extend_from_slice(&challenge_req.r1);
+        for r1 in [challenge_req.r1.clone(), padded_r1] {
+            let err = auth_impl
+                .create_authentication_challenge(Request::new(
+                    CreateAuthenticationChallengeRequest {
+                        r1,
+                        ..challenge_req.clone()
+                    },
+                ))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), Code::Aborted);
+        }
+        assert!(auth_impl.service.auth_id_to_user.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_commitments_are_only_remembered_for_the_challenge_ttl() {
+        let clock = Arc::new(MockClock::new());
+        let auth_impl = AuthImpl::new(AuthConfig {
+            challenge_ttl: Duration::from_secs(60),
+            allow_unregistered_challenges: true,
+            clock: clock.clone(),
+            ..Default::default()
+        });
+        let zkp = ZKP::new();
+        let (y1, y2) = zkp.compute_pair(&zkp.generate_random_scalar());
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let (r1, r2) = zkp.compute_pair(&zkp.generate_random_scalar());
+        for user in ["alice", "mallory"] {
+            auth_impl
+                .create_authentication_challenge(Request::new(
+                    CreateAuthenticationChallengeRequest {
+                        user: user.to_string(),
+                        r1: r1.to_bytes_be(),
+                        r2: r2.to_bytes_be(),
+                        ..Default::default()
+                    },
+                ))
+                .await
+                .unwrap();
+        }
+        // Names nobody registered leave nothing behind
+        let users: Vec<_> = {
+            let used_commitments = auth_impl.service.used_commitments.lock().unwrap();
+            used_commitments.keys().cloned().collect()
+        };
+        assert_eq!(users, ["alice"]);
+
+        // Once the challenge has expired the record is dropped on the next challenge
+        clock.advance(Duration::from_secs(61));
+        let (other_r1, other_r2) = zkp.compute_pair(&zkp.generate_random_scalar());
+        auth_impl
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: other_r1.to_bytes_be(),
+                r2: other_r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+        assert_eq!(auth_impl.service.used_commitments.lock().unwrap()["alice"].len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_short_challenges_still_verify() {
+        let auth_impl = AuthImpl::new(AuthConfig {
+            challenge_bits: Some(128),
+            ..Default::default()
+        });
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = auth_impl
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        assert!(c.bits() <= 128);
+
+        let resp = auth_impl
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id,
+                s: zkp.solve(&k, &c, &x).to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!resp.session_id.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_inline_keys_for_unregistered_user() {
+        let auth_impl = AuthImpl::new(AuthConfig {
+            allow_unregistered_challenges: true,
+            ..Default::default()
+        });
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = auth_impl
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "carol".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let s = zkp.solve(&k, &c, &x);
+
+        let resp = auth_impl
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id,
+                s: s.to_bytes_be(),
+                y1: Some(y1.to_bytes_be()),
+                y2: Some(y2.to_bytes_be()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        // The proof checks out, but nothing is opened for a name no one holds
+        assert_eq!(resp, VerifyAuthenticationResponse::default());
+        assert!(auth_impl.service.sessions.read().unwrap().is_empty());
+        assert!(auth_impl.service.refresh_tokens.read().unwrap().is_empty());
+        assert!(auth_impl.service.user_info.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unregistered_proof_cannot_rotate_keys_once_the_name_is_taken() {
+        let auth_impl = AuthImpl::new(AuthConfig {
+            allow_unregistered_challenges: true,
+            ..Default::default()
+        });
+        let zkp = ZKP::new();
+        let mallory_x = zkp.generate_random_scalar();
+        let (mallory_y1, mallory_y2) = zkp.compute_pair(&mallory_x);
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = auth_impl
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "carol".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // Carol registers while Mallory's challenge for her name is still pending
+        let carol_x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&carol_x);
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "carol".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let proof = VerifyAuthenticationRequest {
+            auth_id: challenge_resp.auth_id,
+            s: zkp.solve(&k, &c, &mallory_x).to_bytes_be(),
+            y1: Some(mallory_y1.to_bytes_be()),
+            y2: Some(mallory_y2.to_bytes_be()),
+            ..Default::default()
+        };
+        let err = auth_impl
+            .rotate_keys(Request::new(rotate_to(
+                &zkp,
+                "carol",
+                &mallory_x,
+                Some(Authorization::Proof(proof)),
+            )))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+        assert_eq!(auth_impl.service.public_keys("carol"), Some((y1.into_inner(), y2.into_inner())));
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_wrong_inline_keys_is_denied() {
+        let auth_impl = AuthImpl::new(AuthConfig {
+            allow_unregistered_challenges: true,
+            ..Default::default()
+        });
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&Scalar::reduce(&zkp, &(&*x + 1u32)));
+
+        let k = zkp.generate_random_scalar();
+        let (r1, r2) = zkp.compute_pair(&k);
+        let challenge_resp = auth_impl
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "carol".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+        let s = zkp.solve(&k, &c, &x);
+
+        let err = auth_impl
+            .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                auth_id: challenge_resp.auth_id,
+                s: s.to_bytes_be(),
+                y1: Some(y1.to_bytes_be()),
+                y2: Some(y2.to_bytes_be()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn test_challenge_for_unregistered_user_is_not_found_by_default() {
+        let auth_impl = AuthImpl::default();
+        let zkp = ZKP::new();
+        let (r1, r2) = zkp.compute_pair(&zkp.generate_random_scalar());
+        let err = auth_impl
+            .create_authentication_challenge(Request::new(CreateAuthenticationChallengeRequest {
+                user: "carol".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_integrity_checks_flag_swapped_registration_keys() {
+        for integrity_checks in [false, true] {
+            let auth_impl = AuthImpl::new(AuthConfig {
+                integrity_checks,
+                ..Default::default()
+            });
+            let zkp = ZKP::new();
+            let x = zkp.generate_random_scalar();
+            let (y1, y2) = zkp.compute_pair(&x);
+            auth_impl
+                .register(Request::new(RegisterRequest {
+                    user: "alice".to_string(),
+                    y1: y2.to_bytes_be(),
+                    y2: y1.to_bytes_be(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            let k = zkp.generate_random_scalar();
+            let (r1, r2) = zkp.compute_pair(&k);
+            let challenge_resp = auth_impl
+                .create_authentication_challenge(Request::new(
+                    CreateAuthenticationChallengeRequest {
+                        user: "alice".to_string(),
+                        r1: r1.to_bytes_be(),
+                        r2: r2.to_bytes_be(),
+                        ..Default::default()
+                    },
+                ))
+                .await
+                .unwrap()
+                .into_inner();
+            let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+
+            let err = auth_impl
+                .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                    auth_id: challenge_resp.auth_id,
+                    s: zkp.solve(&k, &c, &x).to_bytes_be(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), Code::PermissionDenied);
+            assert_eq!(err.message().contains("swapped"), integrity_checks);
+            assert_eq!(logs_contain("y1 and y2 swapped"), integrity_checks);
+        }
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_register_emits_registration_successful_event() {
+        let auth_impl = AuthImpl::default();
+        let zkp = ZKP::new();
+        let (y1, y2) = zkp.compute_pair(&zkp.generate_random_scalar());
+
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        assert!(logs_contain("Registration successful"));
+        assert!(logs_contain("user=alice"));
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_existing_user_and_keeps_original_keys() {
+        let auth_impl = AuthImpl::default();
+        let zkp = ZKP::new();
+
+        let (y1, y2) = zkp.compute_pair(&zkp.generate_random_scalar());
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let (other_y1, other_y2) = zkp.compute_pair(&zkp.generate_random_scalar());
+        let err = auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: other_y1.to_bytes_be(),
+                y2: other_y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::AlreadyExists);
+
+        let user_info_map = auth_impl.service.user_info.read().unwrap();
+        let alice = user_info_map["alice"].lock().unwrap();
+        let (stored_y1, stored_y2) = &alice.slots[DEFAULT_SLOT];
+        assert_eq!(*stored_y1, *y1);
+        assert_eq!(*stored_y2, *y2);
+    }
+
+    fn stats_request(token: Option<&str>) -> Request<GetStatsRequest> {
+        let mut request = Request::new(GetStatsRequest {});
+        if let Some(token) = token {
+            request
+                .metadata_mut()
+                .insert(ADMIN_TOKEN_METADATA, token.parse().unwrap());
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn test_closed_enrollment_requires_the_admin_token() {
+        let auth_impl = AuthImpl {
+            admin_token: Some("let-me-in".to_string()),
+            allow_self_register: false,
+            ..Default::default()
+        };
+        let (y1, y2) = ZKP::new().register_values(&BigUint::from(7u32));
+        let register = |user: &str, token: Option<&str>| {
+            let mut request = Request::new(RegisterRequest {
+                user: user.to_string(),
+                y1: y1.clone(),
+                y2: y2.clone(),
+                ..Default::default()
+            });
+            if let Some(token) = token {
+                request
+                    .metadata_mut()
+                    .insert(ADMIN_TOKEN_METADATA, token.parse().unwrap());
+            }
+            request
+        };
+
+        for token in [None, Some("let-me-out")] {
+            let err = auth_impl
+                .register(register("alice", token))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), Code::PermissionDenied);
+        }
+        assert!(auth_impl.service.public_keys("alice").is_none());
+
+        auth_impl
+            .register(register("alice", Some("let-me-in")))
+            .await
+            .unwrap();
+        assert!(auth_impl.service.public_keys("alice").is_some());
+
+        // Without an admin token configured nobody can register
+        let closed = AuthImpl {
+            allow_self_register: false,
+            ..Default::default()
+        };
+        let err = closed
+            .register(register("bob", Some("let-me-in")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+
+        // The default stays open
+        AuthImpl::default()
+            .register(register("carol", None))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_stats_counts_users_for_the_admin_token(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let auth_impl = AuthImpl {
+            admin_token: Some("let-me-in".to_string()),
+            ..Default::default()
+        };
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+
+        for user in ["alice", "bob", "carol"] {
+            register_and_login(&mut client, user).await?;
+        }
+
+        let stats = client
+            .get_stats(stats_request(Some("let-me-in")))
+            .await?
+            .into_inner();
+        assert_eq!(stats.user_count, 3);
+        assert_eq!(stats.pending_challenges, 0);
+
+        for token in [Some("let-me-out"), Some(""), None] {
+            let err = client.get_stats(stats_request(token)).await.unwrap_err();
+            assert_eq!(err.code(), Code::Unauthenticated);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_an_empty_admin_token_grants_nothing() -> Result<(), Box<dyn std::error::Error>> {
+        let auth_impl = AuthImpl {
+            admin_token: Some(String::new()),
+            ..Default::default()
+        };
+        let (server_addr, _join_handle) = spawn_server_with(auth_impl).await;
+        sleep(Duration::from_millis(100)).await;
+        let mut client = AuthClient::connect(server_addr).await?;
+        register_and_login(&mut client, "alice").await?;
+
+        for token in [Some(""), None] {
+            let err = client.get_stats(stats_request(token)).await.unwrap_err();
+            assert_eq!(err.code(), Code::Unauthenticated);
+        }
+        let err = client
+            .list_sessions(Request::new(ListSessionsRequest {
+                user: "alice".to_string(),
+                session_id: String::new(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        // Nor can a stranger add a key slot to alice and log in with it
+        let zkp = ZKP::new();
+        let (y1, y2) = zkp.compute_pair(&zkp.generate_random_scalar());
+        let err = client
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                slot: Some("mallory".to_string()),
+                session_id: Some(String::new()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_verification_outcomes() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let auth_impl = AuthImpl {
+            metrics: metrics::with_local_recorder(&recorder, Metrics::register),
+            ..Default::default()
+        };
+        let zkp = ZKP::new();
+        let x = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth_impl
+            .register(Request::new(RegisterRequest {
+                user: "alice".to_string(),
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        for correct in [true, false] {
+            let k = zkp.generate_random_scalar();
+            let (r1, r2) = zkp.compute_pair(&k);
+            let challenge_resp = auth_impl
+                .create_authentication_challenge(Request::new(
+                    CreateAuthenticationChallengeRequest {
+                        user: "alice".to_string(),
+                        r1: r1.to_bytes_be(),
+                        r2: r2.to_bytes_be(),
+                        ..Default::default()
+                    },
+                ))
+                .await
+                .unwrap()
+                .into_inner();
+            let c = Scalar::new(&zkp, BigUint::from_bytes_be(&challenge_resp.c)).unwrap();
+            let s = &*zkp.solve(&k, &c, &x) + u32::from(!correct);
+
+            let result = auth_impl
+                .verify_authentication(Request::new(VerifyAuthenticationRequest {
+                    auth_id: challenge_resp.auth_id,
+                    s: s.to_bytes_be(),
+                    ..Default::default()
+                }))
+                .await;
+            assert_eq!(result.is_ok(), correct);
+        }
+
+        let rendered = handle.render();
+        for line in [
+            "register_total 1",
+            "challenge_total 2",
+            "verify_success_total 1",
+            "verify_failure_total 1",
+        ] {
+            assert!(rendered.lines().any(|l| l == line), "{} in {}", line, rendered);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_stats_is_refused_without_a_configured_token() {
+        let auth_impl = AuthImpl::default();
+        let err = auth_impl.get_stats(stats_request(Some(""))).await.unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_serve_returns_after_shutdown_signal() -> Result<(), Box<dyn std::error::Error>> {
+        let std_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let local_addr = std_listener.local_addr().unwrap();
+        drop(std_listener);
+
+        let (shutdown_tx, mut shutdown_rx) = tokio::sync::mpsc::channel::<()>(1);
+        let server = tokio::spawn(serve(AuthImpl::default(), local_addr, None, async move {
+            shutdown_rx.recv().await;
+        }));
+        sleep(Duration::from_millis(100)).await;
+
+        let mut client = AuthClient::connect(format!("http://{}", local_addr)).await?;
+        register_and_login(&mut client, "alice").await?;
+        drop(client);
+
+        shutdown_tx.send(()).await?;
+        let result = tokio::time::timeout(Duration::from_secs(5), server).await??;
+        assert!(result.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_health_service_reports_serving() -> Result<(), Box<dyn std::error::Error>> {
+        let (server_addr, _join_handle) = spawn_server().await;
+        sleep(Duration::from_millis(100)).await;
+
+        let channel = tonic::transport::Channel::from_shared(server_addr)?
+            .connect()
+            .await?;
+        let mut health_client = HealthClient::new(channel);
+        let resp = health_client
+            .check(Request::new(HealthCheckRequest {
+                service: "auth.Auth".to_string(),
+            }))
+            .await?
+            .into_inner();
+        assert_eq!(resp.status(), ServingStatus::Serving);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_end_to_end_auth_flow_over_tls() -> Result<(), Box<dyn std::error::Error>> {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+        let cert_pem = cert.cert.pem();
+        let key_pem = cert.key_pair.serialize_pem();
+
+        let std_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let local_addr = std_listener.local_addr().unwrap();
+        drop(std_listener);
+
+        let tls = ServerTlsConfig::new().identity(Identity::from_pem(&cert_pem, &key_pem));
+        let _join_handle = tokio::spawn(async move {
+            serve(AuthImpl::default(), local_addr, Some(tls), std::future::pending())
+                .await
+                .expect("server failed");
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        let channel = tonic::transport::Channel::from_shared(format!(
+            "https://localhost:{}",
+            local_addr.port()
+        ))?
+        .tls_config(
+            tonic::transport::ClientTlsConfig::new()
+                .ca_certificate(tonic::transport::Certificate::from_pem(&cert_pem))
+                .domain_name("localhost"),
+        )?
+        .connect()
+        .await?;
+        let mut client = AuthClient::new(channel);
+
+        let session_id = register_and_login(&mut client, "alice").await?;
+        assert!(!session_id.is_empty(), "Session ID should not be empty");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! A throwaway `Auth` server for integration tests, on a port the OS picks.
+
+use tokio::{net::TcpListener, sync::oneshot, task::JoinHandle};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Uri;
+
+use crate::server::{self, AuthImpl};
+
+/// Keeps a server from `spawn_test_server` running; dropping it stops the server.
+#[derive(Debug)]
+pub struct ShutdownHandle {
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl ShutdownHandle {
+    /// Stops the server and waits for in-flight requests to finish.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for ShutdownHandle {
+    /// Tells the server to stop without waiting for it.
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+/// Starts a plaintext server with a default `AuthImpl` on `127.0.0.1`, returning its URI.
+/// The port is bound before this returns, so clients can connect straight away. Must be
+/// called from within a Tokio runtime.
+///
+/// ```
+/// use chaum_pedersen_protocol::{
+///     auth::{auth_client::AuthClient, ValidateSessionRequest},
+///     testing::spawn_test_server,
+/// };
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let (uri, server) = spawn_test_server().await;
+/// let mut client = AuthClient::connect(uri).await.unwrap();
+///
+/// let err = client
+///     .validate_session(ValidateSessionRequest {
+///         session_id: "nobody".to_string(),
+///     })
+///     .await
+///     .unwrap_err();
+/// assert_eq!(err.code(), tonic::Code::Unauthenticated);
+///
+/// server.shutdown().await;
+/// # }
+/// ```
+pub async fn spawn_test_server() -> (Uri, ShutdownHandle) {
+    spawn_test_server_with(AuthImpl::default()).await
+}
+
+/// Like `spawn_test_server`, but serving a caller-configured `AuthImpl`.
+pub async fn spawn_test_server_with(auth_impl: AuthImpl) -> (Uri, ShutdownHandle) {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("failed to bind a local port");
+    let uri = format!("http://{}", listener.local_addr().unwrap())
+        .parse()
+        .unwrap();
+    let router = server::router(auth_impl, None)
+        .await
+        .expect("a server without TLS always builds");
+
+    let (shutdown, stopped) = oneshot::channel::<()>();
+    let task = tokio::spawn(async move {
+        router
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                let _ = stopped.await;
+            })
+            .await
+            .expect("test server failed");
+    });
+
+    (
+        uri,
+        ShutdownHandle {
+            shutdown: Some(shutdown),
+            task: Some(task),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::auth_client::AuthClient;
+    use tokio::time::{sleep, Duration};
+
+    #[tokio::test]
+    async fn test_dropping_the_handle_stops_the_server() {
+        let (uri, server) = spawn_test_server().await;
+        assert!(AuthClient::connect(uri.clone()).await.is_ok());
+
+        drop(server);
+        sleep(Duration::from_millis(100)).await;
+        assert!(AuthClient::connect(uri).await.is_err());
+    }
+}
@@ -1,286 +1,366 @@
-use std::{collections::HashMap, sync::Mutex};
-
-use num_bigint::BigUint;
-use tonic::{transport::Server, Code, Request, Response, Status};
-
-use chaum_pedersen::ZKP;
-
-pub mod auth {
-    include!("./auth.rs");
-}
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use auth::{
-    auth_server::{Auth, AuthServer},
-    CreateAuthenticationChallengeRequest, CreateAuthenticationChallengeResponse, RegisterRequest,
-    RegisterResponse, VerifyAuthenticationRequest, VerifyAuthenticationResponse,
+use clap::{Parser, ValueEnum};
+use metrics_exporter_prometheus::PrometheusBuilder;
+use tonic::transport::{Identity, ServerTlsConfig};
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+#[cfg(unix)]
+use chaum_pedersen_protocol::server::serve_uds;
+use chaum_pedersen_protocol::server::{serve, AuthImpl, Metrics};
+use zkp::{
+    audit::JsonlAuditLog,
+    service::{AuthConfig, AuthError, AuthService},
+    GroupSize, ZkpGroup, ZKP,
 };
 
-#[derive(Debug, Default)]
-pub struct AuthImpl {
-    pub user_info: Mutex<HashMap<String, UserInfo>>,
-    pub auth_id_to_user: Mutex<HashMap<String, String>>,
+#[derive(Debug, Parser)]
+#[command(name = "verifier", about = "Chaum-Pedersen authentication server")]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:41337")]
+    addr: String,
+
+    /// Listen on this Unix domain socket instead of TCP, for a prover on the same host
+    #[arg(long, conflicts_with_all = ["addr", "tls_cert"])]
+    uds: Option<PathBuf>,
+
+    /// Group the proofs are computed in
+    #[arg(long, value_enum, default_value_t = Group::Modp1024)]
+    group: Group,
+
+    /// PEM certificate to serve TLS with; requires --tls-key
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key matching --tls-cert
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Operator token, sent in `x-admin-token` metadata. It is required to call GetStats,
+    /// which is refused when unset, and to Register under --closed-enrollment. It also
+    /// stands in for a user's session to list or revoke the user's sessions and to add a
+    /// key slot to the user
+    #[arg(
+        long,
+        env = "VERIFIER_ADMIN_TOKEN",
+        hide_env_values = true,
+        value_parser = clap::builder::NonEmptyStringValueParser::new()
+    )]
+    admin_token: Option<String>,
+
+    /// Refuse Register calls that do not carry the admin token, so only an operator can
+    /// create users
+    #[arg(long, requires = "admin_token")]
+    closed_enrollment: bool,
+
+    /// Issue challenges for unregistered names and check their proofs against the public
+    /// keys sent with VerifyAuthentication, without opening a session
+    #[arg(long)]
+    allow_unregistered: bool,
+
+    /// Address to serve Prometheus metrics on over plain HTTP; metrics are off when unset
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
+
+    /// On a failed login, check whether the client's registered keys look swapped and log it
+    #[arg(long)]
+    integrity_checks: bool,
+
+    /// Draw challenges of this many bits instead of the full subgroup order; shorter
+    /// challenges are faster but give a cheating prover a 2^-bits chance per attempt
+    #[arg(long, value_parser = clap::value_parser!(u64).range(64..))]
+    challenge_bits: Option<u64>,
+
+    /// Append a JSON line per answered challenge to this file: user, auth_id, time,
+    /// outcome and a hash of the transcript
+    #[arg(long)]
+    audit_log: Option<PathBuf>,
+
+    /// Before serving, run a register/challenge/verify cycle against an in-process
+    /// service and exit if the group's math does not hold up
+    #[arg(long)]
+    self_test: bool,
 }
 
-#[derive(Debug, Default)]
-pub struct UserInfo {
-    pub user_name: String,
-    pub y1: BigUint,
-    pub y2: BigUint,
-
-    pub r1: BigUint,
-    pub r2: BigUint,
+impl Args {
+    /// Loads the TLS identity when `--tls-cert`/`--tls-key` were given.
+    fn tls_config(&self) -> std::io::Result<Option<ServerTlsConfig>> {
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => {
+                let identity = Identity::from_pem(std::fs::read(cert)?, std::fs::read(key)?);
+                Ok(Some(ServerTlsConfig::new().identity(identity)))
+            }
+            _ => Ok(None),
+        }
+    }
+}
 
-    pub c: BigUint,
-    pub s: BigUint,
-    pub session_id: String,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Group {
+    /// RFC 5114 1024-bit MODP group with a 160-bit subgroup
+    #[value(name = "1024")]
+    Modp1024,
+    /// RFC 3526 2048-bit MODP group
+    #[value(name = "2048")]
+    Modp2048,
+    /// RFC 3526 3072-bit MODP group
+    #[value(name = "3072")]
+    Modp3072,
 }
 
+impl Group {
+    fn zkp(self) -> ZKP {
+        ZKP::for_group(match self {
+            Group::Modp1024 => GroupSize::Modp1024,
+            Group::Modp2048 => GroupSize::Modp2048,
+            Group::Modp3072 => GroupSize::Modp3072,
+        })
+    }
+}
 
-#[tokio::main(flavor = "current_thread")]
+#[tokio::main]
 async fn main() {
-    let addr = "127.0.0.1:41337".to_string();
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let args = Args::parse();
+    let zkp = args.group.zkp();
+    let tls = args.tls_config().unwrap_or_else(|e| {
+        eprintln!("error: failed to read TLS certificate or key: {}", e);
+        std::process::exit(2);
+    });
+    if args.self_test {
+        self_test(&zkp).unwrap_or_else(|e| {
+            eprintln!("error: self-test failed: {}", e);
+            std::process::exit(2);
+        });
+        info!("Self-test passed");
+    }
+    let mut auth_impl = AuthImpl::new(AuthConfig {
+        allow_unregistered_challenges: args.allow_unregistered,
+        challenge_bits: args.challenge_bits,
+        integrity_checks: args.integrity_checks,
+        ..AuthConfig::with_group(zkp)
+    });
+    auth_impl.admin_token = args.admin_token;
+    auth_impl.allow_self_register = !args.closed_enrollment;
+    if let Some(path) = &args.audit_log {
+        let log = JsonlAuditLog::open(path).unwrap_or_else(|e| {
+            eprintln!("error: failed to open {}: {}", path.display(), e);
+            std::process::exit(2);
+        });
+        auth_impl.audit_log = Some(Arc::new(log));
+    }
+    if let Some(metrics_addr) = args.metrics_addr {
+        PrometheusBuilder::new()
+            .with_http_listener(metrics_addr)
+            .install()
+            .unwrap_or_else(|e| {
+                eprintln!("error: failed to start the metrics exporter: {}", e);
+                std::process::exit(2);
+            });
+        info!("Serving metrics on {}", metrics_addr);
+        auth_impl.metrics = Metrics::register();
+    }
+
+    match args.uds {
+        Some(path) => {
+            info!("Running the server on {}", path.display());
+            serve_on_socket(auth_impl, &path).await;
+        }
+        None => {
+            info!(tls = tls.is_some(), "Running the server in {}", args.addr);
+            serve(auth_impl, args.addr.parse().unwrap(), tls, shutdown_signal())
+                .await
+                .unwrap();
+        }
+    }
+    info!("Server stopped");
+}
 
-    println!("Running the server in {}", addr);
+/// Registers a user with a fresh secret on a new `AuthService` over `zkp`, logs in as
+/// it, and checks that a wrong answer to a second challenge is refused.
+fn self_test(zkp: &ZKP) -> Result<(), String> {
+    let service = AuthService::with_group(zkp.clone());
+    let step = |what: &str, e: AuthError| format!("{}: {}", what, e);
+    let x = zkp.generate_nonzero_scalar().into_inner();
+    let (y1, y2) = ZkpGroup::compute_pair(zkp, &x);
+    service
+        .register(
+            "self-test",
+            &zkp.element_to_bytes(&y1),
+            &zkp.element_to_bytes(&y2),
+        )
+        .map_err(|e| step("register", e))?;
+
+    for correct in [true, false] {
+        let k = zkp.generate_nonzero_scalar().into_inner();
+        let (r1, r2) = ZkpGroup::compute_pair(zkp, &k);
+        let challenge = service
+            .create_challenge(
+                "self-test",
+                &zkp.element_to_bytes(&r1),
+                &zkp.element_to_bytes(&r2),
+            )
+            .map_err(|e| step("challenge", e))?;
+        let mut s = ZkpGroup::solve(zkp, &k, &challenge.c, &x);
+        if !correct {
+            s = (s + 1u32) % &zkp.q;
+        }
+        let result = service.verify(&challenge.auth_id, &zkp.scalar_to_bytes(&s));
+        match (correct, result) {
+            (true, Err(e)) => return Err(step("verify", e)),
+            (false, Ok(_)) => return Err("verify: a wrong answer was accepted".to_string()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
 
-    let auth_impl = AuthImpl::default();
-    Server::builder()
-        .add_service(AuthServer::new(auth_impl))
-        .serve(addr.parse().unwrap())
+/// Serves on a Unix domain socket at `path`, removing the socket file once stopped.
+#[cfg(unix)]
+async fn serve_on_socket(auth_impl: AuthImpl, path: &Path) {
+    let listener = tokio::net::UnixListener::bind(path).unwrap_or_else(|e| {
+        eprintln!("error: failed to bind {}: {}", path.display(), e);
+        std::process::exit(2);
+    });
+    serve_uds(auth_impl, listener, shutdown_signal())
         .await
         .unwrap();
+    let _ = std::fs::remove_file(path);
+}
+
+#[cfg(not(unix))]
+async fn serve_on_socket(_auth_impl: AuthImpl, _path: &Path) {
+    eprintln!("error: --uds needs a platform with Unix domain sockets");
+    std::process::exit(2);
 }
 
-#[tonic::async_trait]
-impl Auth for AuthImpl {
-    async fn register(
-        &self,
-        request: Request<RegisterRequest>,
-    ) -> Result<Response<RegisterResponse>, Status> {
-        let request = request.into_inner();
+/// Resolves on Ctrl-C, or on SIGTERM where there is one.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to listen for Ctrl-C");
+    };
 
-        println!("Registration of user: {:?}", request.user);
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-        let user_info = UserInfo {
-            user_name: request.user.clone(),
-            y1: BigUint::from_bytes_be(&request.y1),
-            y2: BigUint::from_bytes_be(&request.y2),
-            ..Default::default()
-        };
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("Shutting down, draining connections");
+}
 
-        let mut user_info_map = self.user_info.lock().unwrap();
-        user_info_map.insert(request.user, user_info);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        println!("Registration successful");
+    #[test]
+    fn test_args_tls_flags_must_be_given_together() {
+        assert!(Args::try_parse_from(["verifier", "--tls-cert", "cert.pem"]).is_err());
+        assert!(Args::try_parse_from(["verifier", "--tls-key", "key.pem"]).is_err());
 
-        Ok(Response::new(RegisterResponse {}))
+        let args =
+            Args::try_parse_from(["verifier", "--tls-cert", "cert.pem", "--tls-key", "key.pem"])
+                .unwrap();
+        assert_eq!(args.tls_cert, Some(PathBuf::from("cert.pem")));
+        assert_eq!(args.tls_key, Some(PathBuf::from("key.pem")));
     }
 
-    async fn create_authentication_challenge(
-        &self,
-        request: Request<CreateAuthenticationChallengeRequest>,
-    ) -> Result<Response<CreateAuthenticationChallengeResponse>, Status> {
-        let request = request.into_inner();
-        println!("Processing Challenge Request for user: {:?}", request.user);
-
-        let mut user_info_map = self.user_info.lock().unwrap();
-        match user_info_map.get_mut(&request.user) {
-            Some(user_info) => {
-                let (_, _, _, q) = ZKP::get_constants();
-                let c = ZKP::generate_random_number_below(&q);
-                let auth_id = ZKP::generate_random_string(12);
-
-                user_info.c = c.clone();
-                user_info.r1 = BigUint::from_bytes_be(&request.r1);
-                user_info.r2 = BigUint::from_bytes_be(&request.r2);
-
-                let mut auth_map = self.auth_id_to_user.lock().unwrap();
-                auth_map.insert(auth_id.clone(), request.user.clone());
-
-                println!("Challenge created");
-
-                Ok(Response::new(CreateAuthenticationChallengeResponse {
-                    auth_id,
-                    c: c.to_bytes_be(),
-                }))
-            }
-            None => Err(Status::new(
-                Code::NotFound,
-                format!("User '{}' not found", request.user),
-            )),
-        }
+    #[test]
+    fn test_args_defaults() {
+        let args = Args::try_parse_from(["verifier"]).unwrap();
+        assert_eq!(args.addr, "127.0.0.1:41337");
+        assert_eq!(args.uds, None);
+        assert_eq!(args.group, Group::Modp1024);
+        assert_eq!(args.tls_cert, None);
+        assert_eq!(args.tls_key, None);
+        assert_eq!(args.admin_token, None);
+        assert!(!args.closed_enrollment);
+        assert!(!args.allow_unregistered);
+        assert_eq!(args.metrics_addr, None);
+        assert_eq!(args.challenge_bits, None);
+        assert!(!args.integrity_checks);
+        assert_eq!(args.audit_log, None);
+        assert!(!args.self_test);
     }
 
-    async fn verify_authentication(
-        &self,
-        request: Request<VerifyAuthenticationRequest>,
-    ) -> Result<Response<VerifyAuthenticationResponse>, Status> {
-        let request = request.into_inner();
-        println!("Processing Challenge Solution for auth_id: {:?}", request.auth_id);
-
-        let auth_map = self.auth_id_to_user.lock().unwrap();
-        match auth_map.get(&request.auth_id) {
-            Some(user_name) => {
-                let mut user_info_map = self.user_info.lock().unwrap();
-                let user_info = user_info_map
-                    .get_mut(user_name)
-                    .ok_or_else(|| {
-                        Status::new(
-                            Code::NotFound,
-                            format!("AuthId '{}' not found", request.auth_id),
-                        )
-                    })?;
-
-                user_info.s = BigUint::from_bytes_be(&request.s);
-
-                let (alpha, beta, p, q) = ZKP::get_constants();
-                let zkp = ZKP { alpha, beta, p, q };
-                let verified = zkp.verify(
-                    &user_info.r1,
-                    &user_info.r2,
-                    &user_info.y1,
-                    &user_info.y2,
-                    &user_info.c,
-                    &user_info.s,
-                );
-
-                if verified {
-                    let session_id = ZKP::generate_random_string(12);
-                    println!("Solution correct for user: {:?}", user_name);
-
-                    Ok(Response::new(VerifyAuthenticationResponse { session_id }))
-                } else {
-                    println!("Solution incorrect for user: {:?}", user_name);
-                    Err(Status::new(
-                        Code::PermissionDenied,
-                        format!("AuthId '{}' has an incorrect challenge solution", request.auth_id),
-                    ))
-                }
-            }
-            None => Err(Status::new(
-                Code::NotFound,
-                format!("AuthId '{}' not found", request.auth_id),
-            )),
-        }
-    }
-}
+    #[test]
+    fn test_args_overrides() {
+        let args =
+            Args::try_parse_from(["verifier", "--addr", "0.0.0.0:50051", "--group", "2048"]).unwrap();
+        assert_eq!(args.addr, "0.0.0.0:50051");
+        assert_eq!(args.group, Group::Modp2048);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use auth::{
-        auth_client::AuthClient,
-        CreateAuthenticationChallengeRequest, RegisterRequest, VerifyAuthenticationRequest,
-    };
-    use chaum_pedersen::ZKP;
-    use num_bigint::{BigUint, RandBigInt};
-    use rand::thread_rng;
-    use std::net::TcpListener;
-    use tokio::time::{sleep, Duration};
-    use tonic::transport::Server;
-    use tonic::Request;
-
-    /// Spawn the gRPC server on a random free port and return (full_uri, JoinHandle).
-    async fn spawn_server() -> (String, tokio::task::JoinHandle<()>) {
-        // 1) Bind a standard TcpListener to an ephemeral port
-        let std_listener = TcpListener::bind("127.0.0.1:0").unwrap();
-        let local_addr = std_listener.local_addr().unwrap(); // e.g. 127.0.0.1:54321
-        let full_uri = format!("http://{}", local_addr);
-
-        // 2) Convert to AuthImpl
-        let auth_impl = AuthImpl::default();
-
-        // 3) Spawn the server in the background
-        let handle = tokio::spawn(async move {
-            // We don’t need serve_with_incoming — just serve the `local_addr`
-            // The server will keep running until the test finishes and drops the JoinHandle
-            Server::builder()
-                .add_service(AuthServer::new(auth_impl))
-                .serve(local_addr)
-                .await
-                .expect("server failed");
-        });
+        assert!(Args::try_parse_from(["verifier", "--group", "512"]).is_err());
 
-        // 4) Return the address + the join handle
-        (full_uri, handle)
+        assert!(Args::try_parse_from(["verifier", "--closed-enrollment"]).is_err());
+        let args =
+            Args::try_parse_from(["verifier", "--closed-enrollment", "--admin-token", "t"]).unwrap();
+        assert!(args.closed_enrollment);
     }
 
-    #[tokio::test]
-    async fn test_end_to_end_auth_flow() -> Result<(), Box<dyn std::error::Error>> {
-        // --------------------------------------------------
-        // 1) Spawn our gRPC server on a random port
-        // --------------------------------------------------
-        let (server_addr, _join_handle) = spawn_server().await;
-
-        // Give the server a moment to actually start listening
-        sleep(Duration::from_millis(100)).await;
-
-        // --------------------------------------------------
-        // 2) Create a client for our Auth service
-        // --------------------------------------------------
-        let mut client = AuthClient::connect(server_addr).await?;
-
-        // For convenience, create a ZKP struct
-        let (alpha, beta, p, q) = ZKP::get_constants();
-        let zkp = ZKP { alpha, beta, p, q };
-
-        // --------------------------------------------------
-        // 3) REGISTER the user
-        // --------------------------------------------------
-        let x = thread_rng().gen_biguint_below(&zkp.q); // user’s secret
-        let y1 = zkp.alpha.modpow(&x, &zkp.p);
-        let y2 = zkp.beta.modpow(&x, &zkp.p);
-
-        let user_name = "alice".to_string();
-        let register_request = RegisterRequest {
-            user: user_name.clone(),
-            y1: y1.to_bytes_be(),
-            y2: y2.to_bytes_be(),
-        };
-        client.register(Request::new(register_request)).await?;
-        println!("--- Registered user: {} ---", user_name);
-
-        // --------------------------------------------------
-        // 4) CREATE AUTHENTICATION CHALLENGE
-        // --------------------------------------------------
-        let k = thread_rng().gen_biguint_below(&zkp.q); // ephemeral
-        let (r1, r2) = zkp.compute_pair(&k);
-
-        let challenge_req = CreateAuthenticationChallengeRequest {
-            user: user_name.clone(),
-            r1: r1.to_bytes_be(),
-            r2: r2.to_bytes_be(),
-        };
-        let challenge_resp = client
-            .create_authentication_challenge(Request::new(challenge_req))
-            .await?
-            .into_inner();
-
-        let auth_id = challenge_resp.auth_id;
-        let c = BigUint::from_bytes_be(&challenge_resp.c);
-        println!("--- Created challenge for user: {} / auth_id: {} ---", user_name, auth_id);
-
-        // --------------------------------------------------
-        // 5) SOLVE AND VERIFY AUTHENTICATION
-        // --------------------------------------------------
-        // Solve: s = k - c*x (mod q)
-        let s = zkp.solve(&k, &c, &x);
-
-        let verify_req = VerifyAuthenticationRequest {
-            auth_id: auth_id.clone(),
-            s: s.to_bytes_be(),
-        };
-        let verify_resp = client.verify_authentication(Request::new(verify_req)).await;
-
-        match verify_resp {
-            Ok(resp) => {
-                let session_id = resp.into_inner().session_id;
-                assert!(!session_id.is_empty(), "Session ID should not be empty");
-                println!("--- Auth succeeded! Session ID: {} ---", session_id);
-            }
-            Err(e) => panic!("Auth verification failed with error: {:?}", e),
+    #[test]
+    fn test_args_uds_excludes_tcp_and_tls() {
+        let args = Args::try_parse_from(["verifier", "--uds", "/tmp/verifier.sock"]).unwrap();
+        assert_eq!(args.uds, Some(PathBuf::from("/tmp/verifier.sock")));
+        assert!(
+            Args::try_parse_from(["verifier", "--uds", "v.sock", "--addr", "0.0.0.0:50051"])
+                .is_err()
+        );
+        assert!(Args::try_parse_from([
+            "verifier",
+            "--uds",
+            "v.sock",
+            "--tls-cert",
+            "cert.pem",
+            "--tls-key",
+            "key.pem",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_self_test_passes_for_each_group_and_fails_for_broken_params() {
+        for group in [Group::Modp1024, Group::Modp2048] {
+            assert_eq!(self_test(&group.zkp()), Ok(()));
         }
 
-        // Test completes and drops the JoinHandle — the server stops
-        Ok(())
+        // q no longer the order of alpha and beta, so no public key is in the subgroup
+        let mut zkp = ZKP::new();
+        zkp.q += 2u32;
+        let e = self_test(&zkp).unwrap_err();
+        assert!(e.starts_with("register: "), "{}", e);
+    }
+
+    #[test]
+    fn test_args_challenge_bits_must_be_at_least_64() {
+        assert!(Args::try_parse_from(["verifier", "--challenge-bits", "128"]).is_ok());
+        assert!(Args::try_parse_from(["verifier", "--challenge-bits", "16"]).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_args_admin_token_must_not_be_empty() {
+        assert!(Args::try_parse_from(["verifier", "--admin-token", "let-me-in"]).is_ok());
+        assert!(Args::try_parse_from(["verifier", "--admin-token", ""]).is_err());
+    }
+}
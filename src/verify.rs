@@ -0,0 +1,165 @@
+//! Checks a recorded transcript offline, e.g. in a CI job. Exits 0 when the proof verifies
+//! and 1 with a message on stderr when it does not or cannot be read.
+
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{Parser, ValueEnum};
+use zkp::{GroupElement, GroupSize, RangeError, Scalar, Transcript, ZKP};
+
+#[derive(Debug, Parser)]
+#[command(name = "verify", about = "Check a Chaum-Pedersen transcript given as JSON")]
+struct Args {
+    /// JSON file with hex fields r1, r2, y1, y2, c and s; read from stdin when omitted
+    /// or `-`
+    transcript: Option<PathBuf>,
+
+    /// Group the proof was computed in
+    #[arg(long, value_enum, default_value_t = Group::Modp1024)]
+    group: Group,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Group {
+    /// RFC 5114 1024-bit MODP group with a 160-bit subgroup
+    #[value(name = "1024")]
+    Modp1024,
+    /// RFC 3526 2048-bit MODP group
+    #[value(name = "2048")]
+    Modp2048,
+    /// RFC 3526 3072-bit MODP group
+    #[value(name = "3072")]
+    Modp3072,
+}
+
+impl Group {
+    fn zkp(self) -> ZKP {
+        ZKP::for_group(match self {
+            Group::Modp1024 => GroupSize::Modp1024,
+            Group::Modp2048 => GroupSize::Modp2048,
+            Group::Modp3072 => GroupSize::Modp3072,
+        })
+    }
+}
+
+/// Why a transcript was not accepted.
+#[derive(Debug)]
+enum Rejection {
+    Read(io::Error),
+    Parse(String),
+    /// The named field is not a scalar or group element of the chosen group.
+    OutOfRange(&'static str, RangeError),
+    /// The fields are well-formed but the proof does not verify.
+    Invalid,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::Read(e) => write!(f, "failed to read the transcript: {}", e),
+            Rejection::Parse(e) => write!(f, "transcript is not valid JSON: {}", e),
+            Rejection::OutOfRange(field, e) => write!(f, "'{}': {}", field, e),
+            Rejection::Invalid => write!(f, "the transcript does not verify"),
+        }
+    }
+}
+
+/// Reads a JSON `Transcript` from `input` and checks it with `ZKP::verify`.
+fn verify(zkp: &ZKP, mut input: impl Read) -> Result<(), Rejection> {
+    let mut json = String::new();
+    input.read_to_string(&mut json).map_err(Rejection::Read)?;
+    let t = Transcript::from_json(&json).map_err(|e| Rejection::Parse(e.to_string()))?;
+
+    let element = |field, value| {
+        GroupElement::new(zkp, value).map_err(|e| Rejection::OutOfRange(field, e))
+    };
+    let scalar =
+        |field, value| Scalar::new(zkp, value).map_err(|e| Rejection::OutOfRange(field, e));
+    let (r1, r2) = (element("r1", t.r1)?, element("r2", t.r2)?);
+    let (y1, y2) = (element("y1", t.y1)?, element("y2", t.y2)?);
+    let (c, s) = (scalar("c", t.c)?, scalar("s", t.s)?);
+
+    if !zkp.verify(&r1, &r2, &y1, &y2, &c, &s) {
+        return Err(Rejection::Invalid);
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let zkp = args.group.zkp();
+
+    let result = match args.transcript {
+        Some(path) if path.as_os_str() != "-" => match fs::File::open(&path) {
+            Ok(file) => verify(&zkp, file),
+            Err(e) => Err(Rejection::Read(e)),
+        },
+        _ => verify(&zkp, io::stdin().lock()),
+    };
+    match result {
+        Ok(()) => {
+            println!("ok: the transcript verifies");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `transcript` to a file in the temp directory, returning its path.
+    fn write_transcript(name: &str, transcript: &Transcript) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.json", name, std::process::id()));
+        fs::write(&path, transcript.to_json().unwrap()).unwrap();
+        path
+    }
+
+    fn honest_transcript(zkp: &ZKP) -> Transcript {
+        let x = zkp.generate_random_scalar();
+        let k = zkp.generate_random_scalar();
+        let c = zkp.generate_random_scalar();
+        let (y1, y2) = zkp.public_keys(&x);
+        let proof = zkp.prove(&x, &k, &c);
+        Transcript {
+            r1: proof.r1,
+            r2: proof.r2,
+            y1,
+            y2,
+            c: proof.c,
+            s: proof.s,
+        }
+    }
+
+    #[test]
+    fn verify_accepts_a_good_transcript_and_rejects_a_bad_one() {
+        let zkp = ZKP::new();
+        let good = honest_transcript(&zkp);
+        let mut bad = good.clone();
+        bad.s = (&bad.s + 1u32) % &zkp.q;
+
+        let good_path = write_transcript("good-transcript", &good);
+        let bad_path = write_transcript("bad-transcript", &bad);
+        assert!(verify(&zkp, fs::File::open(&good_path).unwrap()).is_ok());
+        assert!(matches!(
+            verify(&zkp, fs::File::open(&bad_path).unwrap()),
+            Err(Rejection::Invalid)
+        ));
+        fs::remove_file(good_path).unwrap();
+        fs::remove_file(bad_path).unwrap();
+
+        let mut out_of_range = good;
+        out_of_range.y1 = zkp.p.clone();
+        let err = verify(&zkp, out_of_range.to_json().unwrap().as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "'y1': group element is not in [1, p)");
+        assert!(matches!(verify(&zkp, &b"{}"[..]), Err(Rejection::Parse(_))));
+    }
+}
@@ -0,0 +1,43 @@
+//! The Chaum-Pedersen proof in one dependency: the proof types of `chaum_pedersen` under
+//! a single namespace, for applications that want the library without the gRPC verifier.
+//!
+//! The groups, proofs and transcripts are at the top level; the service, encodings and
+//! everything else stay one module down, as in `chaum_pedersen`. The features are passed
+//! through, so `default-features = false` gives the same `no_std` + `alloc` build.
+//!
+//! ```
+//! use zkp::{BigUint, Transcript, ZKP};
+//!
+//! let zkp = ZKP::new();
+//! let x = BigUint::from(123456789u32);
+//!
+//! // The prover commits with a nonce k, the verifier picks a challenge c
+//! let k = zkp.generate_nonzero_scalar();
+//! let c = zkp.generate_random_scalar();
+//! let proof = zkp.prove(&x, &k, &c);
+//!
+//! let (y1, y2) = zkp.public_keys(&x);
+//! let transcript = Transcript {
+//!     r1: proof.r1,
+//!     r2: proof.r2,
+//!     y1,
+//!     y2,
+//!     c: proof.c,
+//!     s: proof.s,
+//! };
+//! assert!(zkp.verify_transcript(&transcript));
+//! ```
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use chaum_pedersen::{
+    cross::CrossGroupZkp, GroupElement, GroupParams, GroupSize, ParamError, Proof,
+    ProofParseError, RangeError, RegistrationBundle, Scalar, Transcript, ZkpBuilder, ZkpGroup,
+    ZKP,
+};
+#[cfg(feature = "ec")]
+pub use chaum_pedersen::ec::EcZkp;
+pub use num_bigint::BigUint;
+
+#[cfg(feature = "std")]
+pub use chaum_pedersen::{audit, service};
+pub use chaum_pedersen::{arith, challenge, encoding, modexp, secret, types};